@@ -1,6 +1,8 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 pub mod currency;
+pub mod random;
 pub mod string;
 
 pub use currency::*;
+pub use random::*;
 pub use string::*;
\ No newline at end of file
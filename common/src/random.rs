@@ -0,0 +1,20 @@
+/// On-chain randomness shared by the game pallets.
+///
+/// Every call returns a fresh value, so two rolls in the same block differ.
+pub trait RandomSource<Output: AsRef<[u8]>> {
+    /// A random value for `subject`
+    fn random(subject: &[u8]) -> Output;
+
+    /// A random number in `[min, max)`, `min` if the range is empty
+    fn random_range(subject: &[u8], min: u32, max: u32) -> u32 {
+        if max <= min {
+            return min;
+        }
+        let random = Self::random(subject);
+        let mut bytes = [0u8; 4];
+        for (b, r) in bytes.iter_mut().zip(random.as_ref()) {
+            *b = *r;
+        }
+        min + u32::from_le_bytes(bytes) % (max - min)
+    }
+}
@@ -17,6 +17,7 @@ use subgame_runtime::{
 	SessionConfig, StakingConfig, ImOnlineConfig,
     SystemConfig, WASM_BINARY,
 	IndicesConfig, CouncilConfig, TechnicalCommitteeConfig,
+    UsernameConfig,
 };
 
 fn session_keys(
@@ -568,6 +569,11 @@ fn testnet_genesis(
             },
         }),
         /*** Pallet Contracts ***/
+        /*** Pallet Username ***/
+        pallet_username: Some(UsernameConfig {
+            reserved_names: vec![b"gametop".to_vec()],
+        }),
+        /*** Pallet Username ***/
     }
 }
 
@@ -649,5 +655,10 @@ fn mainnet_genesis(
             },
         }),
         /*** Pallet Contracts ***/
+        /*** Pallet Username ***/
+        pallet_username: Some(UsernameConfig {
+            reserved_names: vec![b"gametop".to_vec()],
+        }),
+        /*** Pallet Username ***/
     }
 }
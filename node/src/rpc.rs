@@ -8,6 +8,9 @@
 use std::sync::Arc;
 
 use pallet_contracts_rpc::{Contracts, ContractsApi};
+use pallet_gamecenter_rpc::{GameCenter, GameCenterApi};
+use pallet_swap_rpc::{Swap, SwapApi};
+use pallet_stake_rpc::{Stake, StakeApi};
 pub use sc_rpc_api::DenyUnsafe;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
@@ -40,6 +43,15 @@ where
     /*** Pallet Contracts ***/
     C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
     /*** Pallet Contracts ***/
+    /*** Pallet GameCenter ***/
+    C::Api: pallet_gamecenter_rpc::GameCenterRuntimeApi<Block, AccountId, BlockNumber>,
+    /*** Pallet GameCenter ***/
+    /*** Pallet Swap ***/
+    C::Api: pallet_swap_rpc::SwapRuntimeApi<Block, AccountId, u32, u32, BlockNumber>,
+    /*** Pallet Swap ***/
+    /*** Pallet Stake ***/
+    C::Api: pallet_stake_rpc::StakeRuntimeApi<Block, AccountId, Balance>,
+    /*** Pallet Stake ***/
 {
     use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
     use substrate_frame_rpc_system::{FullSystem, SystemApi};
@@ -70,5 +82,17 @@ where
     io.extend_with(ContractsApi::to_delegate(Contracts::new(client.clone())));
     /*** Pallet Contracts ***/
 
+    /*** Pallet GameCenter ***/
+    io.extend_with(GameCenterApi::to_delegate(GameCenter::new(client.clone())));
+    /*** Pallet GameCenter ***/
+
+    /*** Pallet Swap ***/
+    io.extend_with(SwapApi::to_delegate(Swap::new(client.clone())));
+    /*** Pallet Swap ***/
+
+    /*** Pallet Stake ***/
+    io.extend_with(StakeApi::to_delegate(Stake::new(client.clone())));
+    /*** Pallet Stake ***/
+
     io
 }
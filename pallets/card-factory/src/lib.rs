@@ -8,7 +8,6 @@ use default_weight::WeightInfo;
 use frame_system::ensure_signed;
 use sp_std::{prelude::*};
 use sp_runtime::RuntimeDebug;
-// use frame_support::traits::Randomness;
 
 use codec::{Encode, Decode};
 use frame_support::{decl_module, decl_event, decl_storage, decl_error, ensure,
@@ -23,8 +22,7 @@ use pallet_lease::Lease;
 
 use pallet_manage_card_info::ManageCardInfo;
 
-use sp_runtime::traits::{Hash, BlakeTwo256};
-use sp_runtime::RandomNumberGenerator;
+use common::RandomSource;
 
 use pallet_nft::UniqueAssets;
 
@@ -37,7 +35,8 @@ pub type PalletId<T> =
 pub trait Config: frame_system::Config {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
-	// type MyRandomness: Randomness<Self::Hash>;
+	/// Randomness for the card abilities
+	type Randomness: RandomSource<Self::Hash>;
 
     type UniqueAssets: UniqueAssets<Self::AccountId>;
     type Lease: Lease<Self::AccountId, NftId<Self>>;
@@ -199,36 +198,20 @@ impl<T: Config> CardFactory<T::AccountId, NftId<T>> for Module<T> {
 		let ability_min = ability_of_level.ability_value_1_min;
 			
 		
-		// let a = T::MyRandomness::random(&(T::PalletId::get(), 100u32).encode());
-		// let random_seed = sp_io::offchain::random_seed();
-		
 		for _n in 1..=quantity {
 			let nft_id = T::UniqueAssets::mint(&admin, Vec::new())?;
 			let id = Self::next_card_id();
-			let ability_value_1: &u32;
-			let mut range = Vec::new();
-			// let mut rng = rand::thread_rng();
-			// let die = Uniform::from(ability_min..ability_max);
-			// let ability_value_1 = die.sample(&mut rng);
-			if ability_max <= ability_min {
-				ability_value_1 = &ability_max;
+			let ability_value_1 = if ability_max <= ability_min {
+				ability_max
 			}else{
-				let random_seed = BlakeTwo256::hash(&(id).encode());
-				let mut rng = RandomNumberGenerator::<BlakeTwo256>::new(random_seed);
-				for n in ability_min..ability_max{
-					range.push(n);
-				}
-				let ability_value_2 = rng.pick_item(&range).unwrap();
-				ability_value_1  =  ability_value_2;
-			}
+				T::Randomness::random_range(&(b"card_factory", id).encode(), ability_min, ability_max)
+			};
 
-			
-			
 			Cards::<T>::insert(id, Card {
 				id: id,
 				card_info_id: card_info_id,
 				level: level,
-				ability_value_1: *ability_value_1,
+				ability_value_1: ability_value_1,
 				nft_id: nft_id.clone(),
 			});
 
@@ -241,7 +224,7 @@ impl<T: Config> CardFactory<T::AccountId, NftId<T>> for Module<T> {
 				id,
 				card_info_id,
 				level,
-				*ability_value_1,
+				ability_value_1,
 				nft_id.clone(),
 			));
 		}
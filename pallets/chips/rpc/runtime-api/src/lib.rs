@@ -0,0 +1,16 @@
+//! Runtime API definition for the Chips pallet, lets auditors check the chips collateral
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+pub use pallet_chips::{AssetSolvency, Solvency};
+
+sp_api::decl_runtime_apis! {
+    pub trait ChipsApi<ChipBalance> where
+        ChipBalance: Codec,
+    {
+        /// Reserved plus free chips of all accounts against the collateral in the chips treasury
+        fn solvency() -> Solvency<ChipBalance>;
+    }
+}
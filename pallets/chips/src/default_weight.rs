@@ -7,12 +7,15 @@ use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
 impl crate::WeightInfo for () {
     fn buy_chips() -> Weight {
         (500_000_000 as Weight)
-            .saturating_add(DbWeight::get().reads(1 as Weight))
-            .saturating_add(DbWeight::get().writes(1 as Weight))
+            .saturating_add(DbWeight::get().reads(6 as Weight))
+            .saturating_add(DbWeight::get().writes(7 as Weight))
     }
     fn redemption() -> Weight {
         (500_000_000 as Weight)
-            .saturating_add(DbWeight::get().reads(1 as Weight))
-            .saturating_add(DbWeight::get().writes(1 as Weight))
+            .saturating_add(DbWeight::get().reads(6 as Weight))
+            .saturating_add(DbWeight::get().writes(7 as Weight))
+    }
+    fn set_exchange_rate() -> Weight {
+        (100_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
     }
 }
@@ -5,14 +5,18 @@
 use codec::{Decode, Encode};
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
-    traits::{Currency, ExistenceRequirement, Get},
+    storage::{IterableStorageDoubleMap, IterableStorageMap},
+    traits::{Currency, EnsureOrigin, ExistenceRequirement, Get},
     weights::Weight,
     Parameter,
 };
 use frame_system::ensure_signed;
+use sp_std::{convert::TryFrom, vec::Vec};
+use pallet_subgame_assets::{AssetsTrait, AssetsTransfer};
 use sp_runtime::{
-    traits::{AtLeast32BitUnsigned, Member, CheckedAdd, CheckedSub},
-    DispatchResult, SaturatedConversion,
+    helpers_128bit::multiply_by_rational,
+    traits::{AccountIdConversion, AtLeast32BitUnsigned, Member, CheckedAdd, CheckedSub, One, Saturating, Zero},
+    DispatchResult, FixedPointNumber, FixedU128, ModuleId, RuntimeDebug, SaturatedConversion,
 };
 
 #[cfg(test)]
@@ -31,25 +35,161 @@ pub struct ChipsDetail<Balance, Reserve> {
     /// Record the amount of pledge deposits
     pub reserve: Reserve,
 }
+/// Chips backed by one asset and the asset units held by the treasury for them
+#[derive(Encode, Decode, Default, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct Backing<ChipBalance> {
+    pub chips: ChipBalance,
+    /// Asset units paid for the chips
+    pub tokens: u128,
+}
+
+impl<ChipBalance: AtLeast32BitUnsigned + Copy> Backing<ChipBalance> {
+    /// Asset units backing part of the chips, pro rata and rounded down
+    pub fn tokens_for(&self, chips: ChipBalance) -> u128 {
+        if chips >= self.chips {
+            return self.tokens;
+        }
+        multiply_by_rational(
+            self.tokens,
+            chips.saturated_into::<u128>(),
+            self.chips.saturated_into::<u128>(),
+        )
+        .unwrap_or(0)
+    }
+}
+
+/// Collateral of the chips backed by one asset
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct AssetSolvency<ChipBalance> {
+    /// Backing asset id
+    pub asset_id: AssetId,
+    /// Chips bought with the asset and not redeemed yet
+    pub chips: ChipBalance,
+    /// Asset units paid for those chips, redeeming all of them pays this much
+    pub owed: u128,
+    /// Asset units held by the chips treasury
+    pub held: u128,
+}
+
+/// Whether the chips in circulation are fully collateralised
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Solvency<ChipBalance> {
+    /// Free chips of all accounts
+    pub free: ChipBalance,
+    /// Reserved chips of all accounts
+    pub reserved: ChipBalance,
+    /// Chips backed by collateral, over all assets
+    pub collateral: ChipBalance,
+    /// Collateral per backing asset
+    pub assets: Vec<AssetSolvency<ChipBalance>>,
+}
+
+impl<ChipBalance: AtLeast32BitUnsigned + Copy> Solvency<ChipBalance> {
+    /// Reserved plus free chips equal the collateral, and the treasury holds enough of every asset
+    pub fn is_solvent(&self) -> bool {
+        self.free.saturating_add(self.reserved) == self.collateral
+            && self.assets.iter().all(|asset| asset.held >= asset.owed)
+    }
+}
+
+/// Why chips moved, attached to the chips events and the ledger
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum ChipsReason {
+    /// Bought or redeemed with the backing asset
+    Exchange(AssetId),
+    /// Played in a game [template id, game instance id]
+    Game(u32, u32),
+    /// Moved for another reason
+    Other,
+}
+
+impl Default for ChipsReason {
+    fn default() -> Self {
+        ChipsReason::Other
+    }
+}
+
+/// The way the chips of an account changed
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum LedgerKind {
+    /// Free chips bought
+    Buy,
+    /// Free chips redeemed
+    Redemption,
+    /// Free chips moved to reserve
+    Reserve,
+    /// Reserved chips moved back to free
+    Unreserve,
+    /// Reserved chips sent to another account
+    RepatriateOut,
+    /// Free chips received from the reserve of another account
+    RepatriateIn,
+}
+
+impl Default for LedgerKind {
+    fn default() -> Self {
+        LedgerKind::Buy
+    }
+}
+
+/// One change of the chips of an account
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct LedgerEntry<BlockNumber, ChipBalance> {
+    /// Block the chips changed in
+    pub block_number: BlockNumber,
+    pub kind: LedgerKind,
+    pub amount: ChipBalance,
+    pub reason: ChipsReason,
+}
+
+pub type LedgerEntryOf<T> =
+    LedgerEntry<<T as frame_system::Config>::BlockNumber, <T as Config>::ChipBalance>;
+
+/// The most entries returned by one ledger page
+pub const MAX_PAGE_SIZE: u32 = 100;
+
 pub trait WeightInfo {
     fn buy_chips() -> Weight;
     fn redemption() -> Weight;
+    fn set_exchange_rate() -> Weight;
 }
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type Balances: Currency<Self::AccountId>;
     type ChipBalance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy;
-    /// The address where funds are temporarily deposited
-    type MasterAddress: Get<Self::AccountId>;
+    /// The chips treasury account is derived from it, all collateral is held there
+    type ModuleId: Get<ModuleId>;
     type WeightInfo: WeightInfo;
+    /// The assets that can back chips besides the native currency
+    type Assets: AssetsTrait + AssetsTransfer<Self::AccountId, AssetId>;
+    /// The origin that can set the exchange rates
+    type RateOrigin: EnsureOrigin<Self::Origin>;
+    /// The number of ledger entries kept for each account
+    type MaxLedgerEntries: Get<u32>;
 }
 
+/// Asset id of a backing asset, as in `pallet_subgame_assets`
+pub type AssetId = u32;
+
+/// The native currency, the same id `pallet_swap` uses for it
+pub const NATIVE_ASSET_ID: AssetId = 0;
+
 pub type BalanceOf<T> =
     <<T as Config>::Balances as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 decl_storage! {
     trait Store for Module<T: Config> as Chips {
         /// Record the chips information of each user
         pub ChipsMap get(fn chips_map): map hasher(blake2_128_concat)  T::AccountId => Option<ChipsDetail<T::ChipBalance, T::ChipBalance>>;
+        /// Backing asset units paid for one chip, the native currency is 1:1 until a rate is set
+        pub ExchangeRates get(fn exchange_rates): map hasher(twox_64_concat) AssetId => Option<FixedU128>;
+        /// Chips backed by each asset over all accounts
+        pub Collateral get(fn collateral): map hasher(twox_64_concat) AssetId => Backing<T::ChipBalance>;
+        /// Chips of each account by backing asset, they can only be redeemed for that asset
+        pub AccountCollateral get(fn account_collateral): double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) AssetId => Backing<T::ChipBalance>;
+        /// Number of ledger entries ever recorded for each account
+        pub LedgerCount get(fn ledger_count): map hasher(blake2_128_concat) T::AccountId => u32;
+        /// Chips changes of each account by sequence number, only the latest `MaxLedgerEntries` are kept
+        pub ChipsLedger get(fn ledger_entry): double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) u32 => Option<LedgerEntryOf<T>>;
     }
 }
 
@@ -59,16 +199,18 @@ decl_event!(
         AccountId = <T as frame_system::Config>::AccountId,
         ChipBalance = <T as Config>::ChipBalance,
     {
-        /// Buy chips event
-        BuyChips(AccountId, ChipBalance),
-        /// Redemption amount with chips event
-        Redemption(AccountId, ChipBalance),
+        /// Buy chips event [who, asset id, chips, asset amount paid]
+        BuyChips(AccountId, AssetId, ChipBalance, u128),
+        /// Redemption amount with chips event [who, asset id, chips, asset amount received]
+        Redemption(AccountId, AssetId, ChipBalance, u128),
+        /// Exchange rate of a backing asset is set, `None` stops the asset from backing chips
+        ExchangeRateSet(AssetId, Option<FixedU128>),
         /// Pledge chips
-        Reserve(AccountId, ChipBalance),
+        Reserve(AccountId, ChipBalance, ChipsReason),
         /// Cancel pledge chips
-        Unreserve(AccountId, ChipBalance),
+        Unreserve(AccountId, ChipBalance, ChipsReason),
         /// Transfer the chips in the pledge to others
-        RepatriateReserved(AccountId, AccountId, ChipBalance),
+        RepatriateReserved(AccountId, AccountId, ChipBalance, ChipsReason),
     }
 );
 
@@ -77,7 +219,13 @@ decl_error! {
         StorageOverflow,
         MoneyNotEnough,
         ChipsIsNotEnough,
-        NeverBoughtChips
+        NeverBoughtChips,
+        /// The asset has no exchange rate
+        AssetNotSupported,
+        /// The exchange rate must be above zero
+        ZeroExchangeRate,
+        /// Not enough chips were bought with the asset
+        CollateralNotEnough,
     }
 }
 
@@ -90,97 +238,385 @@ decl_module! {
         #[weight = T::WeightInfo::buy_chips()]
         pub fn buy_chips(origin, amount: T::ChipBalance) -> dispatch::DispatchResult {
             let _who = ensure_signed(origin)?;
-            let master_address = T::MasterAddress::get();	// Receiving account
-            let chips_map =  Self::chips_map(&_who);	// Get user chip information
-
-            // The receiving account is forbidden to purchase and redeem to avoid errors
-            ensure!(_who != master_address, Error::<T>::ChipsIsNotEnough);
-
-            // payment
-            // [Exchange] chips exchange for SGP
-            let origin_amount = Self::exchange_chip_to_token(amount);
-            T::Balances::transfer(&_who, &master_address, origin_amount, ExistenceRequirement::KeepAlive).map_err(|_| Error::<T>::MoneyNotEnough)?;
-
-
-
-            // Redeem chips for the first time
-            if chips_map.is_none() {
-                let c = ChipsDetail{
-                    balance: amount,
-                    reserve: 0_u128.saturated_into(),
-                };
-                <ChipsMap<T>>::insert(&_who, c);
-            }else{
-                // Return the Some value contained in it
-                let c = chips_map.unwrap();
-                let new_balance = c.balance.checked_add(&amount);
-                ensure!(new_balance != None, Error::<T>::StorageOverflow);
-                
-                let new_reserve = c.reserve;
-
-                let new_chips = ChipsDetail{
-                    balance: new_balance.unwrap(),
-                    reserve: new_reserve,
-                };
-                <ChipsMap<T>>::mutate(&_who, |chips_detail| *chips_detail = Some(new_chips));
-            }
-
-            // Send event notification
-            Self::deposit_event(RawEvent::BuyChips(_who, amount));
-            Ok(())
+            Self::_buy_chips(_who, NATIVE_ASSET_ID, amount)
         }
 
         /// You can use your chips to redemption SGP
         #[weight = T::WeightInfo::redemption()]
         pub fn redemption(origin, amount: T::ChipBalance) -> dispatch::DispatchResult {
             let _who = ensure_signed(origin)?;
-            let master_address = T::MasterAddress::get();	// Receiving account
-            // Get the balance of the chips, if you have not bought the chips, return an error
-            let mut chips_map = Self::chips_map(&_who).ok_or( Error::<T>::NeverBoughtChips)?;
-
-            // Need to have enough chips
-            ensure!(chips_map.balance >= amount, Error::<T>::ChipsIsNotEnough);
-
-
-            // Update chips
-            let new_balance = chips_map.balance.checked_sub(&amount);
-            ensure!(new_balance != None, Error::<T>::StorageOverflow);
-            chips_map.balance = new_balance.unwrap();
-            <ChipsMap<T>>::mutate(&_who, |chips_detail| *chips_detail = Some(chips_map));
-
-            // Ransom refund
-            // 【Exchange】Use chips to exchange SGP
-            let origin_amount = Self::exchange_chip_to_token(amount);
-            T::Balances::transfer(&master_address, &_who, origin_amount, ExistenceRequirement::KeepAlive).map_err(|_| Error::<T>::MoneyNotEnough )?;
-            // Send event notification
-            Self::deposit_event(RawEvent::Redemption(_who, amount));
-            Ok(())
+            Self::_redemption(_who, NATIVE_ASSET_ID, amount)
+        }
+
+        /// Buy chips with a backing asset at its exchange rate
+        #[weight = T::WeightInfo::buy_chips()]
+        pub fn buy_chips_with_asset(origin, asset_id: AssetId, amount: T::ChipBalance) -> dispatch::DispatchResult {
+            let _who = ensure_signed(origin)?;
+            Self::_buy_chips(_who, asset_id, amount)
         }
 
+        /// Redeem chips for a backing asset at its exchange rate
+        #[weight = T::WeightInfo::redemption()]
+        pub fn redemption_to_asset(origin, asset_id: AssetId, amount: T::ChipBalance) -> dispatch::DispatchResult {
+            let _who = ensure_signed(origin)?;
+            Self::_redemption(_who, asset_id, amount)
+        }
+
+        /// Set how many asset units one chip costs, `None` stops the asset from backing chips
+        #[weight = T::WeightInfo::set_exchange_rate()]
+        pub fn set_exchange_rate(origin, asset_id: AssetId, rate: Option<FixedU128>) -> dispatch::DispatchResult {
+            T::RateOrigin::ensure_origin(origin)?;
+            match rate {
+                Some(rate) => {
+                    ensure!(rate.into_inner() > 0, Error::<T>::ZeroExchangeRate);
+                    ExchangeRates::insert(asset_id, rate);
+                }
+                None => ExchangeRates::remove(asset_id),
+            }
+            Self::deposit_event(RawEvent::ExchangeRateSet(asset_id, rate));
+            Ok(())
+        }
     }
 }
 
 impl<T: Config> Module<T> {
-    /// chips to SGP
-    fn exchange_chip_to_token(balance: T::ChipBalance) -> BalanceOf<T> {
-        let u128_b = SaturatedConversion::saturated_into::<u128>(balance);
-        let chip_balance: BalanceOf<T> = u128_b.saturated_into();
-
-        // exchange rate
-        let exchange = 1_u128.saturated_into();
-        chip_balance * exchange
+    /// The chips treasury, it holds the collateral of all chips
+    pub fn account_id() -> T::AccountId {
+        T::ModuleId::get().into_account()
+    }
+
+    /// Asset units of the backing asset held by the chips treasury
+    fn treasury_balance(asset_id: AssetId) -> u128 {
+        if asset_id == NATIVE_ASSET_ID {
+            T::Balances::free_balance(&Self::account_id()).saturated_into()
+        } else {
+            T::Assets::asset_balance(asset_id, Self::account_id()).into()
+        }
+    }
+
+    /// Compare the chips of all accounts with the collateral, for auditors
+    pub fn solvency() -> Solvency<T::ChipBalance> {
+        let mut solvency = Solvency::<T::ChipBalance>::default();
+        for (_, chips) in ChipsMap::<T>::iter() {
+            solvency.free = solvency.free.saturating_add(chips.balance);
+            solvency.reserved = solvency.reserved.saturating_add(chips.reserve);
+        }
+        for (asset_id, backing) in Collateral::<T>::iter() {
+            solvency.collateral = solvency.collateral.saturating_add(backing.chips);
+            solvency.assets.push(AssetSolvency {
+                asset_id,
+                chips: backing.chips,
+                owed: backing.tokens,
+                held: Self::treasury_balance(asset_id),
+            });
+        }
+        solvency.assets.sort_by_key(|asset| asset.asset_id);
+        solvency
+    }
+
+    /// Move the collateral held by the old custodian account into the chips treasury.
+    /// Chips bought before exchange rates existed are backed by the native currency, each account
+    /// gets its share of what the custodian actually moved.
+    pub fn migrate_from_custodian(custodian: &T::AccountId) -> Weight {
+        let mut reads: Weight = 1;
+        if Collateral::<T>::iter().next().is_some() {
+            return T::DbWeight::get().reads(reads);
+        }
+        let mut issued = T::ChipBalance::zero();
+        let mut holders = Vec::new();
+        for (who, chips) in ChipsMap::<T>::iter() {
+            let held = chips.balance.saturating_add(chips.reserve);
+            issued = issued.saturating_add(held);
+            holders.push((who, held));
+            reads += 1;
+        }
+        if issued.is_zero() {
+            return T::DbWeight::get().reads(reads);
+        }
+
+        // A short custodian moves what it has, the chips are backed by that much
+        let free: u128 = T::Balances::free_balance(custodian).saturated_into();
+        let mut moved = free.min(issued.saturated_into::<u128>());
+        if T::Balances::transfer(
+            custodian,
+            &Self::account_id(),
+            moved.saturated_into(),
+            ExistenceRequirement::AllowDeath,
+        )
+        .is_err()
+        {
+            moved = 0;
+        }
+
+        let writes = holders.len() as Weight;
+        let mut tokens_total = 0u128;
+        for (who, held) in holders {
+            let tokens = Backing { chips: issued, tokens: moved }.tokens_for(held);
+            tokens_total = tokens_total.saturating_add(tokens);
+            AccountCollateral::<T>::insert(who, NATIVE_ASSET_ID, Backing { chips: held, tokens });
+        }
+        Collateral::<T>::insert(
+            NATIVE_ASSET_ID,
+            Backing {
+                chips: issued,
+                tokens: tokens_total,
+            },
+        );
+        T::DbWeight::get().reads_writes(reads + 3, writes + 3)
+    }
+
+    /// Asset units paid for one chip
+    pub fn exchange_rate(asset_id: AssetId) -> Option<FixedU128> {
+        match Self::exchange_rates(asset_id) {
+            Some(rate) => Some(rate),
+            None if asset_id == NATIVE_ASSET_ID => Some(FixedU128::one()),
+            None => None,
+        }
+    }
+
+    /// Asset units paid for the chips, rounded up so buying never costs less than the rate
+    pub fn chips_to_token_ceil(
+        asset_id: AssetId,
+        chips: T::ChipBalance,
+    ) -> Result<u128, dispatch::DispatchError> {
+        let rate = Self::exchange_rate(asset_id).ok_or(Error::<T>::AssetNotSupported)?;
+        let chips = chips.saturated_into::<u128>();
+        let inner = rate.into_inner();
+        let token = multiply_by_rational(chips, inner, FixedU128::accuracy())
+            .map_err(|_| Error::<T>::StorageOverflow)?;
+        // Converting back loses nothing only when the division was exact
+        let back = multiply_by_rational(token, FixedU128::accuracy(), inner)
+            .map_err(|_| Error::<T>::StorageOverflow)?;
+        if back < chips {
+            token
+                .checked_add(1)
+                .ok_or_else(|| Error::<T>::StorageOverflow.into())
+        } else {
+            Ok(token)
+        }
+    }
+
+    fn _buy_chips(
+        who: T::AccountId,
+        asset_id: AssetId,
+        amount: T::ChipBalance,
+    ) -> dispatch::DispatchResult {
+        let treasury = Self::account_id(); // Receiving account
+
+        // The receiving account is forbidden to purchase and redeem to avoid errors
+        ensure!(who != treasury, Error::<T>::ChipsIsNotEnough);
+
+        let mut chips = Self::chips_map(&who).unwrap_or_default();
+        chips.balance = chips
+            .balance
+            .checked_add(&amount)
+            .ok_or(Error::<T>::StorageOverflow)?;
+
+        // payment
+        // [Exchange] chips exchange for the backing asset
+        let token_amount = Self::chips_to_token_ceil(asset_id, amount)?;
+        let backing = Self::account_collateral(&who, asset_id);
+        let backing = Backing {
+            chips: backing.chips.checked_add(&amount).ok_or(Error::<T>::StorageOverflow)?,
+            tokens: backing.tokens.checked_add(token_amount).ok_or(Error::<T>::StorageOverflow)?,
+        };
+        let collateral = Self::collateral(asset_id);
+        let collateral = Backing {
+            chips: collateral.chips.checked_add(&amount).ok_or(Error::<T>::StorageOverflow)?,
+            tokens: collateral.tokens.checked_add(token_amount).ok_or(Error::<T>::StorageOverflow)?,
+        };
+        Self::transfer_token(
+            &who,
+            &treasury,
+            asset_id,
+            token_amount,
+            ExistenceRequirement::KeepAlive,
+        )?;
+
+        <ChipsMap<T>>::insert(&who, chips);
+        AccountCollateral::<T>::insert(&who, asset_id, backing);
+        Collateral::<T>::insert(asset_id, collateral);
+        Self::record_ledger(&who, LedgerKind::Buy, amount, ChipsReason::Exchange(asset_id));
+
+        // Send event notification
+        Self::deposit_event(RawEvent::BuyChips(who, asset_id, amount, token_amount));
+        Ok(())
+    }
+
+    fn _redemption(
+        who: T::AccountId,
+        asset_id: AssetId,
+        amount: T::ChipBalance,
+    ) -> dispatch::DispatchResult {
+        let treasury = Self::account_id(); // Paying account
+        // Get the balance of the chips, if you have not bought the chips, return an error
+        let mut chips_map = Self::chips_map(&who).ok_or(Error::<T>::NeverBoughtChips)?;
+
+        // Need to have enough chips
+        ensure!(chips_map.balance >= amount, Error::<T>::ChipsIsNotEnough);
+        let mut backing = Self::account_collateral(&who, asset_id);
+        ensure!(backing.chips >= amount, Error::<T>::CollateralNotEnough);
+
+        // 【Exchange】The chips get their share of the asset units paid for them, whatever the
+        // exchange rate is now
+        let token_amount = backing.tokens_for(amount);
+        backing.chips = backing.chips.saturating_sub(amount);
+        backing.tokens = backing.tokens.saturating_sub(token_amount);
+        let mut collateral = Self::collateral(asset_id);
+        collateral.chips = collateral.chips.saturating_sub(amount);
+        collateral.tokens = collateral.tokens.saturating_sub(token_amount);
+
+        // Update chips
+        chips_map.balance = chips_map
+            .balance
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::StorageOverflow)?;
+
+        // Ransom refund
+        Self::transfer_token(
+            &treasury,
+            &who,
+            asset_id,
+            token_amount,
+            ExistenceRequirement::AllowDeath,
+        )?;
+        <ChipsMap<T>>::insert(&who, chips_map);
+        if backing.chips.is_zero() {
+            AccountCollateral::<T>::remove(&who, asset_id);
+        } else {
+            AccountCollateral::<T>::insert(&who, asset_id, backing);
+        }
+        Collateral::<T>::insert(asset_id, collateral);
+        Self::record_ledger(
+            &who,
+            LedgerKind::Redemption,
+            amount,
+            ChipsReason::Exchange(asset_id),
+        );
+
+        // Send event notification
+        Self::deposit_event(RawEvent::Redemption(who, asset_id, amount, token_amount));
+        Ok(())
+    }
+
+    /// The backing of chips sent to another account goes with them, asset by asset
+    fn move_backing(from: &T::AccountId, to: &T::AccountId, chips: T::ChipBalance) {
+        let mut left = chips;
+        let backings: Vec<(AssetId, Backing<T::ChipBalance>)> =
+            AccountCollateral::<T>::iter_prefix(from).collect();
+        for (asset_id, mut backing) in backings {
+            if left.is_zero() {
+                break;
+            }
+            let moved = left.min(backing.chips);
+            let tokens = backing.tokens_for(moved);
+            backing.chips = backing.chips.saturating_sub(moved);
+            backing.tokens = backing.tokens.saturating_sub(tokens);
+            if backing.chips.is_zero() {
+                AccountCollateral::<T>::remove(from, asset_id);
+            } else {
+                AccountCollateral::<T>::insert(from, asset_id, backing);
+            }
+            AccountCollateral::<T>::mutate(to, asset_id, |to_backing| {
+                to_backing.chips = to_backing.chips.saturating_add(moved);
+                to_backing.tokens = to_backing.tokens.saturating_add(tokens);
+            });
+            left = left.saturating_sub(moved);
+        }
+    }
+
+    /// Append a chips change to the ledger of the account, the oldest entry is pruned once it is full
+    fn record_ledger(
+        who: &T::AccountId,
+        kind: LedgerKind,
+        amount: T::ChipBalance,
+        reason: ChipsReason,
+    ) {
+        let seq = Self::ledger_count(who);
+        ChipsLedger::<T>::insert(
+            who,
+            seq,
+            LedgerEntry {
+                block_number: <frame_system::Module<T>>::block_number(),
+                kind,
+                amount,
+                reason,
+            },
+        );
+        LedgerCount::<T>::insert(who, seq.saturating_add(1));
+
+        let max_entries = T::MaxLedgerEntries::get();
+        if seq >= max_entries {
+            ChipsLedger::<T>::remove(who, seq - max_entries);
+        }
+    }
+
+    /// A page of the chips ledger of the account, newest first
+    pub fn ledger(who: &T::AccountId, page: u32, page_size: u32) -> Vec<LedgerEntryOf<T>> {
+        let page_size = page_size.min(MAX_PAGE_SIZE);
+        let count = Self::ledger_count(who);
+        let skip = page.saturating_mul(page_size);
+        if skip >= count {
+            return Vec::new();
+        }
+        let newest = count - 1 - skip;
+        (0..page_size.min(newest + 1))
+            .map(|i| newest - i)
+            .filter_map(|seq| Self::ledger_entry(who, seq))
+            .collect()
+    }
+
+    /// Move backing asset units between accounts
+    fn transfer_token(
+        from: &T::AccountId,
+        to: &T::AccountId,
+        asset_id: AssetId,
+        amount: u128,
+        existence: ExistenceRequirement,
+    ) -> dispatch::DispatchResult {
+        // Redeeming a few chips can be worth less than one asset unit
+        if amount == 0 {
+            return Ok(());
+        }
+        if asset_id == NATIVE_ASSET_ID {
+            let balance: BalanceOf<T> = amount.saturated_into();
+            // A saturated amount would not match the chips
+            ensure!(
+                balance.saturated_into::<u128>() == amount,
+                Error::<T>::StorageOverflow
+            );
+            T::Balances::transfer(from, to, balance, existence)
+                .map_err(|_| Error::<T>::MoneyNotEnough)?;
+        } else {
+            let amount = u64::try_from(amount).map_err(|_| Error::<T>::StorageOverflow)?;
+            ensure!(
+                T::Assets::asset_balance(asset_id, from.clone()) >= amount,
+                Error::<T>::MoneyNotEnough
+            );
+            T::Assets::transfer_asset(from.clone(), asset_id, to.clone(), amount)?;
+        }
+        Ok(())
     }
 }
 pub trait ChipsTrait {
     type ChipBalance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy;
 }
 pub trait ChipsTransfer<AccountId>: ChipsTrait {
-    fn reserve(account: &AccountId, balance: Self::ChipBalance) -> DispatchResult;
-    fn unreserve(account: &AccountId, balance: Self::ChipBalance) -> DispatchResult;
+    fn reserve(
+        account: &AccountId,
+        balance: Self::ChipBalance,
+        reason: ChipsReason,
+    ) -> DispatchResult;
+    fn unreserve(
+        account: &AccountId,
+        balance: Self::ChipBalance,
+        reason: ChipsReason,
+    ) -> DispatchResult;
     fn repatriate_reserved(
         from: &AccountId,
         to: &AccountId,
         balance: Self::ChipBalance,
+        reason: ChipsReason,
     ) -> DispatchResult;
 }
 
@@ -190,7 +626,11 @@ impl<T: Config> ChipsTrait for Module<T> {
 
 impl<T: Config> ChipsTransfer<T::AccountId> for Module<T> {
     /// 【chips action】Pledge chips
-    fn reserve(_who: &T::AccountId, amount: Self::ChipBalance) -> dispatch::DispatchResult {
+    fn reserve(
+        _who: &T::AccountId,
+        amount: Self::ChipBalance,
+        reason: ChipsReason,
+    ) -> dispatch::DispatchResult {
         // Get the balance of the chip, if it does not exist, return an error
         let mut chips_map = Self::chips_map(&_who).ok_or(Error::<T>::NeverBoughtChips)?;
 
@@ -208,13 +648,18 @@ impl<T: Config> ChipsTransfer<T::AccountId> for Module<T> {
         chips_map.reserve = new_reserve.unwrap();
         <ChipsMap<T>>::mutate(&_who, |chips_detail| *chips_detail = Some(chips_map));
 
+        Self::record_ledger(_who, LedgerKind::Reserve, amount, reason);
         // Send event notification
-        RawEvent::Reserve(_who, amount);
+        Self::deposit_event(RawEvent::Reserve(_who.clone(), amount, reason));
         Ok(())
     }
 
     /// 【chips action】cancel pledge chips
-    fn unreserve(_who: &T::AccountId, amount: Self::ChipBalance) -> dispatch::DispatchResult {
+    fn unreserve(
+        _who: &T::AccountId,
+        amount: Self::ChipBalance,
+        reason: ChipsReason,
+    ) -> dispatch::DispatchResult {
         // Get the balance of the chip, if it does not exist, return an error
         let mut chips_map = Self::chips_map(&_who).ok_or(Error::<T>::NeverBoughtChips)?;
 
@@ -233,8 +678,9 @@ impl<T: Config> ChipsTransfer<T::AccountId> for Module<T> {
         chips_map.reserve = new_reserve.unwrap();
         <ChipsMap<T>>::mutate(&_who, |chips_detail| *chips_detail = Some(chips_map));
 
+        Self::record_ledger(_who, LedgerKind::Unreserve, amount, reason);
         // Send event notification
-        RawEvent::Unreserve(_who, amount);
+        Self::deposit_event(RawEvent::Unreserve(_who.clone(), amount, reason));
         Ok(())
     }
 
@@ -243,10 +689,11 @@ impl<T: Config> ChipsTransfer<T::AccountId> for Module<T> {
         from: &T::AccountId,
         to: &T::AccountId,
         amount: Self::ChipBalance,
+        reason: ChipsReason,
     ) -> dispatch::DispatchResult {
         //  Get the balance of the chip, if it does not exist, return an error
         let mut chips_from = Self::chips_map(&from).ok_or(Error::<T>::NeverBoughtChips)?;
-        let mut chips_to = Self::chips_map(&to).ok_or(Error::<T>::NeverBoughtChips)?;
+        ensure!(Self::chips_map(&to).is_some(), Error::<T>::NeverBoughtChips);
 
         // Need to have enough pledge chips
         ensure!(chips_from.reserve >= amount, Error::<T>::ChipsIsNotEnough);
@@ -257,14 +704,25 @@ impl<T: Config> ChipsTransfer<T::AccountId> for Module<T> {
         chips_from.reserve = new_reserve.unwrap();
         <ChipsMap<T>>::mutate(&from, |chips_detail| *chips_detail = Some(chips_from));
 
-        // to update chip
+        // to update chip, read after the update of from in case both are the same account
+        let mut chips_to = Self::chips_map(&to).ok_or(Error::<T>::NeverBoughtChips)?;
         let new_balance = chips_to.balance.checked_add(&amount);
         ensure!(new_balance != None, Error::<T>::StorageOverflow);
         chips_to.balance = new_balance.unwrap();
         <ChipsMap<T>>::mutate(&to, |chips_detail| *chips_detail = Some(chips_to));
+        if from != to {
+            Self::move_backing(from, to, amount);
+        }
 
+        Self::record_ledger(from, LedgerKind::RepatriateOut, amount, reason);
+        Self::record_ledger(to, LedgerKind::RepatriateIn, amount, reason);
         // Send event notification
-        RawEvent::RepatriateReserved(from, to, amount);
+        Self::deposit_event(RawEvent::RepatriateReserved(
+            from.clone(),
+            to.clone(),
+            amount,
+            reason,
+        ));
         Ok(())
     }
 }
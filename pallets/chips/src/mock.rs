@@ -2,10 +2,12 @@ use crate as pallet_chips;
 use balances;
 use frame_support::parameter_types;
 use frame_system as system;
+use frame_system::EnsureRoot;
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    traits::{AccountIdConversion, BlakeTwo256, IdentityLookup},
+    ModuleId,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -20,6 +22,7 @@ frame_support::construct_runtime!(
     {
         System: frame_system::{Module, Call, Config, Storage, Event<T>},
         Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
+        SubGameAssets: pallet_subgame_assets::{Module, Call, Storage, Event<T>},
         Chips: pallet_chips::{Module, Call, Storage, Event<T>},
     }
 );
@@ -68,16 +71,44 @@ impl balances::Config for Test {
 }
 
 parameter_types! {
-    pub const MasterAddress: u32 = 3;
+    pub const ChipsModuleId: ModuleId = ModuleId(*b"sg/chips");
+    pub const MaxLedgerEntries: u32 = 3;
 }
 impl pallet_chips::Config for Test {
     type Event = Event;
     type Balances = balances::Module<Self>;
     type ChipBalance = u128;
-    type MasterAddress = MasterAddress;
+    type ModuleId = ChipsModuleId;
     type WeightInfo = ();
+    type Assets = SubGameAssets;
+    type MaxLedgerEntries = MaxLedgerEntries;
+    type RateOrigin = EnsureRoot<u64>;
 }
 
+parameter_types! {
+    pub const AssetDepositBase: u64 = 100;
+    pub const AssetDepositPerZombie: u64 = 1;
+    pub const StringLimit: u32 = 50;
+    pub const MetadataDepositBase: u64 = 10;
+    pub const MetadataDepositPerByte: u64 = 1;
+}
+impl pallet_subgame_assets::Config for Test {
+    type Event = Event;
+    type SGAssetBalance = u64;
+    type AssetId = u32;
+    type Currency = balances::Module<Self>;
+    type ForceOrigin = EnsureRoot<Self::AccountId>;
+    type AssetDepositBase = AssetDepositBase;
+    type AssetDepositPerZombie = AssetDepositPerZombie;
+    type StringLimit = StringLimit;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type WeightInfo = ();
+}
+
+/// USDT, as bridged by `pallet_bridge`
+pub const USDT: u32 = 7;
+
 /// Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
     let mut t = system::GenesisConfig::default()
@@ -91,11 +122,18 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
             (3, 1000000),
             (4, 1000000),
             (5, 1000000),
+            // The chips treasury exists before the first chips are bought
+            (ChipsModuleId::get().into_account(), 1000000),
         ],
     }
     .assimilate_storage(&mut t)
     .unwrap();
     let mut ext: sp_io::TestExternalities = t.into();
-    ext.execute_with(|| System::set_block_number(1));
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        // Account 4 issues USDT, account 1 holds some
+        SubGameAssets::_force_create(USDT, 4, 10, 1).unwrap();
+        SubGameAssets::_mint(4, USDT, 1, 1000).unwrap();
+    });
     ext
 }
@@ -1,6 +1,16 @@
-use crate::mock::{new_test_ext, Chips, Origin, Test};
-use crate::{Error};
+use crate::mock::{
+    new_test_ext, Balances, Chips, Event, Origin, SubGameAssets, System, Test, USDT,
+};
+use crate::{
+    AssetSolvency, Backing, ChipsDetail, ChipsMap, ChipsReason, ChipsTransfer, Error, LedgerKind,
+    RawEvent, NATIVE_ASSET_ID,
+};
 use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{traits::BadOrigin, FixedPointNumber, FixedU128};
+
+fn last_event() -> Event {
+    System::events().pop().expect("Event expected").event
+}
 
 /// 【Scenario】Buy chips
 #[test]
@@ -100,3 +110,432 @@ fn redemption_failded_when_chips_is_not_enough() {
         assert_eq!(Chips::chips_map(1).unwrap().balance, 10);
     });
 }
+
+/// 【Scenario】Governance sets the chip price in the native currency
+#[test]
+fn buy_chips_at_native_rate() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // The native currency is 1:1 until a rate is set
+        assert_eq!(
+            Chips::exchange_rate(NATIVE_ASSET_ID),
+            Some(FixedU128::from(1))
+        );
+        assert_noop!(
+            Chips::set_exchange_rate(Origin::signed(1), NATIVE_ASSET_ID, Some(FixedU128::from(2))),
+            BadOrigin
+        );
+        assert_ok!(Chips::set_exchange_rate(
+            Origin::root(),
+            NATIVE_ASSET_ID,
+            Some(FixedU128::from(2))
+        ));
+
+        // 【When】Act
+        assert_ok!(Chips::buy_chips(Origin::signed(1), 100));
+
+        // 【Then】Assert
+        // 100 chips cost 200
+        assert_eq!(Balances::free_balance(1), 1000000 - 200);
+        assert_eq!(Balances::free_balance(Chips::account_id()), 1000000 + 200);
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 100);
+        assert_eq!(
+            last_event(),
+            Event::pallet_chips(RawEvent::BuyChips(1, NATIVE_ASSET_ID, 100, 200))
+        );
+
+        // Redemption is paid at the recorded rate
+        assert_ok!(Chips::redemption(Origin::signed(1), 50));
+        assert_eq!(Balances::free_balance(1), 1000000 - 100);
+        assert_eq!(
+            last_event(),
+            Event::pallet_chips(RawEvent::Redemption(1, NATIVE_ASSET_ID, 50, 100))
+        );
+    });
+}
+
+/// 【Scenario】Buy and redeem chips with USDT
+#[test]
+fn buy_chips_with_asset() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // 1 USDT buys 2 chips
+        assert_ok!(Chips::set_exchange_rate(
+            Origin::root(),
+            USDT,
+            Some(FixedU128::saturating_from_rational(1, 2))
+        ));
+
+        // 【When】Act
+        assert_ok!(Chips::buy_chips_with_asset(Origin::signed(1), USDT, 101));
+
+        // 【Then】Assert
+        // Buying rounds up
+        assert_eq!(SubGameAssets::balance(USDT, 1), 1000 - 51);
+        assert_eq!(SubGameAssets::balance(USDT, Chips::account_id()), 51);
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 101);
+        assert_eq!(
+            last_event(),
+            Event::pallet_chips(RawEvent::BuyChips(1, USDT, 101, 51))
+        );
+
+        // Redemption pays back the asset units paid for the chips
+        assert_ok!(Chips::redemption_to_asset(Origin::signed(1), USDT, 101));
+        assert_eq!(SubGameAssets::balance(USDT, 1), 1000);
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 0);
+        assert_eq!(
+            last_event(),
+            Event::pallet_chips(RawEvent::Redemption(1, USDT, 101, 51))
+        );
+    });
+}
+
+/// 【Scenario】Assets without an exchange rate can not back chips
+#[test]
+fn buy_chips_failed_when_asset_not_supported() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+
+        // 【When】Act
+        // 【Then】Assert
+        assert_noop!(
+            Chips::buy_chips_with_asset(Origin::signed(1), USDT, 100),
+            Error::<Test>::AssetNotSupported
+        );
+        assert_noop!(
+            Chips::redemption_to_asset(Origin::signed(1), USDT, 100),
+            Error::<Test>::CollateralNotEnough
+        );
+        assert_noop!(
+            Chips::set_exchange_rate(Origin::root(), USDT, Some(FixedU128::from(0))),
+            Error::<Test>::ZeroExchangeRate
+        );
+
+        // A removed rate stops the asset from backing chips
+        assert_ok!(Chips::set_exchange_rate(
+            Origin::root(),
+            USDT,
+            Some(FixedU128::from(1))
+        ));
+        assert_ok!(Chips::set_exchange_rate(Origin::root(), USDT, None));
+        assert_eq!(Chips::exchange_rate(USDT), None);
+        assert_eq!(
+            last_event(),
+            Event::pallet_chips(RawEvent::ExchangeRateSet(USDT, None))
+        );
+    });
+}
+
+/// 【Scenario】Chips are fully collateralised by the treasury
+#[test]
+fn solvency() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        assert_ok!(Chips::set_exchange_rate(
+            Origin::root(),
+            USDT,
+            Some(FixedU128::from(2))
+        ));
+
+        // 【When】Act
+        assert_ok!(Chips::buy_chips(Origin::signed(1), 1000));
+        assert_ok!(Chips::buy_chips_with_asset(Origin::signed(1), USDT, 100));
+        assert_ok!(<Chips as ChipsTransfer<_>>::reserve(
+            &1,
+            300,
+            ChipsReason::Other
+        ));
+        assert_ok!(Chips::redemption(Origin::signed(1), 200));
+
+        // 【Then】Assert
+        let solvency = Chips::solvency();
+        assert_eq!(solvency.free, 600);
+        assert_eq!(solvency.reserved, 300);
+        assert_eq!(solvency.collateral, 900);
+        assert_eq!(
+            solvency.assets,
+            vec![
+                AssetSolvency {
+                    asset_id: NATIVE_ASSET_ID,
+                    chips: 800,
+                    owed: 800,
+                    held: 1000000 + 800,
+                },
+                AssetSolvency {
+                    asset_id: USDT,
+                    chips: 100,
+                    owed: 200,
+                    held: 200,
+                },
+            ]
+        );
+        assert!(solvency.is_solvent());
+    });
+}
+
+/// 【Scenario】Chips can only be redeemed for the asset that backs them
+#[test]
+fn redemption_failed_when_collateral_not_enough() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // A user buys 100 chips with USDT
+        assert_ok!(Chips::set_exchange_rate(
+            Origin::root(),
+            USDT,
+            Some(FixedU128::from(1))
+        ));
+        assert_ok!(Chips::buy_chips_with_asset(Origin::signed(1), USDT, 100));
+
+        // 【When】Act
+        // 【Then】Assert
+        assert_noop!(
+            Chips::redemption(Origin::signed(1), 100),
+            Error::<Test>::CollateralNotEnough
+        );
+        assert_ok!(Chips::redemption_to_asset(Origin::signed(1), USDT, 100));
+        assert!(Chips::solvency().is_solvent());
+    });
+}
+
+/// 【Scenario】A new exchange rate does not change what bought chips redeem for
+#[test]
+fn redemption_pays_the_collateral_held() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // A user buys 100 chips 1:1, then the chip price doubles
+        assert_ok!(Chips::buy_chips(Origin::signed(1), 100));
+        assert_ok!(Chips::set_exchange_rate(
+            Origin::root(),
+            NATIVE_ASSET_ID,
+            Some(FixedU128::from(2))
+        ));
+
+        // 【When】Act
+        assert_ok!(Chips::redemption(Origin::signed(1), 100));
+
+        // 【Then】Assert
+        // The treasury pays back what it holds for the chips
+        assert_eq!(Balances::free_balance(1), 1000000);
+        assert_eq!(Balances::free_balance(Chips::account_id()), 1000000);
+        assert_eq!(Chips::collateral(NATIVE_ASSET_ID), Backing::default());
+        assert!(Chips::solvency().is_solvent());
+    });
+}
+
+/// 【Scenario】Chips won in a game keep the backing of the loser
+#[test]
+fn backing_moves_with_the_chips() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // A buys 100 chips with USDT, B buys 100 chips with the native currency
+        assert_ok!(Chips::set_exchange_rate(
+            Origin::root(),
+            USDT,
+            Some(FixedU128::from(2))
+        ));
+        assert_ok!(Chips::buy_chips_with_asset(Origin::signed(1), USDT, 100));
+        assert_ok!(Chips::buy_chips(Origin::signed(2), 100));
+
+        // 【When】Act
+        // B wins the chips of A
+        assert_ok!(<Chips as ChipsTransfer<_>>::reserve(&1, 100, ChipsReason::Other));
+        assert_ok!(<Chips as ChipsTransfer<_>>::repatriate_reserved(
+            &1,
+            &2,
+            100,
+            ChipsReason::Other
+        ));
+
+        // 【Then】Assert
+        assert_eq!(Chips::account_collateral(1, USDT), Backing::default());
+        assert_eq!(
+            Chips::account_collateral(2, USDT),
+            Backing {
+                chips: 100,
+                tokens: 200
+            }
+        );
+        // The USDT backed chips can not drain the native collateral
+        assert_noop!(
+            Chips::redemption(Origin::signed(2), 200),
+            Error::<Test>::CollateralNotEnough
+        );
+        assert_ok!(Chips::redemption(Origin::signed(2), 100));
+        assert_ok!(Chips::redemption_to_asset(Origin::signed(2), USDT, 100));
+        assert_eq!(SubGameAssets::balance(USDT, 2), 200);
+        assert_eq!(SubGameAssets::balance(USDT, Chips::account_id()), 0);
+        assert!(Chips::solvency().is_solvent());
+    });
+}
+
+/// 【Scenario】The collateral of the old custodian moves into the treasury
+#[test]
+fn migrate_from_custodian() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // Chips bought before the treasury existed
+        ChipsMap::<Test>::insert(
+            1,
+            ChipsDetail {
+                balance: 700,
+                reserve: 300,
+            },
+        );
+        assert!(!Chips::solvency().is_solvent());
+
+        // 【When】Act
+        Chips::migrate_from_custodian(&3);
+
+        // 【Then】Assert
+        assert_eq!(Balances::free_balance(3), 1000000 - 1000);
+        assert_eq!(Balances::free_balance(Chips::account_id()), 1000000 + 1000);
+        assert_eq!(Chips::collateral(NATIVE_ASSET_ID).chips, 1000);
+        assert_eq!(
+            Chips::account_collateral(1, NATIVE_ASSET_ID),
+            Backing {
+                chips: 1000,
+                tokens: 1000
+            }
+        );
+        assert!(Chips::solvency().is_solvent());
+
+        // Running it again changes nothing
+        Chips::migrate_from_custodian(&3);
+        assert_eq!(Balances::free_balance(3), 1000000 - 1000);
+    });
+}
+
+/// 【Scenario】A short custodian backs the chips with what it has
+#[test]
+fn migrate_from_short_custodian() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // 2000000 chips were issued, the custodian holds 1000000
+        ChipsMap::<Test>::insert(
+            1,
+            ChipsDetail {
+                balance: 1200000,
+                reserve: 0,
+            },
+        );
+        ChipsMap::<Test>::insert(
+            2,
+            ChipsDetail {
+                balance: 300000,
+                reserve: 500000,
+            },
+        );
+
+        // 【When】Act
+        Chips::migrate_from_custodian(&3);
+
+        // 【Then】Assert
+        // Only what moved is recorded, shared by the holders
+        assert_eq!(Balances::free_balance(3), 0);
+        assert_eq!(
+            Chips::collateral(NATIVE_ASSET_ID),
+            Backing {
+                chips: 2000000,
+                tokens: 1000000
+            }
+        );
+        assert_eq!(Chips::account_collateral(1, NATIVE_ASSET_ID).tokens, 600000);
+        assert_eq!(Chips::account_collateral(2, NATIVE_ASSET_ID).tokens, 400000);
+        assert!(Chips::solvency().is_solvent());
+        // Redeeming all chips pays the share held for them
+        assert_ok!(Chips::redemption(Origin::signed(1), 1200000));
+        assert_eq!(Balances::free_balance(1), 1000000 + 600000);
+    });
+}
+
+/// 【Scenario】Chips movements of a game are reported with the game
+#[test]
+fn chips_transfer_events() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 1000);
+        let reason = ChipsReason::Game(1, 5);
+
+        // 【When】Act
+        assert_ok!(<Chips as ChipsTransfer<_>>::reserve(&1, 300, reason));
+        assert_ok!(<Chips as ChipsTransfer<_>>::unreserve(&1, 100, reason));
+        assert_ok!(<Chips as ChipsTransfer<_>>::repatriate_reserved(
+            &1, &2, 200, reason
+        ));
+
+        // 【Then】Assert
+        let events: Vec<Event> = System::events().into_iter().map(|r| r.event).collect();
+        assert!(events.contains(&Event::pallet_chips(RawEvent::Reserve(1, 300, reason))));
+        assert!(events.contains(&Event::pallet_chips(RawEvent::Unreserve(1, 100, reason))));
+        assert_eq!(
+            last_event(),
+            Event::pallet_chips(RawEvent::RepatriateReserved(1, 2, 200, reason))
+        );
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 800);
+        assert_eq!(Chips::chips_map(1).unwrap().reserve, 0);
+        assert_eq!(Chips::chips_map(2).unwrap().balance, 1200);
+        assert!(Chips::solvency().is_solvent());
+    });
+}
+
+/// 【Scenario】Reserved chips sent to the same account go back to free
+#[test]
+fn repatriate_reserved_to_self() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        assert_ok!(<Chips as ChipsTransfer<_>>::reserve(
+            &1,
+            300,
+            ChipsReason::Other
+        ));
+
+        // 【When】Act
+        assert_ok!(<Chips as ChipsTransfer<_>>::repatriate_reserved(
+            &1,
+            &1,
+            300,
+            ChipsReason::Other
+        ));
+
+        // 【Then】Assert
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 1000);
+        assert_eq!(Chips::chips_map(1).unwrap().reserve, 0);
+        assert!(Chips::solvency().is_solvent());
+    });
+}
+
+/// 【Scenario】The ledger keeps the latest chips changes of the account
+#[test]
+fn chips_ledger() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let reason = ChipsReason::Game(1, 5);
+
+        // 【When】Act
+        assert_ok!(Chips::buy_chips(Origin::signed(1), 1000));
+        System::set_block_number(2);
+        assert_ok!(<Chips as ChipsTransfer<_>>::reserve(&1, 300, reason));
+        assert_ok!(<Chips as ChipsTransfer<_>>::unreserve(&1, 100, reason));
+        assert_ok!(Chips::redemption(Origin::signed(1), 50));
+
+        // 【Then】Assert
+        // Only the latest 3 entries are kept
+        assert_eq!(Chips::ledger_count(1), 4);
+        assert_eq!(Chips::ledger_entry(1, 0), None);
+        let page = Chips::ledger(&1, 0, 2);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].kind, LedgerKind::Redemption);
+        assert_eq!(page[0].amount, 50);
+        assert_eq!(page[0].reason, ChipsReason::Exchange(NATIVE_ASSET_ID));
+        assert_eq!(page[1].kind, LedgerKind::Unreserve);
+        assert_eq!(page[1].reason, reason);
+        assert_eq!(page[1].block_number, 2);
+        let page = Chips::ledger(&1, 1, 2);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].kind, LedgerKind::Reserve);
+        assert!(Chips::ledger(&1, 2, 2).is_empty());
+    });
+}
@@ -104,6 +104,10 @@ impl pallet_lease::Config for Test {
     type OwnerAddress = ModuleOwner;
 }
 
+parameter_types! {
+    pub const MaxExpiriesPerBlock: u32 = 50;
+    pub const MaxExpiryBucketsPerBlock: u32 = 10;
+}
 impl pallet_stake_nft::Config for Test {
     type ProgramId = u64;
     type PalletId = u64;
@@ -112,6 +116,9 @@ impl pallet_stake_nft::Config for Test {
     type Lease = Lease;
     type OwnerAddress = ModuleOwner;
     type Event = Event;
+    type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+    type MaxExpiryBucketsPerBlock = MaxExpiryBucketsPerBlock;
+    type WeightInfo = ();
 }
 
 parameter_types! {
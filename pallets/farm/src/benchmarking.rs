@@ -0,0 +1,97 @@
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use sp_std::{vec, vec::Vec, boxed::Box};
+use sp_runtime::{
+	traits::{
+		SaturatedConversion,
+	}
+};
+use frame_support::{
+	assert_ok,
+	traits::Currency,
+};
+
+#[allow(unused)]
+use crate::Module as Pallet;
+
+pub const USDT_DECIMALS: u64 = 1_000_000;
+pub const GOGO_DECIMALS: u64 = 1_000_000;
+
+fn create_asset<T: Config>(asset_id: u32, name: &str, owner: &T::AccountId, holders: &[T::AccountId]) {
+	let asset_id: T::AssetId = asset_id.saturated_into();
+	assert_ok!(SubGameAssets::Module::<T>::_force_create(asset_id, owner.clone(), 10, 1u32.saturated_into()));
+	assert_ok!(SubGameAssets::Module::<T>::_force_set_metadata(owner.clone(), asset_id, name.as_bytes().to_vec(), name.as_bytes().to_vec(), 6));
+	for holder in holders {
+		let mint_balance = 100000000 * USDT_DECIMALS;
+		assert_ok!(SubGameAssets::Module::<T>::_mint(owner.clone(), asset_id, holder.clone(), mint_balance.saturated_into()));
+	}
+}
+
+/// A GOGO-USDT pool and a farm paying GOGO to its stakers from block 1 to 1001, returns the LP asset
+fn init<T: Config>() -> T::AssetId {
+	let default_balances = 10000000000000000u64.saturated_into();
+	let user: T::AccountId = whitelisted_caller();
+	let owner: T::AccountId = T::OwnerAddress::get();
+	<T as pallet_swap::Config>::Currency::make_free_balance_be(&user, default_balances);
+	<T as pallet_swap::Config>::Currency::make_free_balance_be(&owner, default_balances);
+
+	create_asset::<T>(7, "USDT", &user, &[user.clone()]);
+	create_asset::<T>(8, "GOGO", &user, &[user.clone(), owner.clone()]);
+
+	let amount: u64 = 1000 * GOGO_DECIMALS;
+	assert_ok!(pallet_swap::Module::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), 8u32.saturated_into(), amount.saturated_into(), 7u32.saturated_into(), amount.saturated_into(), 30, pallet_swap::Curve::ConstantProduct));
+	let lp_asset = pallet_swap::Module::<T>::pool_reserves(1u32.saturated_into()).unwrap().asset_lp;
+
+	let reward_per_block: u64 = 1 * GOGO_DECIMALS;
+	assert_ok!(Pallet::<T>::create_farm(RawOrigin::Signed(owner).into(), lp_asset, 8u32.saturated_into(), reward_per_block.saturated_into(), 1u32.saturated_into(), 1001u32.saturated_into()));
+	lp_asset
+}
+
+benchmarks! {
+	create_farm {
+		let lp_asset = init::<T>();
+		let owner: T::AccountId = T::OwnerAddress::get();
+		let reward_per_block: u64 = 1 * GOGO_DECIMALS;
+	}: _(RawOrigin::Signed(owner), lp_asset, 8u32.saturated_into(), reward_per_block.saturated_into(), 1u32.saturated_into(), 1001u32.saturated_into())
+	verify {
+		assert_eq!(Pallet::<T>::farm_count(), 2);
+	}
+
+	stake {
+		init::<T>();
+		let user: T::AccountId = whitelisted_caller();
+		assert_ok!(Pallet::<T>::stake(RawOrigin::Signed(user.clone()).into(), 1, 1000));
+		frame_system::Module::<T>::set_block_number(10u32.saturated_into());
+	}: _(RawOrigin::Signed(user.clone()), 1, 1000)
+	verify {
+		assert_eq!(Pallet::<T>::stake_of(1, &user).amount, 2000);
+	}
+
+	unstake {
+		init::<T>();
+		let user: T::AccountId = whitelisted_caller();
+		assert_ok!(Pallet::<T>::stake(RawOrigin::Signed(user.clone()).into(), 1, 2000));
+		frame_system::Module::<T>::set_block_number(10u32.saturated_into());
+	}: _(RawOrigin::Signed(user.clone()), 1, 1000)
+	verify {
+		assert_eq!(Pallet::<T>::stake_of(1, &user).amount, 1000);
+	}
+
+	claim {
+		init::<T>();
+		let user: T::AccountId = whitelisted_caller();
+		assert_ok!(Pallet::<T>::stake(RawOrigin::Signed(user.clone()).into(), 1, 1000));
+		frame_system::Module::<T>::set_block_number(10u32.saturated_into());
+	}: _(RawOrigin::Signed(user.clone()), 1)
+	verify {
+		assert_eq!(Pallet::<T>::pending_reward(1, &user), Some(0));
+	}
+}
+
+impl_benchmark_test_suite!(
+	Pallet,
+	crate::mock::new_test_ext(),
+	crate::mock::Test,
+);
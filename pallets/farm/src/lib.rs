@@ -0,0 +1,297 @@
+//! SubGame Swap LP Farming
+//!
+//! Reward programs that pay a fixed amount of a reward asset per block to the accounts staking
+//! the LP asset of a swap pool, shared in proportion to their stake. Rewards are tracked with an
+//! accumulated reward per staked LP token, so staking, unstaking and claiming cost the same
+//! however many accounts are in the farm.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, transactional,
+    traits::{Get, ExistenceRequirement},
+    weights::{Weight},
+};
+use frame_system::ensure_signed;
+use sp_runtime::{
+    ModuleId,
+    traits::{AccountIdConversion, SaturatedConversion, Zero},
+};
+use pallet_subgame_assets::{self as SubGameAssets};
+use pallet_swap::math;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+mod default_weight;
+pub trait WeightInfo {
+    fn create_farm() -> Weight;
+    fn stake() -> Weight;
+    fn unstake() -> Weight;
+    fn claim() -> Weight;
+}
+
+pub type FarmId = u32;
+
+/// Scale of `FarmInfo::acc_reward_per_share`
+pub const ACC_REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// The farm's module id, its account holds the staked LP and the rewards not paid out yet
+const MODULE_ID: ModuleId = ModuleId(*b"sgb/farm");
+
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FarmInfo<AssetId, BlockNumber> {
+    /// LP asset staked in the farm
+    pub lp_asset: AssetId,
+    /// Asset the rewards are paid in
+    pub reward_asset: AssetId,
+    /// Reward shared by the stakers every block from `start_block` to `end_block`
+    pub reward_per_block: u128,
+    pub start_block: BlockNumber,
+    pub end_block: BlockNumber,
+    /// LP staked by all accounts
+    pub total_staked: u128,
+    /// Reward earned by one staked LP since the farm started, times `ACC_REWARD_PRECISION`
+    pub acc_reward_per_share: u128,
+    /// Block `acc_reward_per_share` runs up to
+    pub last_reward_block: BlockNumber,
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StakeInfo {
+    /// LP staked by the account
+    pub amount: u128,
+    /// Part of `amount * acc_reward_per_share` that is already paid out or was earned before staking
+    pub reward_debt: u128,
+}
+
+pub trait Config: frame_system::Config + pallet_swap::Config {
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+    /// Account allowed to create farms, it funds their rewards
+    type OwnerAddress: Get<Self::AccountId>;
+    type WeightInfo: WeightInfo;
+}
+
+decl_storage! {
+    trait Store for Module<T: Config> as Farm {
+        pub FarmCount get(fn farm_count): FarmId;
+        pub Farms get(fn farm): map hasher(blake2_128_concat) FarmId => Option<FarmInfo<T::AssetId, T::BlockNumber>>;
+        pub Stakes get(fn stake_of): double_map hasher(blake2_128_concat) FarmId, hasher(blake2_128_concat) T::AccountId => StakeInfo;
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as frame_system::Config>::AccountId,
+        AssetId = <T as SubGameAssets::Config>::AssetId,
+        BlockNumber = <T as frame_system::Config>::BlockNumber,
+    {
+        /// farm id, lp asset, reward asset, reward per block, start block, end block
+        FarmCreated(FarmId, AssetId, AssetId, u128, BlockNumber, BlockNumber),
+        Staked(AccountId, FarmId, u128),
+        Unstaked(AccountId, FarmId, u128),
+        Claimed(AccountId, FarmId, u128),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Config> {
+        PermissionDenied,
+        /// The asset is not the LP asset of a swap pool
+        NotLpAsset,
+        /// The farm has to start no earlier than now and end after it starts
+        InvalidPeriod,
+        ZeroAmount,
+        FarmNotExists,
+        FarmEnded,
+        NotEnoughStake,
+        NoReward,
+        AmountOverflow,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Config> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+        fn deposit_event() = default;
+
+        /// Pay `reward_per_block` of `reward_asset` to the stakers of `lp_asset` every block from
+        /// `start_block` to `end_block`. The owner pays the whole reward into the farm up front.
+        #[weight = <T as Config>::WeightInfo::create_farm()]
+        #[transactional]
+        pub fn create_farm(
+            origin,
+            lp_asset: T::AssetId,
+            reward_asset: T::AssetId,
+            reward_per_block: u128,
+            start_block: T::BlockNumber,
+            end_block: T::BlockNumber,
+        ) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(sender == T::OwnerAddress::get(), Error::<T>::PermissionDenied);
+            ensure!(pallet_swap::Module::<T>::swap_id_of_lp(lp_asset).is_some(), Error::<T>::NotLpAsset);
+            ensure!(reward_per_block > 0, Error::<T>::ZeroAmount);
+            let now = frame_system::Module::<T>::block_number();
+            ensure!(start_block >= now && end_block > start_block, Error::<T>::InvalidPeriod);
+
+            let blocks: u128 = (end_block - start_block).saturated_into();
+            let total_reward = reward_per_block.checked_mul(blocks).ok_or(Error::<T>::AmountOverflow)?;
+            pallet_swap::Module::<T>::transfer(reward_asset, &sender, &Self::account_id(), total_reward, ExistenceRequirement::KeepAlive)?;
+
+            let farm_id = Self::farm_count() + 1;
+            Farms::<T>::insert(farm_id, FarmInfo {
+                lp_asset,
+                reward_asset,
+                reward_per_block,
+                start_block,
+                end_block,
+                total_staked: 0,
+                acc_reward_per_share: 0,
+                last_reward_block: start_block,
+            });
+            FarmCount::put(farm_id);
+
+            Self::deposit_event(RawEvent::FarmCreated(farm_id, lp_asset, reward_asset, reward_per_block, start_block, end_block));
+            Ok(())
+        }
+
+        /// Stake `amount` of the farm's LP asset, paying out the reward earned so far
+        #[weight = <T as Config>::WeightInfo::stake()]
+        #[transactional]
+        pub fn stake(origin, farm_id: FarmId, amount: u128) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(amount > 0, Error::<T>::ZeroAmount);
+            let mut farm = Self::updated_farm(farm_id)?;
+            ensure!(frame_system::Module::<T>::block_number() < farm.end_block, Error::<T>::FarmEnded);
+
+            let mut stake = Self::stake_of(farm_id, &sender);
+            Self::pay_reward(farm_id, &farm, &sender, &stake)?;
+            pallet_swap::Module::<T>::transfer(farm.lp_asset, &sender, &Self::account_id(), amount, ExistenceRequirement::AllowDeath)?;
+
+            stake.amount = stake.amount.checked_add(amount).ok_or(Error::<T>::AmountOverflow)?;
+            farm.total_staked = farm.total_staked.checked_add(amount).ok_or(Error::<T>::AmountOverflow)?;
+            Self::save(farm_id, farm, &sender, stake)?;
+
+            Self::deposit_event(RawEvent::Staked(sender, farm_id, amount));
+            Ok(())
+        }
+
+        /// Take `amount` of the staked LP back, paying out the reward earned so far
+        #[weight = <T as Config>::WeightInfo::unstake()]
+        #[transactional]
+        pub fn unstake(origin, farm_id: FarmId, amount: u128) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(amount > 0, Error::<T>::ZeroAmount);
+            let mut farm = Self::updated_farm(farm_id)?;
+            let mut stake = Self::stake_of(farm_id, &sender);
+            ensure!(stake.amount >= amount, Error::<T>::NotEnoughStake);
+
+            Self::pay_reward(farm_id, &farm, &sender, &stake)?;
+            pallet_swap::Module::<T>::transfer(farm.lp_asset, &Self::account_id(), &sender, amount, ExistenceRequirement::AllowDeath)?;
+
+            stake.amount -= amount;
+            farm.total_staked = farm.total_staked.saturating_sub(amount);
+            Self::save(farm_id, farm, &sender, stake)?;
+
+            Self::deposit_event(RawEvent::Unstaked(sender, farm_id, amount));
+            Ok(())
+        }
+
+        /// Pay out the reward earned so far
+        #[weight = <T as Config>::WeightInfo::claim()]
+        #[transactional]
+        pub fn claim(origin, farm_id: FarmId) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let farm = Self::updated_farm(farm_id)?;
+            let stake = Self::stake_of(farm_id, &sender);
+
+            let reward = Self::pay_reward(farm_id, &farm, &sender, &stake)?;
+            ensure!(reward > 0, Error::<T>::NoReward);
+            Self::save(farm_id, farm, &sender, stake)?;
+            Ok(())
+        }
+    }
+}
+
+impl<T: Config> Module<T> {
+    /// Account holding the staked LP and the rewards of every farm
+    pub fn account_id() -> T::AccountId {
+        MODULE_ID.into_account()
+    }
+
+    /// Reward the account could claim from the farm now
+    pub fn pending_reward(farm_id: FarmId, who: &T::AccountId) -> Option<u128> {
+        let farm = Self::accrue(Self::farm(farm_id)?)?;
+        Self::reward_of(&farm, &Self::stake_of(farm_id, who))
+    }
+
+    /// The farm with its accumulated reward per share run up to now, not written back
+    fn updated_farm(farm_id: FarmId) -> Result<FarmInfo<T::AssetId, T::BlockNumber>, dispatch::DispatchError> {
+        let farm = Self::farm(farm_id).ok_or(Error::<T>::FarmNotExists)?;
+        Self::accrue(farm).ok_or_else(|| Error::<T>::AmountOverflow.into())
+    }
+
+    /// Share the reward of the blocks since `last_reward_block` among the staked LP. Nobody earns
+    /// the reward of the blocks without any stake, it stays in the farm account.
+    fn accrue(mut farm: FarmInfo<T::AssetId, T::BlockNumber>) -> Option<FarmInfo<T::AssetId, T::BlockNumber>> {
+        let now = frame_system::Module::<T>::block_number().min(farm.end_block);
+        if now <= farm.last_reward_block {
+            return Some(farm);
+        }
+        if !farm.total_staked.is_zero() {
+            let blocks: u128 = (now - farm.last_reward_block).saturated_into();
+            let reward = farm.reward_per_block.checked_mul(blocks)?;
+            let per_share = math::mul_div(reward, ACC_REWARD_PRECISION, farm.total_staked)?;
+            farm.acc_reward_per_share = farm.acc_reward_per_share.checked_add(per_share)?;
+        }
+        farm.last_reward_block = now;
+        Some(farm)
+    }
+
+    fn reward_of(farm: &FarmInfo<T::AssetId, T::BlockNumber>, stake: &StakeInfo) -> Option<u128> {
+        let earned = math::mul_div(stake.amount, farm.acc_reward_per_share, ACC_REWARD_PRECISION)?;
+        Some(earned.saturating_sub(stake.reward_debt))
+    }
+
+    fn pay_reward(
+        farm_id: FarmId,
+        farm: &FarmInfo<T::AssetId, T::BlockNumber>,
+        who: &T::AccountId,
+        stake: &StakeInfo,
+    ) -> Result<u128, dispatch::DispatchError> {
+        let reward = Self::reward_of(farm, stake).ok_or(Error::<T>::AmountOverflow)?;
+        if reward > 0 {
+            pallet_swap::Module::<T>::transfer(farm.reward_asset, &Self::account_id(), who, reward, ExistenceRequirement::AllowDeath)?;
+            Self::deposit_event(RawEvent::Claimed(who.clone(), farm_id, reward));
+        }
+        Ok(reward)
+    }
+
+    /// Write the farm and the stake back, with the reward earned so far marked as paid
+    fn save(
+        farm_id: FarmId,
+        farm: FarmInfo<T::AssetId, T::BlockNumber>,
+        who: &T::AccountId,
+        mut stake: StakeInfo,
+    ) -> dispatch::DispatchResult {
+        if stake.amount.is_zero() {
+            Stakes::<T>::remove(farm_id, who);
+        } else {
+            stake.reward_debt = math::mul_div(stake.amount, farm.acc_reward_per_share, ACC_REWARD_PRECISION)
+                .ok_or(Error::<T>::AmountOverflow)?;
+            Stakes::<T>::insert(farm_id, who, stake);
+        }
+        Farms::<T>::insert(farm_id, farm);
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{ACC_REWARD_PRECISION, Error, FarmInfo, StakeInfo, mock::*};
+use frame_support::{assert_noop, assert_ok, traits::{Get, OnFinalize, OnInitialize}};
+use pallet_subgame_assets as SubGameAssets;
+
+pub const USDT_DECIMALS: u64 = 1_000_000;
+pub const GOGO_DECIMALS: u64 = 1_000_000;
+
+const REWARD_PER_BLOCK: u128 = GOGO_DECIMALS as u128;
+
+fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        Farm::on_finalize(System::block_number());
+        System::on_finalize(System::block_number());
+        System::set_block_number(System::block_number() + 1);
+        System::on_initialize(System::block_number());
+        Farm::on_initialize(System::block_number());
+    }
+}
+
+/// A GOGO-USDT pool owned by user 1, who gives users 2 and 3 some of its LP. Returns the LP asset.
+fn init_pool() -> u32 {
+    let user = 1;
+    for (asset_id, name) in [(7, "USDT"), (8, "GOGO")].iter() {
+        let name = name.as_bytes().to_vec();
+        assert_ok!(SubGameAssets::Module::<Test>::_force_create(*asset_id, user, 10, 1));
+        assert_ok!(SubGameAssets::Module::<Test>::_force_set_metadata(user, *asset_id, name.clone(), name, 6));
+        assert_ok!(SubGameAssets::Module::<Test>::_mint(user, *asset_id, user, 100000000 * USDT_DECIMALS));
+    }
+    assert_ok!(Swap::create_pool(Origin::signed(user), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, pallet_swap::Curve::ConstantProduct));
+    let lp_asset = Swap::pool_reserves(1).unwrap().asset_lp;
+    assert_ok!(SubGameAssets::Module::<Test>::_transfer(user, lp_asset, 2, 1000));
+    assert_ok!(SubGameAssets::Module::<Test>::_transfer(user, lp_asset, 3, 3000));
+    lp_asset
+}
+
+fn gogo_balance(who: u64) -> u128 {
+    SubGameAssets::Module::<Test>::balance(8, who) as u128
+}
+
+#[test]
+fn create_farm() {
+    new_test_ext().execute_with(|| {
+        let lp_asset = init_pool();
+        run_to_block(5);
+        let owner = FarmOwner::get();
+
+        // 【Then】only the owner creates farms, for an LP asset, over a period ahead
+        assert_noop!(Farm::create_farm(Origin::signed(2), lp_asset, 8, REWARD_PER_BLOCK, 10, 20), Error::<Test>::PermissionDenied);
+        assert_noop!(Farm::create_farm(Origin::signed(owner), 7, 8, REWARD_PER_BLOCK, 10, 20), Error::<Test>::NotLpAsset);
+        assert_noop!(Farm::create_farm(Origin::signed(owner), lp_asset, 8, 0, 10, 20), Error::<Test>::ZeroAmount);
+        assert_noop!(Farm::create_farm(Origin::signed(owner), lp_asset, 8, REWARD_PER_BLOCK, 4, 20), Error::<Test>::InvalidPeriod);
+        assert_noop!(Farm::create_farm(Origin::signed(owner), lp_asset, 8, REWARD_PER_BLOCK, 10, 10), Error::<Test>::InvalidPeriod);
+
+        // 【When】the owner creates a farm paying 1 GOGO a block from block 10 to 20
+        let before = gogo_balance(owner);
+        assert_ok!(Farm::create_farm(Origin::signed(owner), lp_asset, 8, REWARD_PER_BLOCK, 10, 20));
+
+        // 【Then】the whole reward is paid into the farm
+        assert_eq!(1, Farm::farm_count());
+        assert_eq!(before - 10 * REWARD_PER_BLOCK, gogo_balance(owner));
+        assert_eq!(10 * REWARD_PER_BLOCK, gogo_balance(Farm::account_id()));
+        assert_eq!(Some(FarmInfo {
+            lp_asset,
+            reward_asset: 8,
+            reward_per_block: REWARD_PER_BLOCK,
+            start_block: 10,
+            end_block: 20,
+            total_staked: 0,
+            acc_reward_per_share: 0,
+            last_reward_block: 10,
+        }), Farm::farm(1));
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_farm(crate::RawEvent::FarmCreated(1, lp_asset, 8, REWARD_PER_BLOCK, 10, 20))));
+    });
+}
+
+#[test]
+fn single_staker_earns_all() {
+    new_test_ext().execute_with(|| {
+        let lp_asset = init_pool();
+        assert_ok!(Farm::create_farm(Origin::signed(1), lp_asset, 8, REWARD_PER_BLOCK, 10, 20));
+
+        // 【Given】user 2 stakes before the farm starts
+        assert_ok!(Farm::stake(Origin::signed(2), 1, 1000));
+        assert_eq!(0, SubGameAssets::Module::<Test>::balance(lp_asset, 2));
+        assert_eq!(1000, SubGameAssets::Module::<Test>::balance(lp_asset, Farm::account_id()));
+        run_to_block(10);
+        assert_eq!(Some(0), Farm::pending_reward(1, &2));
+        assert_noop!(Farm::claim(Origin::signed(2), 1), Error::<Test>::NoReward);
+
+        // 【When】5 blocks into the farm
+        run_to_block(15);
+
+        // 【Then】user 2 earned all of their rewards and claims them
+        assert_eq!(Some(5 * REWARD_PER_BLOCK), Farm::pending_reward(1, &2));
+        assert_ok!(Farm::claim(Origin::signed(2), 1));
+        assert_eq!(5 * REWARD_PER_BLOCK, gogo_balance(2));
+        assert_eq!(Some(0), Farm::pending_reward(1, &2));
+
+        // 【When】the farm is over
+        run_to_block(30);
+
+        // 【Then】nothing more is earned after the end, nor staked
+        assert_eq!(Some(5 * REWARD_PER_BLOCK), Farm::pending_reward(1, &2));
+        assert_noop!(Farm::stake(Origin::signed(3), 1, 1000), Error::<Test>::FarmEnded);
+
+        // 【When】user 2 unstakes everything
+        assert_ok!(Farm::unstake(Origin::signed(2), 1, 1000));
+
+        // 【Then】they get their LP and the rest of the reward back
+        assert_eq!(1000, SubGameAssets::Module::<Test>::balance(lp_asset, 2));
+        assert_eq!(10 * REWARD_PER_BLOCK, gogo_balance(2));
+        assert_eq!(StakeInfo::default(), Farm::stake_of(1, 2));
+        assert_eq!(0, Farm::farm(1).unwrap().total_staked);
+        assert_eq!(0, gogo_balance(Farm::account_id()));
+    });
+}
+
+#[test]
+fn rewards_are_shared_by_stake() {
+    new_test_ext().execute_with(|| {
+        let lp_asset = init_pool();
+        assert_ok!(Farm::create_farm(Origin::signed(1), lp_asset, 8, REWARD_PER_BLOCK, 1, 21));
+
+        // 【Given】user 2 stakes 1000 LP alone for 4 blocks
+        assert_ok!(Farm::stake(Origin::signed(2), 1, 1000));
+        run_to_block(5);
+
+        // 【When】user 3 stakes 3000 LP
+        assert_ok!(Farm::stake(Origin::signed(3), 1, 3000));
+        run_to_block(9);
+
+        // 【Then】the next 4 blocks are shared 1 to 3
+        assert_eq!(Some(5 * REWARD_PER_BLOCK), Farm::pending_reward(1, &2));
+        assert_eq!(Some(3 * REWARD_PER_BLOCK), Farm::pending_reward(1, &3));
+        let farm = Farm::farm(1).unwrap();
+        assert_eq!(4000, farm.total_staked);
+        assert_eq!(4 * REWARD_PER_BLOCK * ACC_REWARD_PRECISION / 1000, farm.acc_reward_per_share);
+
+        // 【When】user 3 takes out 2000 and user 2 adds 1000
+        assert_ok!(Farm::unstake(Origin::signed(3), 1, 2000));
+        assert_ok!(Farm::stake(Origin::signed(2), 1, 1000));
+
+        // 【Then】the pending rewards were paid out on the way
+        assert_eq!(5 * REWARD_PER_BLOCK, gogo_balance(2));
+        assert_eq!(3 * REWARD_PER_BLOCK, gogo_balance(3));
+        assert_eq!(2000, SubGameAssets::Module::<Test>::balance(lp_asset, 3));
+        assert_noop!(Farm::unstake(Origin::signed(3), 1, 1001), Error::<Test>::NotEnoughStake);
+
+        // 【When】the farm ends with stakes of 2000 and 1000
+        run_to_block(25);
+        assert_ok!(Farm::claim(Origin::signed(2), 1));
+        assert_ok!(Farm::claim(Origin::signed(3), 1));
+
+        // 【Then】the last 12 blocks were shared 2 to 1 and the farm paid out everything
+        assert_eq!((5 + 8) * REWARD_PER_BLOCK, gogo_balance(2));
+        assert_eq!((3 + 4) * REWARD_PER_BLOCK, gogo_balance(3));
+        assert_eq!(0, gogo_balance(Farm::account_id()));
+    });
+}
+
+#[test]
+fn farm_not_exists() {
+    new_test_ext().execute_with(|| {
+        init_pool();
+
+        // 【Then】no farm, no staking
+        assert_noop!(Farm::stake(Origin::signed(2), 1, 1000), Error::<Test>::FarmNotExists);
+        assert_noop!(Farm::unstake(Origin::signed(2), 1, 1000), Error::<Test>::FarmNotExists);
+        assert_noop!(Farm::claim(Origin::signed(2), 1), Error::<Test>::FarmNotExists);
+        assert_eq!(None, Farm::pending_reward(1, &2));
+    });
+}
@@ -0,0 +1,22 @@
+//! Runtime API definition for the GameCenter pallet, queries the lobby games
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+pub use pallet_gamecenter::{GameInstance, GameInstanceId, TemplateId};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait GameCenterApi<AccountId, BlockNumber> where
+        AccountId: Codec,
+        BlockNumber: Codec,
+    {
+        /// Games of the template that are not over yet
+        fn open_games(template_id: TemplateId) -> Vec<GameInstance<GameInstanceId, AccountId, BlockNumber, u128>>;
+        /// Games the player created or played
+        fn player_games(who: AccountId) -> Vec<(TemplateId, GameInstance<GameInstanceId, AccountId, BlockNumber, u128>)>;
+        /// A page of the template history, newest first
+        fn history(template_id: TemplateId, page: u32, page_size: u32) -> Vec<GameInstance<GameInstanceId, AccountId, BlockNumber, u128>>;
+    }
+}
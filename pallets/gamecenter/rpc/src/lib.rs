@@ -0,0 +1,113 @@
+//! RPC interface for the GameCenter pallet, queries the lobby games
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use pallet_gamecenter_runtime_api::GameCenterApi as GameCenterRuntimeApi;
+use pallet_gamecenter_runtime_api::{GameInstance, GameInstanceId, TemplateId};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Error code of a failed runtime call
+const RUNTIME_ERROR: i64 = 1;
+
+type GameInstanceOf<AccountId, BlockNumber> =
+    GameInstance<GameInstanceId, AccountId, BlockNumber, u128>;
+
+#[rpc]
+pub trait GameCenterApi<BlockHash, AccountId, BlockNumber> {
+    /// Games of the template that are not over yet
+    #[rpc(name = "gameCenter_openGames")]
+    fn open_games(
+        &self,
+        template_id: TemplateId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<GameInstanceOf<AccountId, BlockNumber>>>;
+
+    /// Games the player created or played
+    #[rpc(name = "gameCenter_playerGames")]
+    fn player_games(
+        &self,
+        who: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<(TemplateId, GameInstanceOf<AccountId, BlockNumber>)>>;
+
+    /// A page of the template history, newest first
+    #[rpc(name = "gameCenter_history")]
+    fn history(
+        &self,
+        template_id: TemplateId,
+        page: u32,
+        page_size: u32,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<GameInstanceOf<AccountId, BlockNumber>>>;
+}
+
+/// Implements the GameCenterApi RPC trait for the lobby queries
+pub struct GameCenter<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> GameCenter<C, B> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+fn runtime_error(err: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query the game center.".into(),
+        data: Some(format!("{:?}", err).into()),
+    }
+}
+
+impl<C, Block, AccountId, BlockNumber>
+    GameCenterApi<<Block as BlockT>::Hash, AccountId, BlockNumber> for GameCenter<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: GameCenterRuntimeApi<Block, AccountId, BlockNumber>,
+    AccountId: Codec,
+    BlockNumber: Codec,
+{
+    fn open_games(
+        &self,
+        template_id: TemplateId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<GameInstanceOf<AccountId, BlockNumber>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.open_games(&at, template_id).map_err(runtime_error)
+    }
+
+    fn player_games(
+        &self,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<(TemplateId, GameInstanceOf<AccountId, BlockNumber>)>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.player_games(&at, who).map_err(runtime_error)
+    }
+
+    fn history(
+        &self,
+        template_id: TemplateId,
+        page: u32,
+        page_size: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<GameInstanceOf<AccountId, BlockNumber>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.history(&at, template_id, page, page_size)
+            .map_err(runtime_error)
+    }
+}
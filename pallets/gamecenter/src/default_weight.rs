@@ -7,17 +7,24 @@ use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
 impl crate::WeightInfo for () {
     fn create_game() -> Weight {
         (500_000_000 as Weight)
-            .saturating_add(DbWeight::get().reads(1 as Weight))
-            .saturating_add(DbWeight::get().writes(1 as Weight))
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(5 as Weight))
     }
     fn play_game() -> Weight {
         (500_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(2 as Weight))
+            .saturating_add(DbWeight::get().writes(2 as Weight))
+    }
+    fn settle_game() -> Weight {
+        (10_000_000 as Weight)
             .saturating_add(DbWeight::get().reads(1 as Weight))
-            .saturating_add(DbWeight::get().writes(1 as Weight))
     }
-    fn on_finalize(count: u32) -> Weight {
-        (50_000_000 as Weight)
-            .saturating_add(DbWeight::get().reads(count as Weight))
-            .saturating_add(DbWeight::get().writes(count as Weight))
+    fn on_initialize(count: u32) -> Weight {
+        (5_000_000 as Weight)
+            .saturating_add((30_000_000 as Weight).saturating_mul(count as Weight))
+            .saturating_add(DbWeight::get().reads(2 as Weight))
+            .saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(count as Weight)))
+            .saturating_add(DbWeight::get().writes(2 as Weight))
+            .saturating_add(DbWeight::get().writes((5 as Weight).saturating_mul(count as Weight)))
     }
 }
@@ -2,10 +2,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 use codec::{Decode, Encode};
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch, dispatch::Vec, weights::Weight,
+    decl_error, decl_event, decl_module, decl_storage, dispatch, dispatch::Vec, ensure,
+    storage::{IterableStorageDoubleMap, IterableStorageMap},
+    traits::Get,
+    weights::Weight,
 };
 use frame_system::ensure_signed;
-use pallet_gametemplates_guess_hash::{GuessHashFunc, GuessHashTrait};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+use pallet_gametemplates::{GameRouter, GameStatus, TemplateRegistry};
+pub use pallet_gametemplates::{GameInstanceId, TemplateId};
 
 #[cfg(test)]
 mod mock;
@@ -15,43 +22,72 @@ mod tests;
 
 mod default_weight;
 
-#[derive(Encode, Decode, Default)]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct GameInstance<GameInstanceID, Owner, DrawBlockNumber, Chips> {
     /// game instance id
-    game_instance_id: GameInstanceID,
+    pub game_instance_id: GameInstanceID,
     /// create game user
-    owner: Owner,
+    pub owner: Owner,
     /// bet block num (draw)
-    bet_block_number: DrawBlockNumber,
+    pub bet_block_number: DrawBlockNumber,
     /// bet max limit
-    chips_pool: Chips,
+    pub chips_pool: Chips,
     /// check game is draw
-    game_over: bool,
+    pub game_over: bool,
 }
 
-pub type GameInstanceId = u32;
+pub type GameInstanceOf<T> = GameInstance<
+    GameInstanceId,
+    <T as frame_system::Config>::AccountId,
+    <T as frame_system::Config>::BlockNumber,
+    u128,
+>;
+
+/// The most games returned by one history page
+pub const MAX_PAGE_SIZE: u32 = 100;
 
 pub trait WeightInfo {
     fn play_game() -> Weight;
     fn create_game() -> Weight;
-    fn on_finalize(count: u32) -> Weight;
+    fn settle_game() -> Weight;
+    fn on_initialize(count: u32) -> Weight;
 }
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type WeightInfo: WeightInfo;
-    type GuessHash: GuessHashTrait + GuessHashFunc<Self::AccountId, GameInstanceId, u128>;
+    /// The template ids that can be played
+    type TemplateRegistry: TemplateRegistry;
+    /// The games of the lobby, found by template id
+    type Templates: GameRouter<Self::AccountId, u128>;
+    /// The number of finished games kept in the history of each template
+    type MaxHistory: Get<u32>;
+    /// The number of games kept in the play record of each player
+    type MaxPlayerGames: Get<u32>;
 }
 
 decl_storage! {
     trait Store for Module<T: Config> as GameCenterModule {
-        /// List of games that can still participate in betting
-        pub CurrentGameinstances get(fn get_current_gameinstances): map hasher(blake2_128_concat) u32=> Vec<GameInstance<GameInstanceId, T::AccountId, T::BlockNumber, u128>>;
-        /// List of games that have been drawn
-        pub HistoryGameinstances get(fn get_history_gameinstances): map hasher(blake2_128_concat) u32=> Vec<GameInstance<GameInstanceId, T::AccountId, T::BlockNumber, u128>>;
-        /// Play Record
-        pub PlayMap get(fn get_playmap): map hasher(blake2_128_concat) T::AccountId=> Vec<u32>;
-        /// Draw Record
-        pub DrawMap get(fn draw_map): map hasher(blake2_128_concat) T::BlockNumber => Vec<GameInstanceId>;
+        /// Deprecated list of games that can still participate in betting, moved to `OpenGames` on runtime upgrade
+        CurrentGameinstances: map hasher(blake2_128_concat) u32=> Vec<GameInstanceOf<T>>;
+        /// Deprecated list of games that have been drawn, moved to `History` on runtime upgrade
+        HistoryGameinstances: map hasher(blake2_128_concat) u32=> Vec<GameInstanceOf<T>>;
+        /// Deprecated draw record, replaced by `DrawQueue` and removed on runtime upgrade
+        DrawMap: map hasher(blake2_128_concat) T::BlockNumber => Vec<GameInstanceId>;
+        /// The deprecated lists have been moved to the keyed storage
+        GameListsMigrated get(fn game_lists_migrated): bool;
+        /// Game instances of each template, removed once pruned from the history
+        pub GameInstances get(fn game_instance): double_map hasher(twox_64_concat) TemplateId, hasher(twox_64_concat) GameInstanceId => Option<GameInstanceOf<T>>;
+        /// Games of each template that are not over yet
+        pub OpenGames get(fn is_open): double_map hasher(twox_64_concat) TemplateId, hasher(twox_64_concat) GameInstanceId => bool;
+        /// Number of games that have been drawn, per template
+        pub HistoryCount get(fn history_count): map hasher(twox_64_concat) TemplateId => u32;
+        /// Games that have been drawn by sequence number, only the latest `MaxHistory` are kept
+        pub History get(fn history): double_map hasher(twox_64_concat) TemplateId, hasher(twox_64_concat) u32 => Option<GameInstanceId>;
+        /// Play Record (template id, game instance id), only the latest `MaxPlayerGames` are kept
+        pub PlayMap get(fn get_playmap): map hasher(blake2_128_concat) T::AccountId=> Vec<(TemplateId, GameInstanceId)>;
+        /// Draw Record (template id, game instance id), checked from the draw block until the game is over
+        pub DrawQueue get(fn draw_queue): map hasher(blake2_128_concat) T::BlockNumber => Vec<(TemplateId, GameInstanceId)>;
     }
 }
 
@@ -68,17 +104,20 @@ decl_event!(
 
 decl_error! {
     pub enum Error for Module<T: Config> {
+        TemplateNotRegistered,
     }
 }
 
 decl_module! {
     pub struct Module<T: Config> for enum Call where origin: T::Origin {
+        type Error = Error::<T>;
+
         #[weight = T::WeightInfo::create_game()]
-        /// create template game
-        pub fn create_game(origin, template_id: u32, bet_next_few_block: u32, amount: u128) -> dispatch::DispatchResult {
+        /// create template game, `params` are the template specific options
+        pub fn create_game(origin, template_id: TemplateId, bet_next_few_block: u32, amount: u128, params: Vec<u8>) -> dispatch::DispatchResult {
             let sender = ensure_signed(origin)?;
-            let game_id = T::GuessHash::create_game(&sender, bet_next_few_block, amount)?;
-
+            ensure!(T::TemplateRegistry::is_registered(template_id), Error::<T>::TemplateNotRegistered);
+            let game_id = T::Templates::create(template_id, &sender, bet_next_few_block, amount, params)?;
 
             let block_number = <frame_system::Module<T>>::block_number();
             let _bet_block_number = block_number + bet_next_few_block.into();
@@ -86,68 +125,192 @@ decl_module! {
             // create new game
             let gameinstances = GameInstance{
                 game_instance_id: game_id,
-                owner: sender,
+                owner: sender.clone(),
                 bet_block_number: _bet_block_number,
                 chips_pool: amount,
                 game_over: false,
             };
-            let mut current_game_list = CurrentGameinstances::<T>::get(template_id);
-            let index = current_game_list.len();
-            current_game_list.insert(index, gameinstances);	// insert new record
-            CurrentGameinstances::<T>::insert(&template_id, current_game_list);
+            GameInstances::<T>::insert(template_id, game_id, gameinstances);
+            OpenGames::insert(template_id, game_id, true);
+            Self::record_play(&sender, template_id, game_id);
 
-            // update draw block num
-            let mut drap_map = DrawMap::<T>::get(_bet_block_number);
-            drap_map.insert(drap_map.len(), game_id);
-            DrawMap::<T>::insert(_bet_block_number, drap_map);
+            // update draw block num, the queue of this block has been checked already
+            let check_block = _bet_block_number.max(block_number + 1u32.into());
+            let mut draw_queue = DrawQueue::<T>::get(check_block);
+            draw_queue.insert(draw_queue.len(), (template_id, game_id));
+            DrawQueue::<T>::insert(check_block, draw_queue);
 
             Ok(())
         }
 
         /// play template game
         #[weight = T::WeightInfo::play_game()]
-        pub fn play_game(origin, game_id: u32, amount: u128, game_mode: u8) -> dispatch::DispatchResult {
+        pub fn play_game(origin, template_id: TemplateId, game_id: GameInstanceId, amount: u128, game_mode: u8) -> dispatch::DispatchResult {
             let sender = ensure_signed(origin)?;
-            T::GuessHash::bet(&sender, game_id , amount, game_mode)?;
+            ensure!(T::TemplateRegistry::is_registered(template_id), Error::<T>::TemplateNotRegistered);
+            T::Templates::play(template_id, &sender, game_id, amount, game_mode)?;
+            Self::record_play(&sender, template_id, game_id);
+            Ok(())
+        }
+
+        /// settle a template game that is waiting to be settled, `bet_count` is at least the number of bets of the game
+        #[weight = T::WeightInfo::settle_game().saturating_add(T::Templates::settle_weight(*template_id, *bet_count))]
+        pub fn settle_game(origin, template_id: TemplateId, game_id: GameInstanceId, bet_count: u32) -> dispatch::DispatchResult {
+            ensure_signed(origin)?;
+            ensure!(T::TemplateRegistry::is_registered(template_id), Error::<T>::TemplateNotRegistered);
+            T::Templates::settle(template_id, game_id, bet_count)?;
             Ok(())
         }
-        fn on_initialize(_now: T::BlockNumber) -> Weight {
-            T::WeightInfo::on_finalize(2u32)
+
+        fn on_runtime_upgrade() -> Weight {
+            Self::migrate_game_lists()
         }
 
-        fn on_finalize(now: T::BlockNumber) {
-            let game_id_list = Self::draw_map(now);
+        /// Check the games of the draw queue, the games not settled yet are checked again in the
+        /// next block. The weight covers every game checked, moved to the history or queued again.
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let game_id_list = DrawQueue::<T>::take(now);
+            let count = game_id_list.len() as u32;
             // ready draw
             if !game_id_list.is_empty() {
-                for game_id in game_id_list {
-                    Self::game_over(1, game_id).ok();
+                let mut not_over = Vec::new();
+                for (template_id, game_id) in game_id_list {
+                    match T::Templates::status(template_id, game_id) {
+                        Some(GameStatus::Betting) | Some(GameStatus::Drawing) => not_over.push((template_id, game_id)),
+                        _ => {
+                            Self::game_over(template_id, game_id).ok();
+                        }
+                    }
+                }
+                if !not_over.is_empty() {
+                    DrawQueue::<T>::mutate(now + 1u32.into(), |draw_queue| draw_queue.extend(not_over));
                 }
             }
+            T::WeightInfo::on_initialize(count)
         }
     }
 }
 
 impl<T: Config> Module<T> {
     /// The game is over, the game instance will be moved to the history
-    fn game_over(template_id: u32, game_instance_id: u32) -> dispatch::DispatchResult {
-        let for_current_game_list = CurrentGameinstances::<T>::get(template_id);
-        let mut current_game_list = CurrentGameinstances::<T>::get(template_id);
-        let mut history_game_list = HistoryGameinstances::<T>::get(template_id);
-        let mut remove_game_instance_id: usize = 999999999;
-        let mut tmp_data: Option<GameInstance<GameInstanceId, T::AccountId, T::BlockNumber, u128>> =
-            None;
-        for (k, v) in for_current_game_list.into_iter().enumerate() {
-            if v.game_instance_id == game_instance_id {
-                remove_game_instance_id = k;
-                tmp_data = Some(v);
-            }
+    fn game_over(
+        template_id: TemplateId,
+        game_instance_id: GameInstanceId,
+    ) -> dispatch::DispatchResult {
+        if !OpenGames::take(template_id, game_instance_id) {
+            return Ok(());
         }
-        if tmp_data.is_some() {
-            current_game_list.remove(remove_game_instance_id);
-            CurrentGameinstances::<T>::insert(template_id, current_game_list);
-            history_game_list.insert(history_game_list.len(), tmp_data.unwrap());
-            HistoryGameinstances::<T>::insert(template_id, history_game_list);
+        if let Some(mut game) = Self::game_instance(template_id, game_instance_id) {
+            game.game_over = true;
+            Self::push_history(template_id, game);
         }
         Ok(())
     }
+
+    /// Append the game to the history, the oldest game is pruned once the history is full
+    fn push_history(template_id: TemplateId, game: GameInstanceOf<T>) {
+        let seq = Self::history_count(template_id);
+        History::insert(template_id, seq, game.game_instance_id);
+        GameInstances::<T>::insert(template_id, game.game_instance_id, game);
+        HistoryCount::insert(template_id, seq.saturating_add(1));
+
+        let max_history = T::MaxHistory::get();
+        if seq >= max_history {
+            if let Some(pruned_id) = History::take(template_id, seq - max_history) {
+                GameInstances::<T>::remove(template_id, pruned_id);
+            }
+        }
+    }
+
+    /// Add the game to the play record of the player, the oldest game is dropped once it is full
+    fn record_play(who: &T::AccountId, template_id: TemplateId, game_id: GameInstanceId) {
+        PlayMap::<T>::mutate(who, |games| {
+            if games.contains(&(template_id, game_id)) {
+                return;
+            }
+            if games.len() as u32 >= T::MaxPlayerGames::get() && !games.is_empty() {
+                games.remove(0);
+            }
+            games.push((template_id, game_id));
+        });
+    }
+
+    /// Move the games from the per template lists to the keyed storage and remove the old draw
+    /// record, once
+    fn migrate_game_lists() -> Weight {
+        if GameListsMigrated::get() {
+            return T::DbWeight::get().reads(1);
+        }
+        let now = <frame_system::Module<T>>::block_number();
+        let mut count: u64 = 0;
+        for (template_id, games) in CurrentGameinstances::<T>::drain() {
+            for game in games {
+                let game_id = game.game_instance_id;
+                // Check from the bet block whether the game is over
+                let check_block = if game.bet_block_number > now {
+                    game.bet_block_number
+                } else {
+                    now + 1u32.into()
+                };
+                DrawQueue::<T>::mutate(check_block, |draw_queue| {
+                    draw_queue.push((template_id, game_id))
+                });
+                GameInstances::<T>::insert(template_id, game_id, game);
+                OpenGames::insert(template_id, game_id, true);
+                count += 1;
+            }
+        }
+        for (template_id, games) in HistoryGameinstances::<T>::drain() {
+            for game in games {
+                Self::push_history(template_id, game);
+                count += 1;
+            }
+        }
+        // The games of the old draw record are in `DrawQueue` already
+        let draws = DrawMap::<T>::drain().count() as u64;
+        GameListsMigrated::put(true);
+        T::DbWeight::get().reads_writes(
+            count.saturating_add(draws).saturating_add(4),
+            count.saturating_mul(4).saturating_add(draws).saturating_add(3),
+        )
+    }
+
+    /// Games of the template that are not over yet
+    pub fn open_games(template_id: TemplateId) -> Vec<GameInstanceOf<T>> {
+        let mut games: Vec<GameInstanceOf<T>> = OpenGames::iter_prefix(template_id)
+            .filter_map(|(game_id, _)| Self::game_instance(template_id, game_id))
+            .collect();
+        games.sort_by_key(|game| game.game_instance_id);
+        games
+    }
+
+    /// Games the player created or played, oldest first
+    pub fn player_games(who: T::AccountId) -> Vec<(TemplateId, GameInstanceOf<T>)> {
+        Self::get_playmap(who)
+            .into_iter()
+            .filter_map(|(template_id, game_id)| {
+                Self::game_instance(template_id, game_id).map(|game| (template_id, game))
+            })
+            .collect()
+    }
+
+    /// A page of the template history, newest first
+    pub fn history_page(
+        template_id: TemplateId,
+        page: u32,
+        page_size: u32,
+    ) -> Vec<GameInstanceOf<T>> {
+        let page_size = page_size.min(MAX_PAGE_SIZE);
+        let count = Self::history_count(template_id);
+        let skip = page.saturating_mul(page_size);
+        if skip >= count {
+            return Vec::new();
+        }
+        let newest = count - 1 - skip;
+        (0..page_size.min(newest + 1))
+            .map(|i| newest - i)
+            .filter_map(|seq| Self::history(template_id, seq))
+            .filter_map(|game_id| Self::game_instance(template_id, game_id))
+            .collect()
+    }
 }
@@ -1,13 +1,16 @@
 use crate as pallet_gamecenter;
 use balances;
+use common::RandomSource;
 use frame_support::parameter_types;
 use frame_system as system;
 use pallet_chips;
+use pallet_gametemplates::TemplateRegistry;
 use pallet_gametemplates_guess_hash;
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    traits::{AccountIdConversion, BlakeTwo256, Hash, IdentityLookup},
+    ModuleId,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -72,27 +75,61 @@ impl balances::Config for Test {
 }
 
 parameter_types! {
-    pub const MasterAddress: u32 = 3;
+    pub const ChipsModuleId: ModuleId = ModuleId(*b"sg/chips");
+    pub const MaxLedgerEntries: u32 = 3;
 }
 impl pallet_chips::Config for Test {
     type Event = Event;
     type Balances = balances::Module<Self>;
     type ChipBalance = u128;
-    type MasterAddress = MasterAddress;
+    type ModuleId = ChipsModuleId;
     type WeightInfo = ();
+    type Assets = ();
+    type MaxLedgerEntries = MaxLedgerEntries;
+    type RateOrigin = frame_system::EnsureRoot<u64>;
 }
 
+parameter_types! {
+    pub const MaxBetsPerGame: u32 = 10;
+    pub const SettlementWeightLimit: u64 = 2_000_000_000_000;
+    pub const GuessHashTemplateId: u32 = 1;
+    pub const MaxHistory: u32 = 3;
+    pub const MaxPlayerGames: u32 = 3;
+}
 impl pallet_gametemplates_guess_hash::Config for Test {
     type Event = Event;
     type GameIndex = u32;
     type WeightInfo = ();
     type Chips = Chips;
+    type SeedSource = pallet_gametemplates_guess_hash::BabeVrf<TestRandomness>;
+    type MaxBetsPerGame = MaxBetsPerGame;
+    type SettlementWeightLimit = SettlementWeightLimit;
+    type TemplateId = GuessHashTemplateId;
+}
+
+/// Template 1 (guess hash) is registered
+pub struct TestTemplateRegistry;
+impl TemplateRegistry for TestTemplateRegistry {
+    fn is_registered(template_id: u32) -> bool {
+        template_id == 1
+    }
 }
 
 impl pallet_gamecenter::Config for Test {
     type Event = Event;
     type WeightInfo = ();
-    type GuessHash = GameGuessHashModule;
+    type TemplateRegistry = TestTemplateRegistry;
+    type Templates = (GameGuessHashModule,);
+    type MaxHistory = MaxHistory;
+    type MaxPlayerGames = MaxPlayerGames;
+}
+
+/// Deterministic stand-in for the shared randomness
+pub struct TestRandomness;
+impl RandomSource<H256> for TestRandomness {
+    fn random(subject: &[u8]) -> H256 {
+        BlakeTwo256::hash(subject)
+    }
 }
 
 /// Build genesis storage according to the mock runtime.
@@ -108,6 +145,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
             (3, 1000000),
             (4, 1000000),
             (5, 1000000),
+            // The chips treasury exists before the first chips are bought
+            (ChipsModuleId::get().into_account(), 1000000),
         ],
     }
     .assimilate_storage(&mut t)
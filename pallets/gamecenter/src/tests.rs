@@ -1,10 +1,16 @@
-use crate::mock::{
-    new_test_ext, Chips, GameCenter, GameGuessHashModule, Origin, System,
+use crate::mock::{new_test_ext, Chips, GameCenter, GameGuessHashModule, Origin, System, Test};
+use crate::{
+    CurrentGameinstances, DrawMap, Error, GameInstance, GameListsMigrated, HistoryGameinstances,
 };
+use codec::Encode;
 use frame_support::{
-    assert_ok,
-    traits::{OnFinalize, OnInitialize},
+    assert_noop, assert_ok,
+    traits::{OnFinalize, OnInitialize, OnRuntimeUpgrade},
+    StorageMap, StorageValue,
 };
+use frame_system::InitKind;
+use pallet_gametemplates_guess_hash::GameType;
+use sp_core::H256;
 
 // jump to block
 fn run_to_block(n: u64) {
@@ -12,7 +18,14 @@ fn run_to_block(n: u64) {
         GameGuessHashModule::on_finalize(System::block_number());
         GameCenter::on_finalize(System::block_number());
         System::on_finalize(System::block_number());
-        System::set_block_number(System::block_number() + 1);
+        // Every block needs a hash, the draw seed depends on it
+        let parent_hash = H256::from_low_u64_be(System::block_number());
+        System::initialize(
+            &(System::block_number() + 1),
+            &parent_hash,
+            &Default::default(),
+            InitKind::Full,
+        );
         System::on_initialize(System::block_number());
         GameGuessHashModule::on_initialize(System::block_number());
         GameCenter::on_initialize(System::block_number());
@@ -34,16 +47,17 @@ fn create_game() {
             Origin::signed(1),
             1,
             bet_next_few_block_num,
-            100
+            100,
+            vec![]
         ));
 
         // 【Then】Assert
 
         // check current gameinstances
-        let current1 = GameCenter::get_current_gameinstances(1);
+        let current1 = GameCenter::open_games(1);
         assert_eq!(current1.len(), 1);
         // check history gameinstances
-        let history1 = GameCenter::get_history_gameinstances(1);
+        let history1 = GameCenter::history_page(1, 0, 10);
         assert_eq!(history1.len(), 0);
 
         let game_info = current1.last().unwrap();
@@ -72,11 +86,12 @@ fn create_game() {
         run_to_block(40);
 
         // check current gameinstances
-        let current2 = GameCenter::get_current_gameinstances(1);
+        let current2 = GameCenter::open_games(1);
         assert_eq!(current2.len(), 0);
         // check history gameinstances
-        let history2 = GameCenter::get_history_gameinstances(1);
+        let history2 = GameCenter::history_page(1, 0, 10);
         assert_eq!(history2.len(), 1);
+        assert!(history2[0].game_over);
     });
 }
 
@@ -91,11 +106,16 @@ fn bet() {
         let _ = Chips::buy_chips(Origin::signed(2), 100);
         let bet_next_few_block_num = 10u32;
         // A user have a new game, game index = 1
-        let _ = GameGuessHashModule::create_game(Origin::signed(1), bet_next_few_block_num, 100);
+        let _ = GameGuessHashModule::create_game(
+            Origin::signed(1),
+            bet_next_few_block_num,
+            100,
+            GameType::OddEven,
+        );
 
         // 【When】Act
         // B user下注 100 chips/ bet single
-        assert_ok!(GameCenter::play_game(Origin::signed(2), 1, 100, 1));
+        assert_ok!(GameCenter::play_game(Origin::signed(2), 1, 1, 100, 1));
 
         // 【Then】Assert
         // check chip balance=0
@@ -119,3 +139,186 @@ fn bet() {
         assert_eq!(game_mode, 1);
     });
 }
+
+// 【Scenario】only registered templates can be played
+#[test]
+fn create_game_failed_when_template_not_registered() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+
+        // 【When】Act
+        // 【Then】Assert
+        assert_noop!(
+            GameCenter::create_game(Origin::signed(1), 2, 10, 100, vec![]),
+            Error::<Test>::TemplateNotRegistered
+        );
+        assert_noop!(
+            GameCenter::play_game(Origin::signed(1), 2, 1, 100, 1),
+            Error::<Test>::TemplateNotRegistered
+        );
+    });
+}
+
+// 【Scenario】a game waiting to be settled is settled with a bet count witness
+#[test]
+fn settle_game() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        assert_ok!(GameCenter::create_game(Origin::signed(1), 1, 2, 100, vec![]));
+        assert_ok!(GameCenter::play_game(Origin::signed(2), 1, 1, 10, 1));
+        // The game is drawn in block 4 and waits in the settlement queue
+        run_to_block(3);
+        <frame_system::BlockHash<Test>>::insert(3, H256::from_low_u64_be(3));
+        GameGuessHashModule::enqueue_drawn(4);
+
+        // 【When】Act
+        // 【Then】Assert
+        assert_noop!(
+            GameCenter::settle_game(Origin::signed(3), 1, 1, 0),
+            pallet_gametemplates_guess_hash::Error::<Test>::BetCountWitnessError
+        );
+        assert_ok!(GameCenter::settle_game(Origin::signed(3), 1, 1, 1));
+        assert_eq!(Chips::chips_map(2).unwrap().reserve, 0);
+        assert!(GameGuessHashModule::is_settled(1));
+        // The lobby moves it to the history in the next block
+        run_to_block(4);
+        assert_eq!(GameCenter::open_games(1).len(), 0);
+        assert_eq!(GameCenter::history_page(1, 0, 10).len(), 1);
+    });
+}
+
+// 【Scenario】the template options are passed to the game
+#[test]
+fn create_game_with_params() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+
+        // 【When】Act
+        assert_ok!(GameCenter::create_game(
+            Origin::signed(1),
+            1,
+            10,
+            1000,
+            GameType::ExactDigit.encode()
+        ));
+        assert_ok!(GameCenter::play_game(Origin::signed(2), 1, 1, 10, 15));
+
+        // 【Then】Assert
+        assert_eq!(GameGuessHashModule::game_type(1), GameType::ExactDigit);
+        assert_eq!(Chips::chips_map(2).unwrap().reserve, 10);
+
+        // The game moves to the history once it is settled
+        run_to_block(11);
+        assert_eq!(GameCenter::open_games(1).len(), 1);
+        run_to_block(13);
+        assert_eq!(GameCenter::open_games(1).len(), 0);
+        assert_eq!(GameCenter::history_page(1, 0, 10).len(), 1);
+    });
+}
+
+// 【Scenario】the play record lists the games a player created or played
+#[test]
+fn player_games() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+
+        // 【When】Act
+        for _ in 0..4 {
+            assert_ok!(GameCenter::create_game(
+                Origin::signed(1),
+                1,
+                10,
+                100,
+                vec![]
+            ));
+        }
+        assert_ok!(GameCenter::play_game(Origin::signed(2), 1, 4, 10, 1));
+        assert_ok!(GameCenter::play_game(Origin::signed(2), 1, 4, 10, 2));
+
+        // 【Then】Assert
+        // Only the latest 3 games of A are kept
+        assert_eq!(GameCenter::get_playmap(1), vec![(1, 2), (1, 3), (1, 4)]);
+        // B played game 4 twice
+        assert_eq!(GameCenter::get_playmap(2), vec![(1, 4)]);
+        let games = GameCenter::player_games(2);
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].1.game_instance_id, 4);
+        assert_eq!(GameCenter::open_games(1).len(), 4);
+    });
+}
+
+// 【Scenario】the history is paged newest first and only the latest games are kept
+#[test]
+fn history_page() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        for _ in 0..5 {
+            assert_ok!(GameCenter::create_game(
+                Origin::signed(1),
+                1,
+                2,
+                100,
+                vec![]
+            ));
+        }
+
+        // 【When】Act
+        run_to_block(10);
+
+        // 【Then】Assert
+        assert_eq!(GameCenter::history_count(1), 5);
+        // Games 1 and 2 are pruned
+        assert_eq!(GameCenter::game_instance(1, 1), None);
+        assert_eq!(GameCenter::game_instance(1, 2), None);
+        let ids = |games: Vec<crate::GameInstanceOf<Test>>| {
+            games
+                .into_iter()
+                .map(|game| game.game_instance_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(GameCenter::history_page(1, 0, 2)), vec![5, 4]);
+        assert_eq!(ids(GameCenter::history_page(1, 1, 2)), vec![3]);
+        assert!(GameCenter::history_page(1, 2, 2).is_empty());
+    });
+}
+
+// 【Scenario】the old game lists move to the keyed storage once and the old draw record is removed
+#[test]
+fn game_lists_migration() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        // An open game drawn in block 5 and a game over, stored the old way
+        let game = |game_instance_id, game_over| GameInstance {
+            game_instance_id,
+            owner: 1,
+            bet_block_number: 5,
+            chips_pool: 100,
+            game_over,
+        };
+        CurrentGameinstances::<Test>::insert(1, vec![game(2, false)]);
+        HistoryGameinstances::<Test>::insert(1, vec![game(1, true)]);
+        DrawMap::<Test>::insert(5, vec![2]);
+
+        // 【When】Act
+        GameCenter::on_runtime_upgrade();
+        // A later upgrade does not run it again
+        CurrentGameinstances::<Test>::insert(1, vec![game(3, false)]);
+        GameCenter::on_runtime_upgrade();
+
+        // 【Then】Assert
+        assert!(GameListsMigrated::get());
+        assert_eq!(GameCenter::open_games(1), vec![game(2, false)]);
+        assert_eq!(GameCenter::draw_queue(5), vec![(1, 2)]);
+        assert_eq!(GameCenter::history_page(1, 0, 10), vec![game(1, true)]);
+        assert!(!DrawMap::<Test>::contains_key(5));
+        assert!(!HistoryGameinstances::<Test>::contains_key(1));
+    });
+}
@@ -0,0 +1,100 @@
+//! Benchmarking setup for pallet-gametemplates-guess-hash
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite};
+use sp_runtime::SaturatedConversion;
+use sp_std::vec;
+
+#[allow(unused)]
+use crate::Module as Pallet;
+
+const SEED: u32 = 0;
+
+/// Give the account chips without going through the exchange
+fn fund<T: Config + pallet_chips::Config<ChipBalance = ChipBalance<T>>>(
+    who: &T::AccountId,
+    amount: ChipBalance<T>,
+) {
+    pallet_chips::ChipsMap::<T>::insert(
+        who,
+        pallet_chips::ChipsDetail {
+            balance: amount,
+            reserve: 0u32.into(),
+        },
+    );
+    // Backed by the native currency, the backing moves with the chips
+    pallet_chips::AccountCollateral::<T>::insert(
+        who,
+        pallet_chips::NATIVE_ASSET_ID,
+        pallet_chips::Backing {
+            chips: amount,
+            tokens: amount.saturated_into::<u128>(),
+        },
+    );
+}
+
+benchmarks! {
+    where_clause { where T: pallet_chips::Config<ChipBalance = ChipBalance<T>> }
+
+    // N games drawn in the same block join the settlement queue
+    enqueue_drawn {
+        let n in 1 .. 100;
+
+        let owner: T::AccountId = account("banker", 0, SEED);
+        fund::<T>(&owner, 1_000_000_000u32.into());
+        let now = <frame_system::Module<T>>::block_number();
+        for _ in 0 .. n {
+            Pallet::<T>::_create_game(&owner, 1, 1_000u32.into(), GameType::OddEven)?;
+        }
+        // The bet block hash is known in the draw block
+        let bet_block = now + 1u32.into();
+        <frame_system::BlockHash<T>>::insert(bet_block, T::Hashing::hash(b"bet block"));
+        let draw_block = bet_block + 1u32.into();
+    }: {
+        Pallet::<T>::enqueue_drawn(draw_block);
+    }
+    verify {
+        assert_eq!(Pallet::<T>::queued_games().len() as u32, n);
+        assert!(Pallet::<T>::draw_map(draw_block).is_empty());
+    }
+
+    // N queued games, M bets each
+    settle {
+        let n in 1 .. 20;
+        let m in 0 .. T::MaxBetsPerGame::get();
+
+        let owner: T::AccountId = account("banker", 0, SEED);
+        fund::<T>(&owner, 1_000_000_000u32.into());
+        let bettors: Vec<T::AccountId> = (0 .. m).map(|j| account("bettor", j, SEED)).collect();
+        for bettor in bettors.iter() {
+            fund::<T>(bettor, 1_000_000u32.into());
+        }
+
+        let now = <frame_system::Module<T>>::block_number();
+        for _ in 0 .. n {
+            let game_id = Pallet::<T>::_create_game(&owner, 1, 1_000_000u32.into(), GameType::OddEven)?;
+            for bettor in bettors.iter() {
+                Pallet::<T>::_bet(bettor, game_id, 1u32.into(), GAME_MODE_IS_SINGLE)?;
+            }
+        }
+
+        // The bet block hash is known in the draw block
+        let bet_block = now + 1u32.into();
+        <frame_system::BlockHash<T>>::insert(bet_block, T::Hashing::hash(b"bet block"));
+        let draw_block = bet_block + 1u32.into();
+        Pallet::<T>::enqueue_drawn(draw_block);
+    }: {
+        Pallet::<T>::process_settlement_queue(Weight::max_value());
+    }
+    verify {
+        assert!(Pallet::<T>::queued_games().is_empty());
+        assert_eq!(Pallet::<T>::queue_head(), Pallet::<T>::queue_tail());
+    }
+}
+
+impl_benchmark_test_suite!(
+    Pallet,
+    crate::mock::new_test_ext(),
+    crate::mock::Test,
+);
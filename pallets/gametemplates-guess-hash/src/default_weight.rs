@@ -15,9 +15,41 @@ impl crate::WeightInfo for () {
             .saturating_add(DbWeight::get().reads(1 as Weight))
             .saturating_add(DbWeight::get().writes(1 as Weight))
     }
-    fn on_finalize(count: u32) -> Weight {
-        (50_000_000 as Weight)
+    fn commit_seed() -> Weight {
+        (200_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn reveal_seed() -> Weight {
+        (200_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn cancel_game(count: u32) -> Weight {
+        (200_000_000 as Weight)
+            .saturating_add((50_000_000 as Weight).saturating_mul(count as Weight))
+            .saturating_add(DbWeight::get().reads(3 as Weight))
             .saturating_add(DbWeight::get().reads(count as Weight))
+            .saturating_add(DbWeight::get().writes(3 as Weight))
             .saturating_add(DbWeight::get().writes(count as Weight))
     }
+    fn enqueue_drawn(games: u32) -> Weight {
+        (5_000_000 as Weight)
+            .saturating_add((10_000_000 as Weight).saturating_mul(games as Weight))
+            .saturating_add(DbWeight::get().reads(2 as Weight))
+            .saturating_add(DbWeight::get().reads((4 as Weight).saturating_mul(games as Weight)))
+            .saturating_add(DbWeight::get().writes(2 as Weight))
+            .saturating_add(DbWeight::get().writes((4 as Weight).saturating_mul(games as Weight)))
+    }
+    fn settle(games: u32, bets: u32) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((150_000_000 as Weight).saturating_mul(games as Weight))
+            .saturating_add((60_000_000 as Weight).saturating_mul(bets as Weight))
+            .saturating_add(DbWeight::get().reads(2 as Weight))
+            .saturating_add(DbWeight::get().reads((10 as Weight).saturating_mul(games as Weight)))
+            .saturating_add(DbWeight::get().reads((8 as Weight).saturating_mul(bets as Weight)))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+            .saturating_add(DbWeight::get().writes((7 as Weight).saturating_mul(games as Weight)))
+            .saturating_add(DbWeight::get().writes((14 as Weight).saturating_mul(bets as Weight)))
+    }
 }
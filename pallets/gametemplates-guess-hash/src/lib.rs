@@ -1,22 +1,25 @@
-//! Game template 1 Guess Hash: Please guess the block hash, the last number is odd or even. Winner gets chips.
+//! Game template 1 Guess Hash: Please guess the last hex digit of the block hash (odd/even, big/small, exact digit or range). Winner gets chips.
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Decode, Encode};
 use frame_support::{
     debug, decl_error, decl_event, decl_module, decl_storage, dispatch, dispatch::Vec, ensure,
-    weights::Weight, Parameter,
+    traits::Get, weights::Weight, Parameter,
 };
-use frame_system::ensure_signed;
+use frame_system::{ensure_root, ensure_signed};
 use sp_runtime::{
-    traits::{AtLeast32Bit, Bounded, CheckedAdd},
-    DispatchError,
+    traits::{AtLeast32Bit, Bounded, CheckedAdd, Hash, Saturating},
+    DispatchError, RuntimeDebug,
 };
-
-extern crate alloc;
-use alloc::{format, str, string::*};
+use sp_std::convert::TryInto;
 
 // use chips trait
-use pallet_chips::{ChipsTrait, ChipsTransfer};
+use pallet_chips::{ChipsReason, ChipsTrait, ChipsTransfer};
+// use game template trait
+use pallet_gametemplates::{GameInstanceId, GameStatus, GameTemplate, TemplateId};
+
+pub mod seed;
+pub use crate::seed::{BabeVrf, CommitReveal, SeedSource};
 
 #[cfg(test)]
 mod mock;
@@ -24,6 +27,9 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
 mod default_weight;
 
 /// Game detail info
@@ -47,30 +53,114 @@ pub struct BetInfo<Account, GameIndex, Amount, GameMode> {
     pub game_id: GameIndex,
     /// bet amount
     pub amount: Amount,
-    /// game mode is odd or even(1 or 2)
+    /// the bet choice, its meaning depends on the game type (e.g. odd or even(1 or 2))
     pub game_mode: GameMode,
 }
 pub trait WeightInfo {
     fn create_game() -> Weight;
     fn bet() -> Weight;
-    fn on_finalize(count: u32) -> Weight;
+    fn commit_seed() -> Weight;
+    fn reveal_seed() -> Weight;
+    fn cancel_game(count: u32) -> Weight;
+    fn enqueue_drawn(games: u32) -> Weight;
+    fn settle(games: u32, bets: u32) -> Weight;
 }
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type GameIndex: Parameter + AtLeast32Bit + Bounded + Default + Copy;
     type WeightInfo: WeightInfo;
     type Chips: ChipsTrait + ChipsTransfer<Self::AccountId>;
+    /// Where the draw seed comes from
+    type SeedSource: SeedSource<Self>;
+    /// The maximum number of bets in one game
+    type MaxBetsPerGame: Get<u32>;
+    /// The maximum weight spent on settling games in one block
+    type SettlementWeightLimit: Get<Weight>;
+    /// The template id the game is registered under in the lobby
+    type TemplateId: Get<TemplateId>;
 }
 
 /// chips unit type
 type ChipBalance<T> = <<T as Config>::Chips as pallet_chips::ChipsTrait>::ChipBalance;
 
-/// Define the game mode
+/// Define the game mode (the bet choice)
 pub type GameMode = u8;
 /// Guess the odd number
 pub const GAME_MODE_IS_SINGLE: GameMode = 1;
 /// Guess the even number
 pub const GAME_MODE_IS_DOUBLE: GameMode = 2;
+/// Guess small (0~7)
+pub const GAME_MODE_IS_SMALL: GameMode = 1;
+/// Guess big (8~f)
+pub const GAME_MODE_IS_BIG: GameMode = 2;
+
+/// Every game is drawn as one hex digit (0~f)
+pub const DRAW_DIGITS: u8 = 16;
+
+/// The kind of game the banker opens, each with its own payout table
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum GameType {
+    /// Odd (1) or even (2), pays 1:1
+    OddEven,
+    /// Small (1: 0~7) or big (2: 8~f), pays 1:1
+    BigSmall,
+    /// The exact digit (0~15), pays 1:15
+    ExactDigit,
+    /// One of four ranges (1: 0~3, 2: 4~7, 3: 8~b, 4: c~f), pays 1:3
+    HexRange,
+}
+
+impl Default for GameType {
+    fn default() -> Self {
+        GameType::OddEven
+    }
+}
+
+impl GameType {
+    /// Chips won per chip bet, the principal is returned on top
+    pub fn payout(&self) -> u32 {
+        match self {
+            GameType::OddEven => 1,
+            GameType::BigSmall => 1,
+            GameType::ExactDigit => 15,
+            GameType::HexRange => 3,
+        }
+    }
+
+    /// Is the bet choice valid for this game type
+    pub fn is_valid_mode(&self, game_mode: GameMode) -> bool {
+        match self {
+            GameType::OddEven => {
+                game_mode == GAME_MODE_IS_SINGLE || game_mode == GAME_MODE_IS_DOUBLE
+            }
+            GameType::BigSmall => game_mode == GAME_MODE_IS_SMALL || game_mode == GAME_MODE_IS_BIG,
+            GameType::ExactDigit => game_mode < DRAW_DIGITS,
+            GameType::HexRange => (1..=4).contains(&game_mode),
+        }
+    }
+
+    /// Does the bet choice win when `digit` is drawn
+    pub fn is_win(&self, game_mode: GameMode, digit: u8) -> bool {
+        match self {
+            GameType::OddEven => {
+                if digit % 2 == 0 {
+                    game_mode == GAME_MODE_IS_DOUBLE
+                } else {
+                    game_mode == GAME_MODE_IS_SINGLE
+                }
+            }
+            GameType::BigSmall => {
+                if digit < 8 {
+                    game_mode == GAME_MODE_IS_SMALL
+                } else {
+                    game_mode == GAME_MODE_IS_BIG
+                }
+            }
+            GameType::ExactDigit => game_mode == digit,
+            GameType::HexRange => game_mode == digit / 4 + 1,
+        }
+    }
+}
 
 decl_storage! {
     trait Store for Module<T: Config> as GameGuessHashModule {
@@ -82,6 +172,26 @@ decl_storage! {
         pub GameCount get(fn game_count): T::GameIndex;
         /// Can use block num to check which games are about to be drawn.
         pub DrawMap get(fn draw_map): map hasher(blake2_128_concat) T::BlockNumber => Vec<T::GameIndex>;
+        /// Drawn games waiting to be settled, by position in the queue
+        pub SettlementQueue get(fn settlement_slot): map hasher(twox_64_concat) u32 => Option<T::GameIndex>;
+        /// Position of the next game to settle in `SettlementQueue`
+        pub QueueHead get(fn queue_head): u32;
+        /// Position the next drawn game is queued at in `SettlementQueue`
+        pub QueueTail get(fn queue_tail): u32;
+        /// Position of each game waiting in `SettlementQueue`
+        pub QueuePositions get(fn queue_position): map hasher(blake2_128_concat) T::GameIndex => Option<u32>;
+        /// Game type of each game, games created before game types existed are odd/even
+        pub GameTypes get(fn game_type): map hasher(blake2_128_concat) T::GameIndex => GameType;
+        /// Seed commitment of the banker (commit-reveal seed source)
+        pub SeedCommits get(fn seed_commits): map hasher(blake2_128_concat) T::GameIndex => Option<T::Hash>;
+        /// Seed secret revealed by the banker (commit-reveal seed source)
+        pub SeedReveals get(fn seed_reveals): map hasher(blake2_128_concat) T::GameIndex => Option<Vec<u8>>;
+        /// Seed of each game, taken when the game is drawn
+        pub GameSeeds get(fn game_seed): map hasher(blake2_128_concat) T::GameIndex => Option<T::Hash>;
+        /// Games cancelled and refunded instead of drawn
+        pub CancelledGames get(fn is_cancelled): map hasher(blake2_128_concat) T::GameIndex => bool;
+        /// Games already settled
+        pub SettledGames get(fn is_settled): map hasher(blake2_128_concat) T::GameIndex => bool;
     }
 }
 
@@ -92,16 +202,26 @@ decl_event!(
         ChipBalance = ChipBalance<T>,
         GameIndex = <T as Config>::GameIndex,
         BlockNumber = <T as frame_system::Config>::BlockNumber,
-        BlockHash = <T as frame_system::Config>::Hash,
+        Seed = <T as frame_system::Config>::Hash,
     {
-        /// Opening (banker, GameIndex, prize pool amount, betting block)
-        CreateGame(AccountId, GameIndex, ChipBalance, BlockNumber),
-        ///Place a bet (player, game ID, bet amount, 1: odd or 2: even, bet id)
+        /// Opening (banker, GameIndex, prize pool amount, betting block, game type)
+        CreateGame(AccountId, GameIndex, ChipBalance, BlockNumber, GameType),
+        ///Place a bet (player, game ID, bet amount, bet choice, bet id)
         Bet(AccountId, GameIndex, ChipBalance, GameMode, u32),
-        /// The player settles the winning amount (player, game ID, winning amount, betting ID, game result (1: odd or 2: even), drawn Block Hash)
-        BettorResult(AccountId, GameIndex, ChipBalance, u32, GameMode, BlockHash),
-        /// Game over (the dealer, the game ID, the total amount received by the dealer, the result of the game (1: odd or 2: even), drawn Block Hash)
-        GameOver(AccountId, GameIndex, ChipBalance, GameMode, BlockHash),
+        /// The banker committed the seed (banker, game ID, commitment)
+        SeedCommitted(AccountId, GameIndex, Seed),
+        /// The banker revealed the seed secret (banker, game ID, secret)
+        SeedRevealed(AccountId, GameIndex, Vec<u8>),
+        /// The player settles the winning amount (player, game ID, winning amount, betting ID, drawn digit (0~f), draw seed)
+        BettorResult(AccountId, GameIndex, ChipBalance, u32, u8, Seed),
+        /// Game over (the dealer, the game ID, the total amount received by the dealer, drawn digit (0~f), draw seed)
+        GameOver(AccountId, GameIndex, ChipBalance, u8, Seed),
+        /// A bet is refunded (player, game ID, refund amount, betting ID)
+        BetRefunded(AccountId, GameIndex, ChipBalance, u32),
+        /// The prize pool is refunded (banker, game ID, refund amount)
+        PoolRefunded(AccountId, GameIndex, ChipBalance),
+        /// The game is cancelled, by the banker or root, or because it has no draw seed (game ID)
+        GameCancelled(GameIndex),
     }
 );
 
@@ -116,6 +236,18 @@ decl_error! {
         TransferError,
         BetAmountLimitError,	// The bet amount reaches the upper limit
         GameOver,
+        PermissionDenied,
+        SeedNotCommitted,	// The banker has not committed the seed yet
+        SeedAlreadyCommitted,
+        SeedAlreadyRevealed,
+        SeedMismatch,	// The secret does not match the commitment
+        GameAlreadyHasBets,
+        RevealTooLate,
+        GameCancelled,
+        BetCountWitnessError,	// The bet count witness is lower than the number of bets
+        TooManyBets,	// The game reaches the maximum number of bets
+        InvalidGameParams,	// The lobby params do not decode to a game type
+        GameNotQueued,	// The game is not waiting to be settled
     }
 }
 
@@ -126,9 +258,9 @@ decl_module! {
 
         /// create guess hash game
         #[weight = T::WeightInfo::create_game()]
-        pub fn create_game(origin, bet_next_few_block: u32, amount: ChipBalance<T>) -> dispatch::DispatchResult {
+        pub fn create_game(origin, bet_next_few_block: u32, amount: ChipBalance<T>, game_type: GameType) -> dispatch::DispatchResult {
             let sender = ensure_signed(origin)?;
-            Self::_create_game(&sender, bet_next_few_block, amount)?;
+            Self::_create_game(&sender, bet_next_few_block, amount, game_type)?;
             Ok(())
         }
 
@@ -140,90 +272,318 @@ decl_module! {
             Ok(())
         }
 
-        fn on_initialize(_now: T::BlockNumber) -> Weight {
-            T::WeightInfo::on_finalize(2u32)
+        /// The banker commits `hash(secret)` before anyone bets (commit-reveal seed source)
+        #[weight = T::WeightInfo::commit_seed()]
+        pub fn commit_seed(origin, game_id: T::GameIndex, commitment: T::Hash) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Games::<T>::contains_key(game_id), Error::<T>::GameIsNotExist);
+            let game_info = Self::game_list(game_id);
+            ensure!(game_info.owner == sender, Error::<T>::PermissionDenied);
+            ensure!(!SeedCommits::<T>::contains_key(game_id), Error::<T>::SeedAlreadyCommitted);
+            // Nobody may have bet without seeing the commitment
+            ensure!(Self::bet_list(game_id).is_empty(), Error::<T>::GameAlreadyHasBets);
+
+            SeedCommits::<T>::insert(game_id, commitment);
+            Self::deposit_event(RawEvent::SeedCommitted(sender, game_id, commitment));
+            Ok(())
         }
 
-        /// When the target block is generated, the game chips are settled according to the game rules on the chain
-        fn on_finalize(now: T::BlockNumber) {
-            let game_id_list = Self::draw_map(now);
-            // ready draw
-            if !game_id_list.is_empty() {
-                for game_id in game_id_list {
-
-                    // 前一筆交易的block hash
-                    let block_hash = <frame_system::Module<T>>::block_hash(now-1u32.into());
-                    let game_info = Self::game_list(&game_id);
-
-                    // get winning mode (odd or even)
-                    let result_game_mode = Self::get_game_result(block_hash);
-
-                    // Get betting record
-                    let bet_list = Self::bet_list(&game_id);
-
-                    // -----------------------Reward distribution-----------------------
-                    // Total prize pool
-                    let mut owner_pool = game_info.amount;
-                    // The total amount the owner will receive
-                    let mut owner_get_total_amount = game_info.amount;
-
-                    // owner
-                    let owner = game_info.owner;
-                    for (k, v) in bet_list.iter().enumerate() {
-                        // winner
-                        if v.game_mode == result_game_mode {
-                            // Return the bettor's principal
-                            T::Chips::unreserve(&v.user, v.amount).map_err(|err| debug::error!("err: {:?}", err)).ok();
-                            // Owner issues rewards to punters
-                            T::Chips::repatriate_reserved(&owner, &v.user, v.amount).map_err(|err| debug::error!("err: {:?}", err)).ok();
-
-                            // Notify the punter to get the amount
-                            Self::deposit_event(RawEvent::BettorResult(v.user.clone(), game_id, v.amount * 2u32.into(), k as u32, result_game_mode, block_hash));
-
-                            // Calculate the remaining amount of the prize pool
-                            owner_pool-=v.amount;
-
-                            // Owner lost, total get amount decreased
-                            owner_get_total_amount-=v.amount;
-                        }
-                        // loser
-                        else{
-                            // The bettor issues a reward to the owner
-                            T::Chips::repatriate_reserved(&v.user, &owner, v.amount).map_err(|err| debug::error!("err: {:?}", err)).ok();
-                            // The owner wins, the total get amount decreases
-                            owner_get_total_amount+=v.amount;
-                        }
-
-                    }
-                    // The remaining amount of the prize pool is returned to the owner
-                    T::Chips::unreserve(&owner, owner_pool).map_err(|err| debug::error!("err: {:?}", err)).ok();
-
-                    // Send notification
-                    Self::deposit_event(RawEvent::GameOver(owner, game_id, owner_get_total_amount, result_game_mode, block_hash));
-                }
+        /// The banker reveals the secret, at the latest in the bet block (commit-reveal seed source)
+        #[weight = T::WeightInfo::reveal_seed()]
+        pub fn reveal_seed(origin, game_id: T::GameIndex, secret: Vec<u8>) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Games::<T>::contains_key(game_id), Error::<T>::GameIsNotExist);
+            let game_info = Self::game_list(game_id);
+            ensure!(game_info.owner == sender, Error::<T>::PermissionDenied);
+            let commitment = Self::seed_commits(game_id).ok_or(Error::<T>::SeedNotCommitted)?;
+            ensure!(!SeedReveals::<T>::contains_key(game_id), Error::<T>::SeedAlreadyRevealed);
+            ensure!(T::Hashing::hash(&secret) == commitment, Error::<T>::SeedMismatch);
+            // Once the bet block hash is known the banker could decide whether to reveal
+            let now_block_number = <frame_system::Module<T>>::block_number();
+            ensure!(now_block_number <= game_info.bet_block_number, Error::<T>::RevealTooLate);
+
+            SeedReveals::<T>::insert(game_id, secret.clone());
+            Self::deposit_event(RawEvent::SeedRevealed(sender, game_id, secret));
+            Ok(())
+        }
+
+        /// The banker or root cancels the game before the bet block, all the chips are returned
+        #[weight = T::WeightInfo::cancel_game(*bet_count)]
+        pub fn cancel_game(origin, game_id: T::GameIndex, bet_count: u32) -> dispatch::DispatchResult {
+            let maybe_sender = ensure_signed(origin.clone()).ok();
+            if maybe_sender.is_none() {
+                ensure_root(origin)?;
             }
+            ensure!(Games::<T>::contains_key(game_id), Error::<T>::GameIsNotExist);
+            ensure!(!Self::is_cancelled(game_id), Error::<T>::GameCancelled);
+            let game_info = Self::game_list(game_id);
+            if let Some(sender) = maybe_sender {
+                ensure!(game_info.owner == sender, Error::<T>::PermissionDenied);
+            }
+            let now_block_number = <frame_system::Module<T>>::block_number();
+            ensure!(now_block_number < game_info.bet_block_number, Error::<T>::GameOver);
+            ensure!(Self::bet_list(game_id).len() as u32 <= bet_count, Error::<T>::BetCountWitnessError);
+
+            // The game will not be drawn
+            let draw_block_number = game_info.bet_block_number + 1u32.into();
+            <DrawMap<T>>::mutate(draw_block_number, |game_id_list| game_id_list.retain(|id| *id != game_id));
+
+            Self::refund_game(game_id);
+            Ok(())
+        }
+
+        /// The settlement queue is settled according to the game rules on the chain as far as the
+        /// weight limit allows. The weight of the games drawn at the end of the block is charged
+        /// here as well.
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let drawing = <DrawMap<T>>::decode_len(now).unwrap_or(0) as u32;
+            let queued = T::WeightInfo::enqueue_drawn(drawing).saturating_add(T::DbWeight::get().reads(1));
+            let limit = T::SettlementWeightLimit::get().saturating_sub(queued);
+            queued.saturating_add(Self::process_settlement_queue(limit))
+        }
+
+        /// When the target block is generated, the due games take their seed and join the
+        /// settlement queue. This happens at the end of the block, once the randomness of the
+        /// block is known.
+        fn on_finalize(now: T::BlockNumber) {
+            Self::enqueue_drawn(now);
         }
     }
 }
 
 impl<T: Config> Module<T> {
+    /// Take the seed of the games drawn in this block and append them to the settlement queue
+    pub fn enqueue_drawn(now: T::BlockNumber) -> Weight {
+        let drawn = <DrawMap<T>>::take(now);
+        if drawn.is_empty() {
+            return T::WeightInfo::enqueue_drawn(0);
+        }
+
+        let mut tail = Self::queue_tail();
+        for game_id in drawn.iter() {
+            // Without a seed the game is refunded when it is settled
+            if let Some(seed) = T::SeedSource::draw_seed(*game_id) {
+                <GameSeeds<T>>::insert(game_id, seed);
+            }
+            <SettlementQueue<T>>::insert(tail, game_id);
+            <QueuePositions<T>>::insert(game_id, tail);
+            tail = tail.wrapping_add(1);
+        }
+        QueueTail::put(tail);
+        T::WeightInfo::enqueue_drawn(drawn.len() as u32)
+    }
+
+    /// Settle the queued games in order. The games that do not fit in `limit` are carried over
+    /// to the next block. Every position read is charged, including the ones left empty by games
+    /// settled on demand and the game that does not fit.
+    pub fn process_settlement_queue(limit: Weight) -> Weight {
+        let mut head = Self::queue_head();
+        let tail = Self::queue_tail();
+        let mut games = 0u32;
+        let mut bets = 0u32;
+        // Reads of positions that were not settled
+        let mut skipped = 0u32;
+        let spent = |games: u32, bets: u32, skipped: u32| {
+            T::WeightInfo::settle(games, bets).saturating_add(T::DbWeight::get().reads(skipped as Weight))
+        };
+
+        while head != tail {
+            // Always read one position so the queue keeps moving
+            if (games > 0 || skipped > 0) && spent(games, bets, skipped + 1) > limit {
+                break;
+            }
+            let game_id = match <SettlementQueue<T>>::get(head) {
+                Some(game_id) => game_id,
+                None => {
+                    // Settled on demand
+                    skipped += 1;
+                    head = head.wrapping_add(1);
+                    continue;
+                }
+            };
+            let bet_count = <BetList<T>>::decode_len(game_id).unwrap_or(0) as u32;
+            // Always settle one game so the queue keeps moving
+            if games > 0 && spent(games + 1, bets + bet_count, skipped) > limit {
+                // The position and the bet count were read
+                skipped += 2;
+                break;
+            }
+            <SettlementQueue<T>>::remove(head);
+            <QueuePositions<T>>::remove(game_id);
+            head = head.wrapping_add(1);
+            Self::settle_game(game_id, Self::bet_list(game_id));
+            games += 1;
+            bets += bet_count;
+        }
+
+        QueueHead::put(head);
+        spent(games, bets, skipped)
+    }
+
+    /// Games waiting in the settlement queue, in order
+    pub fn queued_games() -> Vec<T::GameIndex> {
+        let mut games = Vec::new();
+        let mut position = Self::queue_head();
+        let tail = Self::queue_tail();
+        while position != tail {
+            if let Some(game_id) = Self::settlement_slot(position) {
+                games.push(game_id);
+            }
+            position = position.wrapping_add(1);
+        }
+        games
+    }
+
+    /// Draw the game and pay out the winners
+    fn settle_game(
+        game_id: T::GameIndex,
+        bet_list: Vec<BetInfo<T::AccountId, T::GameIndex, ChipBalance<T>, GameMode>>,
+    ) {
+        let game_info = Self::game_list(&game_id);
+
+        // Without a seed nobody wins, return all the chips
+        let seed = match Self::game_seed(game_id) {
+            Some(seed) => seed,
+            None => {
+                debug::warn!("guess hash game {:?} has no draw seed, refund", game_id);
+                Self::refund_game(game_id);
+                return;
+            }
+        };
+
+        // get the drawn digit and the payout table of the game
+        let result_digit = Self::get_game_result(seed);
+        let game_type = Self::game_type(&game_id);
+        let payout: ChipBalance<T> = game_type.payout().into();
+
+        // -----------------------Reward distribution-----------------------
+        // Total prize pool
+        let mut owner_pool = game_info.amount;
+        // The total amount the owner will receive
+        let mut owner_get_total_amount = game_info.amount;
+
+        // owner
+        let owner = game_info.owner;
+        let reason = Self::chips_reason(game_id);
+        for (k, v) in bet_list.iter().enumerate() {
+            // winner
+            if game_type.is_win(v.game_mode, result_digit) {
+                let winnings = v.amount.saturating_mul(payout);
+                // Return the bettor's principal
+                T::Chips::unreserve(&v.user, v.amount, reason)
+                    .map_err(|err| debug::error!("err: {:?}", err))
+                    .ok();
+                // Owner issues rewards to punters
+                T::Chips::repatriate_reserved(&owner, &v.user, winnings, reason)
+                    .map_err(|err| debug::error!("err: {:?}", err))
+                    .ok();
+
+                // Notify the punter to get the amount
+                Self::deposit_event(RawEvent::BettorResult(
+                    v.user.clone(),
+                    game_id,
+                    v.amount.saturating_add(winnings),
+                    k as u32,
+                    result_digit,
+                    seed,
+                ));
+
+                // Calculate the remaining amount of the prize pool
+                owner_pool = owner_pool.saturating_sub(winnings);
+
+                // Owner lost, total get amount decreased
+                owner_get_total_amount = owner_get_total_amount.saturating_sub(winnings);
+            }
+            // loser
+            else {
+                // The bettor issues a reward to the owner
+                T::Chips::repatriate_reserved(&v.user, &owner, v.amount, reason)
+                    .map_err(|err| debug::error!("err: {:?}", err))
+                    .ok();
+                // The owner wins, the total get amount decreases
+                owner_get_total_amount += v.amount;
+            }
+        }
+        <SettledGames<T>>::insert(game_id, true);
+
+        // The remaining amount of the prize pool is returned to the owner
+        T::Chips::unreserve(&owner, owner_pool, reason)
+            .map_err(|err| debug::error!("err: {:?}", err))
+            .ok();
+
+        // Send notification
+        Self::deposit_event(RawEvent::GameOver(
+            owner,
+            game_id,
+            owner_get_total_amount,
+            result_digit,
+            seed,
+        ));
+    }
+
+    /// Chips moved by the game are reported with the template id and game id
+    fn chips_reason(game_id: T::GameIndex) -> ChipsReason {
+        // `next_game_id` only hands out ids that fit
+        ChipsReason::Game(T::TemplateId::get(), game_id.try_into().unwrap_or_default())
+    }
+
+    /// Return the chips of every bettor and the prize pool, then mark the game cancelled
+    fn refund_game(game_id: T::GameIndex) {
+        let game_info = Self::game_list(game_id);
+        let reason = Self::chips_reason(game_id);
+        for (k, v) in Self::bet_list(game_id).iter().enumerate() {
+            T::Chips::unreserve(&v.user, v.amount, reason)
+                .map_err(|err| debug::error!("err: {:?}", err))
+                .ok();
+            Self::deposit_event(RawEvent::BetRefunded(
+                v.user.clone(),
+                game_id,
+                v.amount,
+                k as u32,
+            ));
+        }
+        T::Chips::unreserve(&game_info.owner, game_info.amount, reason)
+            .map_err(|err| debug::error!("err: {:?}", err))
+            .ok();
+        Self::deposit_event(RawEvent::PoolRefunded(
+            game_info.owner,
+            game_id,
+            game_info.amount,
+        ));
+
+        <CancelledGames<T>>::insert(game_id, true);
+        Self::deposit_event(RawEvent::GameCancelled(game_id));
+    }
+
     /// check will it exceed the compensable amount of the prize pool after placing a bet?
-    fn check_bet_over_pool(game_id: T::GameIndex, bet_amount: ChipBalance<T>) -> bool {
+    fn check_bet_over_pool(
+        game_id: T::GameIndex,
+        bet_amount: ChipBalance<T>,
+        game_mode: GameMode,
+    ) -> bool {
         let game_info = Self::game_list(game_id);
         let bet_list = Self::bet_list(game_id);
+        let game_type = Self::game_type(game_id);
+        let payout: ChipBalance<T> = game_type.payout().into();
 
         // Maximum Compensable Amount of Prize Pool
         let pool_total = game_info.amount;
 
-        // Total bet amount (including quasi bet amount)
-        let mut bet_total: ChipBalance<T> = 0u32.into();
-        for v in bet_list {
-            bet_total += v.amount;
-        }
-        bet_total += bet_amount;
+        // The prize pool has to pay the winners of whichever digit is drawn (including quasi bet)
+        (0..DRAW_DIGITS).any(|digit| {
+            let mut payout_total: ChipBalance<T> = 0u32.into();
+            for v in bet_list.iter() {
+                if game_type.is_win(v.game_mode, digit) {
+                    payout_total = payout_total.saturating_add(v.amount.saturating_mul(payout));
+                }
+            }
+            if game_type.is_win(game_mode, digit) {
+                payout_total = payout_total.saturating_add(bet_amount.saturating_mul(payout));
+            }
 
-        // Return true if it will exceed the prize pool
-        pool_total < bet_total
+            // Return true if it will exceed the prize pool
+            pool_total < payout_total
+        })
     }
 
     /// Get new game_id
@@ -232,7 +592,13 @@ impl<T: Config> Module<T> {
         if game_id == None {
             return Err(Error::<T>::StorageOverflow.into());
         }
-        Ok(game_id.unwrap())
+        let game_id = game_id.unwrap();
+        // The lobby and the chips ledger know the game by a `GameInstanceId`
+        ensure!(
+            TryInto::<GameInstanceId>::try_into(game_id).is_ok(),
+            Error::<T>::StorageOverflow
+        );
+        Ok(game_id)
     }
 
     /// create guess hash game
@@ -240,6 +606,7 @@ impl<T: Config> Module<T> {
         sender: &T::AccountId,
         bet_next_few_block: u32,
         _amount: ChipBalance<T>,
+        game_type: GameType,
     ) -> sp_std::result::Result<T::GameIndex, DispatchError> {
         // Current transaction block number
         let _block_number = <frame_system::Module<T>>::block_number();
@@ -252,7 +619,18 @@ impl<T: Config> Module<T> {
             Error::<T>::StorageOverflow
         );
         let _bet_block_number = new_block_num.unwrap();
-        
+
+        // The block where the reward is distributed (the next block mined by the betting block is drawn)
+        let new_draw_block_number = _bet_block_number.checked_add(&1u32.into());
+        ensure!(
+            new_draw_block_number != None,
+            Error::<T>::StorageOverflow
+        );
+        let draw_block_number = new_draw_block_number.unwrap();
+
+        // Pledge first, nothing is written if it fails
+        T::Chips::reserve(&sender, _amount, Self::chips_reason(game_id)).map_err(|_| Error::<T>::TransferError)?;
+
         let game_info = GameInfo {
             owner: sender.clone(),
             block_number: _block_number,
@@ -260,28 +638,20 @@ impl<T: Config> Module<T> {
             amount: _amount,
         };
         <Games<T>>::insert(&game_id, game_info);
+        <GameTypes<T>>::insert(&game_id, game_type);
         <GameCount<T>>::put(game_id);
 
-        // The block where the reward is distributed (the next block mined by the betting block is drawn)
-        let new_draw_block_number = _bet_block_number.checked_add(&1u32.into());
-        ensure!(
-            new_draw_block_number != None,
-            Error::<T>::StorageOverflow
-        );
-        let draw_block_number = new_draw_block_number.unwrap();
         let mut game_id_list = <DrawMap<T>>::get(&draw_block_number);
         game_id_list.insert(game_id_list.len(), game_id);
         <DrawMap<T>>::insert(&draw_block_number, game_id_list);
 
-        // Pledge now
-        T::Chips::reserve(&sender, _amount).map_err(|_| Error::<T>::TransferError)?;
-
         // Notification of create game
         Self::deposit_event(RawEvent::CreateGame(
             sender.clone(),
             game_id,
             _amount,
             _bet_block_number,
+            game_type,
         ));
         Ok(game_id)
     }
@@ -307,14 +677,30 @@ impl<T: Config> Module<T> {
             Error::<T>::GameOver
         );
 
-        // Check the bet amount
-        let is_over_pool = Self::check_bet_over_pool(_game_id, value);
-        ensure!(!is_over_pool, Error::<T>::BetAmountLimitError);
+        // Check that the game is not cancelled
+        ensure!(!Self::is_cancelled(_game_id), Error::<T>::GameCancelled);
+
+        // Check that the seed source is ready
+        ensure!(
+            T::SeedSource::accepts_bets(_game_id),
+            Error::<T>::SeedNotCommitted
+        );
+
+        // Check the number of bets
+        ensure!(
+            (Self::bet_list(_game_id).len() as u32) < T::MaxBetsPerGame::get(),
+            Error::<T>::TooManyBets
+        );
 
         // Check game mode
-        if _game_mode != GAME_MODE_IS_DOUBLE && _game_mode != GAME_MODE_IS_SINGLE {
-            return Err(Error::<T>::GameModeIsNotExist.into());
-        }
+        ensure!(
+            Self::game_type(_game_id).is_valid_mode(_game_mode),
+            Error::<T>::GameModeIsNotExist
+        );
+
+        // Check the bet amount
+        let is_over_pool = Self::check_bet_over_pool(_game_id, value, _game_mode);
+        ensure!(!is_over_pool, Error::<T>::BetAmountLimitError);
 
         // define new betting record
         let new_bet_info = BetInfo {
@@ -324,15 +710,15 @@ impl<T: Config> Module<T> {
             game_mode: _game_mode,
         };
 
+        // Pledge first, nothing is written if it fails
+        T::Chips::reserve(&sender, value, Self::chips_reason(_game_id)).map_err(|err| err)?;
+
         // Record new betting records
         let mut bet_list = BetList::<T>::get(_game_id); // Get all betting records
         let bet_index = bet_list.len(); // New bet id
         bet_list.insert(bet_index, new_bet_info); // insert records
         <BetList<T>>::insert(&_game_id, bet_list);
 
-        // Pledge now
-        T::Chips::reserve(&sender, value).map_err(|err| err)?;
-
         // Notification of bet record
         Self::deposit_event(RawEvent::Bet(
             sender.clone(),
@@ -344,69 +730,76 @@ impl<T: Config> Module<T> {
         Ok(())
     }
 
-    /// Get the result
-    fn get_game_result(block_hash: T::Hash) -> GameMode {
-        let block_hash_char: String = format!("{:?}", block_hash);
-        let char_vec: Vec<char> = block_hash_char.chars().collect();
-
-        let mut is_have_ans = false;
-        let mut ans: u8 = 0;
-        let mut n = char_vec.len() - 1;
-        while !is_have_ans {
-            // string to u8
-            let num = char_vec[n].to_string().parse::<u8>().ok();
-            if num != None {
-                ans = num.unwrap();
-                is_have_ans = true;
-            } else {
-            }
-            n -= 1;
-        }
-        // even
-        if (ans % 2) == 0 {
-            GAME_MODE_IS_DOUBLE
-        }
-        // odd
-        else {
-            GAME_MODE_IS_SINGLE
-        }
+    /// Get the result: the last hex digit of the seed
+    fn get_game_result(seed: T::Hash) -> u8 {
+        seed.as_ref().last().map_or(0, |b| b & 0x0f)
     }
 }
-pub trait GuessHashTrait {}
-
-impl<T: Config> GuessHashTrait for Module<T> {}
+/// Provided to the lobby (create game / play / settle / status)
+impl<T: Config> GameTemplate<T::AccountId, ChipBalance<T>> for Module<T> {
+    fn template_id() -> TemplateId {
+        T::TemplateId::get()
+    }
 
-pub trait GuessHashFunc<AccountId, GameIndex, ChipBalance>: GuessHashTrait {
-    fn create_game(
-        sender: &AccountId,
-        bet_next_few_block: u32,
-        amount: ChipBalance,
-    ) -> sp_std::result::Result<GameIndex, DispatchError>;
-    fn bet(
-        sender: &AccountId,
-        game_id: GameIndex,
-        value: ChipBalance,
-        game_mode: GameMode,
-    ) -> dispatch::DispatchResult;
-}
-/// Provided to other modules（new game/ bet）
-impl<T: Config> GuessHashFunc<T::AccountId, T::GameIndex, ChipBalance<T>> for Module<T> {
-    fn create_game(
-        sender: &T::AccountId,
+    /// `params` is the encoded game type, odd/even if empty
+    fn create(
+        owner: &T::AccountId,
         bet_next_few_block: u32,
         amount: ChipBalance<T>,
-    ) -> sp_std::result::Result<T::GameIndex, DispatchError> {
-        let game_id = Self::_create_game(sender, bet_next_few_block, amount)?;
-        Ok(game_id)
+        params: Vec<u8>,
+    ) -> sp_std::result::Result<GameInstanceId, DispatchError> {
+        let game_type = if params.is_empty() {
+            GameType::default()
+        } else {
+            GameType::decode(&mut &params[..]).map_err(|_| Error::<T>::InvalidGameParams)?
+        };
+        let game_id = Self::_create_game(owner, bet_next_few_block, amount, game_type)?;
+        game_id.try_into().map_err(|_| Error::<T>::StorageOverflow.into())
     }
 
-    fn bet(
-        sender: &T::AccountId,
-        game_id: T::GameIndex,
-        value: ChipBalance<T>,
-        game_mode: GameMode,
+    fn play(
+        who: &T::AccountId,
+        game_id: GameInstanceId,
+        amount: ChipBalance<T>,
+        choice: u8,
     ) -> dispatch::DispatchResult {
-        Self::_bet(sender, game_id, value, game_mode)?;
+        Self::_bet(who, game_id.into(), amount, choice)
+    }
+
+    /// Settle a game waiting in the settlement queue right away
+    fn settle(game_id: GameInstanceId, bet_count: u32) -> dispatch::DispatchResult {
+        let game_id: T::GameIndex = game_id.into();
+        let position = Self::queue_position(game_id).ok_or(Error::<T>::GameNotQueued)?;
+        let bet_list = Self::bet_list(game_id);
+        ensure!(bet_list.len() as u32 <= bet_count, Error::<T>::BetCountWitnessError);
+
+        // The queue skips the empty position
+        <SettlementQueue<T>>::remove(position);
+        <QueuePositions<T>>::remove(game_id);
+        Self::settle_game(game_id, bet_list);
         Ok(())
     }
+
+    fn settle_weight(bet_count: u32) -> Weight {
+        T::WeightInfo::settle(1, bet_count.min(T::MaxBetsPerGame::get()))
+    }
+
+    fn status(game_id: GameInstanceId) -> Option<GameStatus> {
+        let game_id: T::GameIndex = game_id.into();
+        if !Games::<T>::contains_key(game_id) {
+            return None;
+        }
+        if Self::is_cancelled(game_id) {
+            return Some(GameStatus::Cancelled);
+        }
+        if Self::is_settled(game_id) {
+            return Some(GameStatus::Settled);
+        }
+        let now_block_number = <frame_system::Module<T>>::block_number();
+        if now_block_number < Self::game_list(game_id).bet_block_number {
+            Some(GameStatus::Betting)
+        } else {
+            Some(GameStatus::Drawing)
+        }
+    }
 }
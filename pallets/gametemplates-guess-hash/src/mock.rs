@@ -1,12 +1,17 @@
 use crate as pallet_gametemplates_guess_hash;
 use balances;
-use frame_support::parameter_types;
+use frame_support::{
+    parameter_types,
+    traits::{Get, Randomness},
+    weights::Weight,
+};
 use frame_system as system;
 use pallet_chips;
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    traits::{AccountIdConversion, BlakeTwo256, Hash, IdentityLookup},
+    ModuleId,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -23,6 +28,7 @@ frame_support::construct_runtime!(
         Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
         Chips: pallet_chips::{Module, Call, Storage, Event<T>},
         GameGuessHashModule: pallet_gametemplates_guess_hash::{Module, Call, Storage, Event<T>},
+        SubgameRandomness: pallet_subgame_randomness::{Module, Call, Storage},
     }
 );
 
@@ -70,23 +76,55 @@ impl balances::Config for Test {
 }
 
 parameter_types! {
-    pub const MasterAddress: u32 = 3;
+    pub const ChipsModuleId: ModuleId = ModuleId(*b"sg/chips");
+    pub const MaxLedgerEntries: u32 = 3;
 }
 /// Chips module
 impl pallet_chips::Config for Test {
     type Event = Event;
     type Balances = balances::Module<Self>;
     type ChipBalance = u128;
-    type MasterAddress = MasterAddress;
+    type ModuleId = ChipsModuleId;
     type WeightInfo = ();
+    type Assets = ();
+    type MaxLedgerEntries = MaxLedgerEntries;
+    type RateOrigin = frame_system::EnsureRoot<u64>;
 }
 
+parameter_types! {
+    pub const MaxBetsPerGame: u32 = 10;
+    pub const GuessHashTemplateId: u32 = 1;
+}
+/// One full game fits in a block
+pub struct SettlementWeightLimit;
+impl Get<Weight> for SettlementWeightLimit {
+    fn get() -> Weight {
+        <() as pallet_gametemplates_guess_hash::WeightInfo>::settle(1, MaxBetsPerGame::get())
+    }
+}
 /// Game module
 impl pallet_gametemplates_guess_hash::Config for Test {
     type Event = Event;
     type GameIndex = u32;
     type WeightInfo = ();
     type Chips = Chips;
+    type SeedSource = pallet_gametemplates_guess_hash::BabeVrf<SubgameRandomness>;
+    type MaxBetsPerGame = MaxBetsPerGame;
+    type SettlementWeightLimit = SettlementWeightLimit;
+    type TemplateId = GuessHashTemplateId;
+}
+
+/// Deterministic stand-in for the BABE randomness
+pub struct TestRandomness;
+impl Randomness<H256> for TestRandomness {
+    fn random(subject: &[u8]) -> H256 {
+        BlakeTwo256::hash(subject)
+    }
+}
+
+/// The shared randomness the games are drawn from
+impl pallet_subgame_randomness::Config for Test {
+    type Randomness = TestRandomness;
 }
 
 /// Build genesis storage according to the mock runtime.
@@ -103,6 +141,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
             (3, 1000000),
             (4, 1000000),
             (5, 1000000),
+            // The chips treasury exists before the first chips are bought
+            (ChipsModuleId::get().into_account(), 1000000),
         ],
     }
     .assimilate_storage(&mut t)
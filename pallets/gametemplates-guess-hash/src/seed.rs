@@ -0,0 +1,72 @@
+//! # Draw Seed Interface
+//!
+//! The seed decides the result of a Guess Hash game. Where the seed comes from is chosen by the
+//! runtime through `Config::SeedSource`. The seed is taken once, when the game is drawn at the end
+//! of the block after the bet block, and stored with the game. The game is settled from the stored
+//! seed, so settling later or on demand does not change the result.
+//!
+//! Two sources are provided:
+//! * [`CommitReveal`]: the banker commits `hash(secret)` before any bet is placed and reveals the
+//!   secret no later than the bet block. The seed is `hash(secret, hash of the bet block)`.
+//! * [`BabeVrf`]: the seed is taken from the shared `RandomSource` for the game id, mixed with the
+//!   hash of the bet block. In the runtime the shared source is backed by the BABE VRF output of
+//!   the draw block, which is not known before the bets close. The author of the draw block can
+//!   compute it in advance and withhold the block, but cannot choose it. A block authored in a
+//!   secondary plain slot carries no VRF output and falls back to the epoch randomness, which is
+//!   known in advance.
+//!
+//! The seed is published in the `GameOver` event so anyone can verify the draw.
+
+use codec::Encode;
+use common::RandomSource;
+use sp_runtime::traits::Hash;
+use sp_std::marker::PhantomData;
+
+use crate::{Config, Module};
+
+/// Provide the seed used to draw a game
+pub trait SeedSource<T: Config> {
+    /// Can the game accept bets yet
+    fn accepts_bets(_game_id: T::GameIndex) -> bool {
+        true
+    }
+    /// The seed of the game, taken once when the game is drawn. `None` if it cannot be produced
+    fn draw_seed(game_id: T::GameIndex) -> Option<T::Hash>;
+}
+
+/// Hash of the bet block, `None` if it is not known (yet)
+fn bet_block_hash<T: Config>(game_id: T::GameIndex) -> Option<T::Hash> {
+    let game_info = Module::<T>::game_list(game_id);
+    let block_hash = <frame_system::Module<T>>::block_hash(game_info.bet_block_number);
+    if block_hash == T::Hash::default() {
+        return None;
+    }
+    Some(block_hash)
+}
+
+/// Banker commit-reveal seed
+pub struct CommitReveal;
+
+impl<T: Config> SeedSource<T> for CommitReveal {
+    /// The banker has to commit the seed before anyone bets
+    fn accepts_bets(game_id: T::GameIndex) -> bool {
+        Module::<T>::seed_commits(game_id).is_some()
+    }
+
+    fn draw_seed(game_id: T::GameIndex) -> Option<T::Hash> {
+        let secret = Module::<T>::seed_reveals(game_id)?;
+        let block_hash = bet_block_hash::<T>(game_id)?;
+        Some(T::Hashing::hash_of(&(secret, block_hash)))
+    }
+}
+
+/// BABE VRF seed, through the shared randomness
+pub struct BabeVrf<R>(PhantomData<R>);
+
+impl<T: Config, R: RandomSource<T::Hash>> SeedSource<T> for BabeVrf<R> {
+    fn draw_seed(game_id: T::GameIndex) -> Option<T::Hash> {
+        let block_hash = bet_block_hash::<T>(game_id)?;
+        let random = R::random(&(b"guess_hash", game_id).encode());
+        Some(T::Hashing::hash_of(&(random, block_hash)))
+    }
+}
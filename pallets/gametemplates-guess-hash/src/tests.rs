@@ -1,15 +1,40 @@
-use crate::mock::{new_test_ext, Chips, GameGuessHashModule, Origin, System};
+use crate::mock::{
+    new_test_ext, Chips, Event, GameGuessHashModule, Origin, SubgameRandomness, System, Test,
+    TestRandomness,
+};
+use crate::{CommitReveal, Error, GameType, RawEvent, SeedSource};
+use codec::Encode;
 use frame_support::{
-    assert_ok,
-    traits::{OnFinalize, OnInitialize},
+    assert_noop, assert_ok,
+    traits::{OnFinalize, OnInitialize, Randomness},
 };
+use frame_system::InitKind;
+use pallet_gametemplates::{GameStatus, GameTemplate};
+use pallet_subgame_randomness::RandomSource;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use sp_runtime::DispatchError;
+
+/// The seed `BabeVrf` takes for the game, after `nonce` other rolls of the shared randomness
+fn expected_seed(game_id: u32, bet_block: u64, nonce: u64) -> H256 {
+    let random = TestRandomness::random(&(b"guess_hash", game_id).encode());
+    let random = BlakeTwo256::hash_of(&(random, nonce));
+    BlakeTwo256::hash_of(&(random, H256::from_low_u64_be(bet_block)))
+}
 
 /// Jump to the specified block
 fn run_to_block(n: u64) {
     while System::block_number() < n {
         GameGuessHashModule::on_finalize(System::block_number());
         System::on_finalize(System::block_number());
-        System::set_block_number(System::block_number() + 1);
+        // Every block needs a hash, the draw seed depends on it
+        let parent_hash = H256::from_low_u64_be(System::block_number());
+        System::initialize(
+            &(System::block_number() + 1),
+            &parent_hash,
+            &Default::default(),
+            InitKind::Full,
+        );
         System::on_initialize(System::block_number());
         GameGuessHashModule::on_initialize(System::block_number());
     }
@@ -29,7 +54,8 @@ fn create_game() {
         assert_ok!(GameGuessHashModule::create_game(
             Origin::signed(1),
             bet_next_few_block_num,
-            100
+            100,
+            GameType::OddEven
         ));
 
         // 【Then】Assert
@@ -55,6 +81,23 @@ fn create_game() {
     });
 }
 
+/// 【Scenario】Creating a game without enough chips writes nothing
+#[test]
+fn create_game_failed_when_chips_not_enough() {
+    new_test_ext().execute_with(|| {
+        // 【Given】A user has 50 chips
+        let _ = Chips::buy_chips(Origin::signed(1), 50);
+
+        // 【When】A user creates a game with a pool of 100
+        // 【Then】It fails and no game is recorded
+        assert_noop!(
+            GameGuessHashModule::create_game(Origin::signed(1), 10, 100, GameType::OddEven),
+            Error::<Test>::TransferError
+        );
+        assert_eq!(GameGuessHashModule::game_count(), 0);
+    });
+}
+
 /// [Scenario] Test the betting function
 #[test]
 fn bet() {
@@ -66,7 +109,12 @@ fn bet() {
         let _ = Chips::buy_chips(Origin::signed(2), 100);
         let bet_next_few_block_num = 10u32;
         // A user have a new game, game index = 1
-        let _ = GameGuessHashModule::create_game(Origin::signed(1), bet_next_few_block_num, 100);
+        let _ = GameGuessHashModule::create_game(
+            Origin::signed(1),
+            bet_next_few_block_num,
+            100,
+            GameType::OddEven,
+        );
 
         // 【When】Act
         // B user bet 100 chips/ bet number
@@ -95,6 +143,25 @@ fn bet() {
     });
 }
 
+/// [Scenario] Betting without enough chips writes nothing
+#[test]
+fn bet_failed_when_chips_not_enough() {
+    new_test_ext().execute_with(|| {
+        // 【Given】A user has a game with a pool of 100, B user has 50 chips
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = Chips::buy_chips(Origin::signed(2), 50);
+        assert_ok!(GameGuessHashModule::create_game(Origin::signed(1), 10, 100, GameType::OddEven));
+
+        // 【When】B user bets 60 chips
+        // 【Then】It fails and no bet is recorded
+        assert_noop!(
+            GameGuessHashModule::bet(Origin::signed(2), 1, 60, 1),
+            pallet_chips::Error::<Test>::ChipsIsNotEnough
+        );
+        assert!(GameGuessHashModule::bet_list(1).is_empty());
+    });
+}
+
 /// [Scenario] Test whether the reward distribution is correct
 #[test]
 fn draw() {
@@ -111,7 +178,12 @@ fn draw() {
 
         let bet_next_few_block_num = 19u32;
         // A user have a new game, game index = 1, pool = 500
-        let _ = GameGuessHashModule::create_game(Origin::signed(1), bet_next_few_block_num, 500);
+        let _ = GameGuessHashModule::create_game(
+            Origin::signed(1),
+            bet_next_few_block_num,
+            500,
+            GameType::OddEven,
+        );
         // B bet single num, 100 chips
         let _ = GameGuessHashModule::bet(Origin::signed(2), 1, 100, 1);
         // C bet single num, 100 chips
@@ -173,3 +245,494 @@ fn draw() {
         }
     });
 }
+
+/// [Scenario] The draw can be verified from the seed
+#[test]
+fn draw_follows_seed() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+        // B bet single num, 100 chips
+        let _ = GameGuessHashModule::bet(Origin::signed(2), 1, 100, 1);
+
+        // 【When】Act
+        run_to_block(10);
+
+        // 【Then】Assert
+        // Recompute the seed and the last hex digit
+        let seed = GameGuessHashModule::game_seed(1).unwrap();
+        assert_eq!(seed, expected_seed(1, 6, 0));
+        let digit = seed.as_bytes()[31] & 0x0f;
+        if digit % 2 == 1 {
+            // B wins
+            assert_eq!(Chips::chips_map(2).unwrap().balance, 200);
+            assert_eq!(Chips::chips_map(1).unwrap().balance, 0);
+        } else {
+            // A wins
+            assert_eq!(Chips::chips_map(2).unwrap().balance, 0);
+            assert_eq!(Chips::chips_map(1).unwrap().balance, 200);
+        }
+    });
+}
+
+/// [Scenario] The seed is taken when the game is drawn and kept until the game is settled
+#[test]
+fn draw_seed_is_taken_when_drawn() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        // Two games drawn in the same block, the second one is carried over
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 500, GameType::OddEven);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 500, GameType::OddEven);
+        for _ in 0..6 {
+            assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 1, 1));
+            assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 2, 1, 2));
+        }
+        // No seed before the draw
+        run_to_block(7);
+        assert_eq!(GameGuessHashModule::game_seed(2), None);
+        run_to_block(8);
+        assert_eq!(GameGuessHashModule::queued_games(), vec![2]);
+        let seed = GameGuessHashModule::game_seed(2).unwrap();
+
+        // 【When】Act
+        // Other pallets roll the shared randomness before the game is settled
+        let first = SubgameRandomness::random(b"card_factory");
+        let second = SubgameRandomness::random(b"card_factory");
+        run_to_block(9);
+
+        // 【Then】Assert
+        assert_ne!(first, second);
+        // The game is settled with the seed taken in the draw block
+        assert_eq!(GameGuessHashModule::game_seed(2), Some(seed));
+        assert_eq!(seed, expected_seed(2, 6, 1));
+        let digit = seed.as_bytes()[31] & 0x0f;
+        let events: Vec<Event> = System::events().into_iter().map(|r| r.event).collect();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::pallet_gametemplates_guess_hash(RawEvent::GameOver(1, 2, _, d, s))
+                if *d == digit && *s == seed
+        )));
+    });
+}
+
+/// [Scenario] The banker commits and reveals the seed
+#[test]
+fn commit_reveal_seed() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+        let secret = b"banker secret".to_vec();
+        let commitment = BlakeTwo256::hash(&secret);
+
+        // 【When】Act
+        // Only the banker can commit
+        assert_noop!(
+            GameGuessHashModule::commit_seed(Origin::signed(2), 1, commitment),
+            Error::<Test>::PermissionDenied
+        );
+        assert_ok!(GameGuessHashModule::commit_seed(
+            Origin::signed(1),
+            1,
+            commitment
+        ));
+        assert_noop!(
+            GameGuessHashModule::commit_seed(Origin::signed(1), 1, commitment),
+            Error::<Test>::SeedAlreadyCommitted
+        );
+        // The secret has to match the commitment
+        assert_noop!(
+            GameGuessHashModule::reveal_seed(Origin::signed(1), 1, b"other".to_vec()),
+            Error::<Test>::SeedMismatch
+        );
+        assert_ok!(GameGuessHashModule::reveal_seed(
+            Origin::signed(1),
+            1,
+            secret.clone()
+        ));
+
+        // 【Then】Assert
+        // No seed before the bet block hash is known
+        assert_eq!(<CommitReveal as SeedSource<Test>>::draw_seed(1), None);
+        run_to_block(7);
+        let bet_block_hash = System::block_hash(6);
+        assert_eq!(
+            <CommitReveal as SeedSource<Test>>::draw_seed(1),
+            Some(BlakeTwo256::hash_of(&(secret, bet_block_hash)))
+        );
+    });
+}
+
+/// [Scenario] The banker cannot reveal after the bet block
+#[test]
+fn reveal_seed_failed_when_too_late() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+        let secret = b"banker secret".to_vec();
+        assert_ok!(GameGuessHashModule::commit_seed(
+            Origin::signed(1),
+            1,
+            BlakeTwo256::hash(&secret)
+        ));
+
+        // 【When】Act
+        run_to_block(7);
+
+        // 【Then】Assert
+        assert_noop!(
+            GameGuessHashModule::reveal_seed(Origin::signed(1), 1, secret),
+            Error::<Test>::RevealTooLate
+        );
+    });
+}
+
+/// [Scenario] Commit-reveal games only accept bets after the commitment
+#[test]
+fn commit_reveal_accepts_bets_after_commit() {
+    new_test_ext().execute_with(|| {
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+        assert!(!<CommitReveal as SeedSource<Test>>::accepts_bets(1));
+
+        assert_ok!(GameGuessHashModule::commit_seed(
+            Origin::signed(1),
+            1,
+            BlakeTwo256::hash(b"banker secret")
+        ));
+        assert!(<CommitReveal as SeedSource<Test>>::accepts_bets(1));
+    });
+}
+
+/// [Scenario] Each game type only accepts its own bet choices
+#[test]
+fn bet_failed_when_mode_not_in_game_type() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 300, GameType::BigSmall);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 300, GameType::ExactDigit);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 300, GameType::HexRange);
+
+        // 【When】Act
+        // 【Then】Assert
+        assert_noop!(
+            GameGuessHashModule::bet(Origin::signed(2), 1, 10, 3),
+            Error::<Test>::GameModeIsNotExist
+        );
+        assert_noop!(
+            GameGuessHashModule::bet(Origin::signed(2), 2, 10, 16),
+            Error::<Test>::GameModeIsNotExist
+        );
+        assert_noop!(
+            GameGuessHashModule::bet(Origin::signed(2), 3, 10, 0),
+            Error::<Test>::GameModeIsNotExist
+        );
+        assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 10, 2));
+        assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 2, 10, 0));
+        assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 3, 10, 4));
+    });
+}
+
+/// [Scenario] The prize pool has to cover the worst-case payout
+#[test]
+fn bet_failed_when_worst_case_over_pool() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 300);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        // Exact digit pays 1:15, pool = 300
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 300, GameType::ExactDigit);
+
+        // 【When】Act
+        // 20 chips on digit 7 may cost the pool 300
+        assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 20, 7));
+
+        // 【Then】Assert
+        // One more chip on digit 7 exceeds the pool
+        assert_noop!(
+            GameGuessHashModule::bet(Origin::signed(2), 1, 1, 7),
+            Error::<Test>::BetAmountLimitError
+        );
+        // Another digit is a different outcome, only one of them can win
+        assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 20, 8));
+    });
+}
+
+/// [Scenario] Exact digit games pay 1:15
+#[test]
+fn draw_exact_digit() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1500);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 1500, GameType::ExactDigit);
+        // The digit that will be drawn
+        let seed = expected_seed(1, 6, 0);
+        let digit = seed.as_bytes()[31] & 0x0f;
+        // B bets 100 chips on the drawn digit
+        assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 100, digit));
+
+        // 【When】Act
+        run_to_block(10);
+
+        // 【Then】Assert
+        // B gets the principal and 15 times the bet
+        assert_eq!(Chips::chips_map(2).unwrap().balance, 1600);
+        assert_eq!(Chips::chips_map(2).unwrap().reserve, 0);
+        // A pays out the whole pool
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 0);
+        assert_eq!(Chips::chips_map(1).unwrap().reserve, 0);
+    });
+}
+
+/// [Scenario] The banker cancels the game and everyone gets the chips back
+#[test]
+fn cancel_game() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+        let _ = GameGuessHashModule::bet(Origin::signed(2), 1, 50, 1);
+
+        // 【When】Act
+        // Only the banker or root can cancel
+        assert_noop!(
+            GameGuessHashModule::cancel_game(Origin::signed(2), 1, 1),
+            Error::<Test>::PermissionDenied
+        );
+        // The bet count witness has to cover the bets
+        assert_noop!(
+            GameGuessHashModule::cancel_game(Origin::signed(1), 1, 0),
+            Error::<Test>::BetCountWitnessError
+        );
+        assert_ok!(GameGuessHashModule::cancel_game(Origin::signed(1), 1, 1));
+
+        // 【Then】Assert
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 100);
+        assert_eq!(Chips::chips_map(1).unwrap().reserve, 0);
+        assert_eq!(Chips::chips_map(2).unwrap().balance, 100);
+        assert_eq!(Chips::chips_map(2).unwrap().reserve, 0);
+        assert!(GameGuessHashModule::is_cancelled(1));
+        assert!(GameGuessHashModule::draw_map(7).is_empty());
+        // Each refund has its own event
+        let events: Vec<Event> = System::events().into_iter().map(|r| r.event).collect();
+        assert!(events.contains(&Event::pallet_gametemplates_guess_hash(
+            RawEvent::BetRefunded(2, 1, 50, 0)
+        )));
+        assert!(events.contains(&Event::pallet_gametemplates_guess_hash(
+            RawEvent::PoolRefunded(1, 1, 100)
+        )));
+        // The cancelled game takes no more bets and cannot be cancelled twice
+        assert_noop!(
+            GameGuessHashModule::bet(Origin::signed(2), 1, 50, 1),
+            Error::<Test>::GameCancelled
+        );
+        assert_noop!(
+            GameGuessHashModule::cancel_game(Origin::root(), 1, 1),
+            Error::<Test>::GameCancelled
+        );
+
+        // Nothing is drawn
+        run_to_block(10);
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 100);
+        assert_eq!(Chips::chips_map(2).unwrap().balance, 100);
+    });
+}
+
+/// [Scenario] Root can cancel, but only before the bet block
+#[test]
+fn cancel_game_by_root() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 200);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+
+        // 【When】Act
+        assert_ok!(GameGuessHashModule::cancel_game(Origin::root(), 1, 0));
+        run_to_block(6);
+
+        // 【Then】Assert
+        assert_noop!(
+            GameGuessHashModule::cancel_game(Origin::root(), 2, 0),
+            Error::<Test>::GameOver
+        );
+        assert_noop!(
+            GameGuessHashModule::cancel_game(Origin::none(), 2, 0),
+            DispatchError::BadOrigin
+        );
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 100);
+        assert_eq!(Chips::chips_map(1).unwrap().reserve, 100);
+    });
+}
+
+/// [Scenario] Without the bet block hash the game is refunded
+#[test]
+fn refund_when_draw_seed_unavailable() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 100);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 100, GameType::OddEven);
+        let _ = GameGuessHashModule::bet(Origin::signed(2), 1, 50, 1);
+        run_to_block(6);
+
+        // 【When】Act
+        // The draw block runs without the bet block hash
+        System::set_block_number(7);
+        GameGuessHashModule::on_finalize(7);
+        System::set_block_number(8);
+        GameGuessHashModule::on_initialize(8);
+
+        // 【Then】Assert
+        assert_eq!(GameGuessHashModule::game_seed(1), None);
+        assert_eq!(Chips::chips_map(1).unwrap().balance, 100);
+        assert_eq!(Chips::chips_map(2).unwrap().balance, 100);
+        assert!(GameGuessHashModule::is_cancelled(1));
+        let events: Vec<Event> = System::events().into_iter().map(|r| r.event).collect();
+        assert!(events.contains(&Event::pallet_gametemplates_guess_hash(
+            RawEvent::BetRefunded(2, 1, 50, 0)
+        )));
+        assert!(events.contains(&Event::pallet_gametemplates_guess_hash(
+            RawEvent::GameCancelled(1)
+        )));
+    });
+}
+
+/// [Scenario] A game takes at most `MaxBetsPerGame` bets
+#[test]
+fn bet_failed_when_too_many_bets() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 1000, GameType::OddEven);
+        for _ in 0..10 {
+            assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 1, 1));
+        }
+
+        // 【When】Act
+        // 【Then】Assert
+        assert_noop!(
+            GameGuessHashModule::bet(Origin::signed(2), 1, 1, 1),
+            Error::<Test>::TooManyBets
+        );
+    });
+}
+
+/// [Scenario] Games that do not fit in the settlement weight carry over to the next block
+#[test]
+fn settlement_carries_over() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        // Two games drawn in the same block, 6 bets each
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 500, GameType::OddEven);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 500, GameType::OddEven);
+        for _ in 0..6 {
+            assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 1, 1));
+            assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 2, 1, 2));
+        }
+
+        // 【When】Act
+        // Both games are drawn at the end of block 7
+        run_to_block(8);
+
+        // 【Then】Assert
+        // Only the first game fits in block 8
+        assert_eq!(GameGuessHashModule::queued_games(), vec![2]);
+        assert_eq!(Chips::chips_map(2).unwrap().reserve, 6);
+
+        // The second game is settled in block 9
+        run_to_block(9);
+        assert!(GameGuessHashModule::queued_games().is_empty());
+        assert_eq!(Chips::chips_map(1).unwrap().reserve, 0);
+        assert_eq!(Chips::chips_map(2).unwrap().reserve, 0);
+        // No chips are lost
+        assert_eq!(
+            Chips::chips_map(1).unwrap().balance + Chips::chips_map(2).unwrap().balance,
+            1100
+        );
+    });
+}
+
+/// [Scenario] The lobby creates, plays and settles the game through the game template
+#[test]
+fn game_template() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+
+        // 【When】Act
+        // Odd/even game by default, exact digit game from the encoded game type
+        assert_eq!(GameGuessHashModule::create(&1, 5, 100, vec![]), Ok(1));
+        assert_eq!(
+            GameGuessHashModule::create(&1, 5, 500, GameType::ExactDigit.encode()),
+            Ok(2)
+        );
+        assert_noop!(
+            GameGuessHashModule::create(&1, 5, 100, vec![9]),
+            Error::<Test>::InvalidGameParams
+        );
+        assert_ok!(GameGuessHashModule::play(&2, 2, 10, 15));
+
+        // 【Then】Assert
+        assert_eq!(GameGuessHashModule::template_id(), 1);
+        assert_eq!(GameGuessHashModule::game_type(1), GameType::OddEven);
+        assert_eq!(GameGuessHashModule::game_type(2), GameType::ExactDigit);
+        assert_eq!(GameGuessHashModule::status(2), Some(GameStatus::Betting));
+        assert_eq!(GameGuessHashModule::status(3), None);
+        run_to_block(6);
+        assert_eq!(GameGuessHashModule::status(2), Some(GameStatus::Drawing));
+        // The game is drawn at the end of block 7
+        run_to_block(7);
+        assert_noop!(GameGuessHashModule::settle(2, 1), Error::<Test>::GameNotQueued);
+        run_to_block(8);
+        assert_eq!(GameGuessHashModule::status(2), Some(GameStatus::Settled));
+    });
+}
+
+/// [Scenario] A carried over game can be settled on demand
+#[test]
+fn game_template_settle_queued_game() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let _ = Chips::buy_chips(Origin::signed(1), 1000);
+        let _ = Chips::buy_chips(Origin::signed(2), 100);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 500, GameType::OddEven);
+        let _ = GameGuessHashModule::create_game(Origin::signed(1), 5, 500, GameType::OddEven);
+        for _ in 0..6 {
+            assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 1, 1, 1));
+            assert_ok!(GameGuessHashModule::bet(Origin::signed(2), 2, 1, 2));
+        }
+        run_to_block(8);
+        assert_eq!(GameGuessHashModule::status(2), Some(GameStatus::Drawing));
+
+        // 【When】Act
+        // The bet count witness has to cover the bets of the game
+        assert_noop!(
+            GameGuessHashModule::settle(2, 5),
+            Error::<Test>::BetCountWitnessError
+        );
+        assert_ok!(GameGuessHashModule::settle(2, 6));
+
+        // 【Then】Assert
+        assert!(GameGuessHashModule::queued_games().is_empty());
+        assert_eq!(GameGuessHashModule::status(2), Some(GameStatus::Settled));
+        assert_eq!(Chips::chips_map(2).unwrap().reserve, 0);
+        assert_noop!(GameGuessHashModule::settle(2, 6), Error::<Test>::GameNotQueued);
+        // The next block skips the empty position
+        run_to_block(9);
+        assert_eq!(GameGuessHashModule::queue_head(), GameGuessHashModule::queue_tail());
+    });
+}
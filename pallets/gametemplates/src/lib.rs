@@ -8,6 +8,11 @@ use frame_support::{
 use frame_system::ensure_signed;
 mod default_weight;
 
+pub mod template;
+pub use crate::template::{
+    GameInstanceId, GameRouter, GameStatus, GameTemplate, TemplateId, TemplateRegistry,
+};
+
 #[cfg(test)]
 mod mock;
 
@@ -76,3 +81,10 @@ decl_module! {
         }
     }
 }
+
+/// Provided to the lobby: only registered templates can be played
+impl<T: Config> TemplateRegistry for Module<T> {
+    fn is_registered(template_id: TemplateId) -> bool {
+        TemplateMap::contains_key(template_id)
+    }
+}
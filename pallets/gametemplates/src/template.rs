@@ -0,0 +1,176 @@
+//! # Game Template Interface
+//!
+//! A game pallet joins the lobby by implementing [`GameTemplate`] and being added to the
+//! runtime's `pallet_gamecenter::Config::Templates` tuple. The lobby finds the game by the
+//! template id it is registered under in this pallet.
+
+use codec::{Decode, Encode};
+use frame_support::{
+    dispatch::{DispatchError, DispatchResult, Vec},
+    weights::Weight,
+};
+use sp_runtime::RuntimeDebug;
+
+/// Template id, as registered by `create_template`
+pub type TemplateId = u32;
+
+/// Game instance id, unique within a template
+pub type GameInstanceId = u32;
+
+/// Where a game instance is in its life cycle
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum GameStatus {
+    /// Players can still play
+    Betting,
+    /// Betting is over, waiting to be settled
+    Drawing,
+    /// The game is settled
+    Settled,
+    /// The game is cancelled and refunded
+    Cancelled,
+}
+
+/// A game that can be played from the lobby
+pub trait GameTemplate<AccountId, Balance> {
+    /// The template id the game is registered under
+    fn template_id() -> TemplateId;
+    /// Create a game instance, `params` are the template specific options
+    fn create(
+        owner: &AccountId,
+        bet_next_few_block: u32,
+        amount: Balance,
+        params: Vec<u8>,
+    ) -> Result<GameInstanceId, DispatchError>;
+    /// Play (bet) in a game instance
+    fn play(who: &AccountId, game_id: GameInstanceId, amount: Balance, choice: u8) -> DispatchResult;
+    /// Settle a game instance that is waiting to be settled, fails if it has more than
+    /// `bet_count` bets
+    fn settle(game_id: GameInstanceId, bet_count: u32) -> DispatchResult;
+    /// The most weight `settle` takes for a game with `bet_count` bets
+    fn settle_weight(bet_count: u32) -> Weight;
+    /// The status of a game instance, `None` if it does not exist
+    fn status(game_id: GameInstanceId) -> Option<GameStatus>;
+}
+
+/// Route the calls to the game registered under the template id
+pub trait GameRouter<AccountId, Balance> {
+    fn create(
+        template_id: TemplateId,
+        owner: &AccountId,
+        bet_next_few_block: u32,
+        amount: Balance,
+        params: Vec<u8>,
+    ) -> Result<GameInstanceId, DispatchError>;
+    fn play(
+        template_id: TemplateId,
+        who: &AccountId,
+        game_id: GameInstanceId,
+        amount: Balance,
+        choice: u8,
+    ) -> DispatchResult;
+    fn settle(template_id: TemplateId, game_id: GameInstanceId, bet_count: u32) -> DispatchResult;
+    fn settle_weight(template_id: TemplateId, bet_count: u32) -> Weight;
+    fn status(template_id: TemplateId, game_id: GameInstanceId) -> Option<GameStatus>;
+}
+
+/// Template ids registered in the template list
+pub trait TemplateRegistry {
+    fn is_registered(template_id: TemplateId) -> bool;
+}
+
+const TEMPLATE_NOT_FOUND: &str = "TemplateNotFound";
+
+impl<AccountId, Balance> GameRouter<AccountId, Balance> for () {
+    fn create(
+        _: TemplateId,
+        _: &AccountId,
+        _: u32,
+        _: Balance,
+        _: Vec<u8>,
+    ) -> Result<GameInstanceId, DispatchError> {
+        Err(DispatchError::Other(TEMPLATE_NOT_FOUND))
+    }
+    fn play(_: TemplateId, _: &AccountId, _: GameInstanceId, _: Balance, _: u8) -> DispatchResult {
+        Err(DispatchError::Other(TEMPLATE_NOT_FOUND))
+    }
+    fn settle(_: TemplateId, _: GameInstanceId, _: u32) -> DispatchResult {
+        Err(DispatchError::Other(TEMPLATE_NOT_FOUND))
+    }
+    fn settle_weight(_: TemplateId, _: u32) -> Weight {
+        0
+    }
+    fn status(_: TemplateId, _: GameInstanceId) -> Option<GameStatus> {
+        None
+    }
+}
+
+/// A tuple of games routes to the first one with a matching template id
+macro_rules! impl_game_router {
+    ($($game:ident),+) => {
+        impl<AccountId, Balance, $($game: GameTemplate<AccountId, Balance>),+>
+            GameRouter<AccountId, Balance> for ($($game,)+)
+        {
+            fn create(
+                template_id: TemplateId,
+                owner: &AccountId,
+                bet_next_few_block: u32,
+                amount: Balance,
+                params: Vec<u8>,
+            ) -> Result<GameInstanceId, DispatchError> {
+                $(
+                    if $game::template_id() == template_id {
+                        return $game::create(owner, bet_next_few_block, amount, params);
+                    }
+                )+
+                Err(DispatchError::Other(TEMPLATE_NOT_FOUND))
+            }
+            fn play(
+                template_id: TemplateId,
+                who: &AccountId,
+                game_id: GameInstanceId,
+                amount: Balance,
+                choice: u8,
+            ) -> DispatchResult {
+                $(
+                    if $game::template_id() == template_id {
+                        return $game::play(who, game_id, amount, choice);
+                    }
+                )+
+                Err(DispatchError::Other(TEMPLATE_NOT_FOUND))
+            }
+            fn settle(template_id: TemplateId, game_id: GameInstanceId, bet_count: u32) -> DispatchResult {
+                $(
+                    if $game::template_id() == template_id {
+                        return $game::settle(game_id, bet_count);
+                    }
+                )+
+                Err(DispatchError::Other(TEMPLATE_NOT_FOUND))
+            }
+            fn settle_weight(template_id: TemplateId, bet_count: u32) -> Weight {
+                $(
+                    if $game::template_id() == template_id {
+                        return $game::settle_weight(bet_count);
+                    }
+                )+
+                0
+            }
+            fn status(template_id: TemplateId, game_id: GameInstanceId) -> Option<GameStatus> {
+                $(
+                    if $game::template_id() == template_id {
+                        return $game::status(game_id);
+                    }
+                )+
+                None
+            }
+        }
+    };
+}
+
+impl_game_router!(A);
+impl_game_router!(A, B);
+impl_game_router!(A, B, C);
+impl_game_router!(A, B, C, D);
+impl_game_router!(A, B, C, D, E);
+impl_game_router!(A, B, C, D, E, F);
+impl_game_router!(A, B, C, D, E, F, G);
+impl_game_router!(A, B, C, D, E, F, G, H);
@@ -1,5 +1,5 @@
 use crate::mock::{new_test_ext, GameTemplate, Origin, Test};
-use crate::{Error};
+use crate::{Error, TemplateRegistry};
 use frame_support::{
     assert_noop, assert_ok
 };
@@ -37,3 +37,19 @@ fn create_template_failded_when_permission_denied() {
         assert_eq!(templates.len(), 0);
     });
 }
+
+/// 【Scenario】created templates are registered for the lobby
+#[test]
+fn template_is_registered() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        assert!(!<GameTemplate as TemplateRegistry>::is_registered(0));
+
+        // 【When】Act
+        assert_ok!(GameTemplate::create_template(Origin::signed(1), 100));
+
+        // 【Then】Assert
+        assert!(<GameTemplate as TemplateRegistry>::is_registered(0));
+        assert!(!<GameTemplate as TemplateRegistry>::is_registered(1));
+    });
+}
@@ -5,9 +5,9 @@ use default_weight::WeightInfo;
 
 
 use sp_std::{prelude::*};
-use sp_runtime::{RuntimeDebug,RandomNumberGenerator};
+use sp_runtime::{RuntimeDebug};
 
-use sp_runtime::traits::{Hash, BlakeTwo256, SaturatedConversion};
+use sp_runtime::traits::{SaturatedConversion};
 use codec::{Encode, Decode};
 use frame_support::{decl_module, decl_event, decl_storage, decl_error, ensure,
 	traits::{Currency,Get},
@@ -18,6 +18,8 @@ use frame_system::ensure_signed;
 
 use pallet_nft::UniqueAssets;
 
+use common::RandomSource;
+
 use pallet_subgame_assets::{self as SubGameAssets};
 use pallet_subgame_assets::{AssetsTrait, AssetsTransfer};
 
@@ -1134,23 +1136,12 @@ decl_module! {
 // The main implementation block for the module.
 impl<T: Config> Module<T> {
     
-	/// ran
+	/// A random number in `[min, max)`
 	fn _range_random(
 		min: u32,
 		max: u32,
 	) -> u32 {
-		let result: &u32;
-		let mut range = Vec::new();
-
-
-		let now = pallet_timestamp::Pallet::<T>::get();
-		let random_seed = BlakeTwo256::hash(&(now.encode()));
-		let mut rng = RandomNumberGenerator::<BlakeTwo256>::new(random_seed);
-		for n in min..max{
-			range.push(n);
-		}
-		result = rng.pick_item(&range).unwrap();
-		*result
+		T::Randomness::random_range(b"sonic_racer", min, max)
 	}
 
 }
@@ -0,0 +1,95 @@
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite};
+use sp_std::{vec, vec::Vec};
+use sp_runtime::{
+	traits::{
+		SaturatedConversion,
+	}
+};
+use frame_support::{
+	assert_ok,
+	traits::OnInitialize,
+};
+
+#[allow(unused)]
+use crate::Module as Pallet;
+
+const STAKE_AMOUNT: u64 = 1_000;
+
+/// `n` stakes of a program valid for 0 days from their own accounts, expiring at the timestamp
+/// they are made at and not renewed
+fn init<T: Config + pallet_lease::Config>(n: u32) {
+	let owner = <T as Config>::OwnerAddress::get();
+	T::Balances::make_free_balance_be(&owner, 1_000_000_000u64.saturated_into());
+	assert_ok!(Pallet::<T>::add_program(RawOrigin::Signed(owner).into(), Default::default(), STAKE_AMOUNT.saturated_into(), 0));
+	let lease_owner = <T as pallet_lease::Config>::OwnerAddress::get();
+	assert_ok!(pallet_lease::Module::<T>::add_pallet(RawOrigin::Signed(lease_owner).into(), Default::default(), b"bench".to_vec()));
+
+	for i in 0 .. n {
+		let staker: T::AccountId = account("staker", i, 0);
+		T::Balances::make_free_balance_be(&staker, 1_000_000u64.saturated_into());
+		assert_ok!(Pallet::<T>::stake(RawOrigin::Signed(staker.clone()).into(), Default::default(), Default::default()));
+		let nft_id = Pallet::<T>::stake_infos(&staker)[0].nft_id.clone();
+		assert_ok!(Pallet::<T>::set_stake_will_expire(RawOrigin::Signed(staker).into(), nft_id, true));
+	}
+}
+
+/// Move the timestamp into bucket `bucket`, past the expiry of the stakes made at 0
+fn set_now<T: Config>(bucket: u64) {
+	let now_ms = (bucket * EXPIRY_BUCKET_SECONDS as u64 + 1) * 1000;
+	pallet_timestamp::Pallet::<T>::set_timestamp(now_ms.saturated_into());
+}
+
+benchmarks! {
+	where_clause { where T: pallet_lease::Config }
+
+	// `e` stakes due in the first bucket, then `r - e` stale entries of stakes that are gone,
+	// then `b - 1` empty buckets up to now
+	on_initialize {
+		let b in 1 .. T::MaxExpiryBucketsPerBlock::get();
+		let r in 0 .. T::MaxExpiriesPerBlock::get();
+		let e in 0 .. T::MaxExpiriesPerBlock::get();
+		init::<T>(e);
+		for i in e .. r {
+			let staker: T::AccountId = account("stale", i, 0);
+			ExpiryQueue::<T>::append(0u64, (staker, NftId::<T>::default()));
+		}
+		NextExpiryBucket::put(0);
+		set_now::<T>((b - 1) as u64);
+	}: {
+		Pallet::<T>::on_initialize(1u32.into());
+	}
+	verify {
+		// reading a full first bucket ends the block's reads
+		let full = r.max(e) >= T::MaxExpiriesPerBlock::get();
+		let next = if full { (b - 1).min(1) } else { b - 1 };
+		assert_eq!(Pallet::<T>::next_expiry_bucket(), Some(next as u64));
+		for i in 0 .. e {
+			let staker: T::AccountId = account("staker", i, 0);
+			assert!(Pallet::<T>::stake_infos(&staker).is_empty());
+		}
+	}
+
+	// Twice `MaxExpiriesPerBlock` stakes due, only `MaxExpiriesPerBlock` are touched
+	on_initialize_saturated {
+		let max = T::MaxExpiriesPerBlock::get();
+		init::<T>(max * 2);
+		set_now::<T>(T::MaxExpiryBucketsPerBlock::get() as u64);
+	}: {
+		Pallet::<T>::on_initialize(1u32.into());
+	}
+	verify {
+		let expired = (0 .. max * 2)
+			.filter(|i| Pallet::<T>::stake_infos(&account::<T::AccountId>("staker", *i, 0)).is_empty())
+			.count();
+		assert_eq!(expired as u32, max);
+	}
+}
+
+impl_benchmark_test_suite!(
+	Pallet,
+	crate::mock::new_test_ext(),
+	crate::mock::Test,
+);
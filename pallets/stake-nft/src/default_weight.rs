@@ -0,0 +1,21 @@
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(clippy::unnecessary_cast)]
+
+use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
+
+impl crate::WeightInfo for () {
+    fn on_initialize(b: u32, r: u32, e: u32, ) -> Weight {
+		(6_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(b as Weight))
+			.saturating_add((5_000_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add((91_000_000 as Weight).saturating_mul(e as Weight))
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(b as Weight)))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(r as Weight)))
+			.saturating_add(DbWeight::get().reads((6 as Weight).saturating_mul(e as Weight)))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+			.saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(b as Weight)))
+			.saturating_add(DbWeight::get().writes((6 as Weight).saturating_mul(e as Weight)))
+	}
+}
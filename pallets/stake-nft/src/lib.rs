@@ -4,7 +4,8 @@
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
     traits::{Currency, ExistenceRequirement, Get},
-    Parameter,
+    weights::Weight,
+    Parameter, transactional,
 };
 use frame_system::ensure_signed;
 use sp_runtime::traits::{Member};
@@ -32,6 +33,19 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+mod default_weight;
+pub trait WeightInfo {
+    /// Expiry in `on_initialize` reading `b` queue buckets and `r` of their entries, and expiring,
+    /// renewing or retrying `e` stakes
+    fn on_initialize(b: u32, r: u32, e: u32) -> Weight;
+}
+
+/// Seconds of expiry time covered by one bucket of `ExpiryQueue`
+pub const EXPIRY_BUCKET_SECONDS: i64 = 60;
+
 #[derive(Encode, Decode, Default, Copy, Clone, Eq)]
 pub struct Program<ProgramId, Balance> {
     program_id: ProgramId,
@@ -98,6 +112,13 @@ pub trait Config: frame_system::Config + pallet_timestamp::Config {
     type PalletId: Member + Parameter + Default + Copy + HasCompact + Ord;
     type Balances: Currency<Self::AccountId>;
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+    /// `ExpiryQueue` entries read in a block at most, also the size of a bucket
+    type MaxExpiriesPerBlock: Get<u32>;
+    /// `ExpiryQueue` buckets read in a block at most
+    type MaxExpiryBucketsPerBlock: Get<u32>;
+    /// `ExpiryQueue` buckets looked at to queue a stake at most
+    type MaxExpiryProbes: Get<u32>;
+    type WeightInfo: WeightInfo;
 }
 
 /// The runtime system's hashing algorithm is used to uniquely identify commodities.
@@ -117,6 +138,13 @@ decl_storage! {
         Programs get(fn programs_list): Vec<Program<T::ProgramId, BalanceOf<T>>>;
         StakeUsers get(fn stake_users):  Vec<T::AccountId>;
         StakeInfos get(fn stake_infos): map hasher(blake2_128_concat) T::AccountId => Vec<StakeInfo<T::ProgramId, PalletId<T>, BalanceOf<T>, NftId<T>>>;
+        /// Stakes by their expiry time divided by `EXPIRY_BUCKET_SECONDS`. An entry whose stake is
+        /// gone or now expires in a later bucket is stale and dropped when its bucket is read.
+        ExpiryQueue get(fn expiry_queue): map hasher(twox_64_concat) u64 => Vec<(T::AccountId, NftId<T>)>;
+        /// First bucket of `ExpiryQueue` not read to the end, none before any stake
+        NextExpiryBucket get(fn next_expiry_bucket): Option<u64>;
+        /// Whether the stakes made before `ExpiryQueue` existed are queued
+        ExpiryQueueBuilt get(fn expiry_queue_built): bool;
     }
 }
 
@@ -148,7 +176,8 @@ decl_error! {
         NotFoundNft,
         MoneyNotEnough,
         PermissionDenied,
-        NotFoundData
+        NotFoundData,
+        ExpiryQueueFull
     }
 }
 
@@ -200,7 +229,8 @@ decl_module! {
             }
         }
       
-        #[weight = 10_000]
+        #[weight = 10_000 + T::DbWeight::get().reads(T::MaxExpiryProbes::get() as Weight)]
+        #[transactional]
         pub fn stake(origin, program_id: T::ProgramId, pallet_id: PalletId<T>) -> dispatch::DispatchResult {
             let from_address = ensure_signed(origin)?;
 
@@ -247,6 +277,7 @@ decl_module! {
             StakeInfos::<T>::mutate(from_address.clone(), |stake_nft_data| {
                 stake_nft_data.insert(stake_nft_data.len(), new_stake_nft.clone())
             });
+            Self::queue_expiry(&from_address, &new_stake_nft.nft_id, Self::expiry_bucket(new_stake_nft.expires_at))?;
             
             let mut users = StakeUsers::<T>::get();
             match users.binary_search(&from_address) {
@@ -282,70 +313,211 @@ decl_module! {
             })
         }
         
-        fn on_finalize() {
-            let now = pallet_timestamp::Pallet::<T>::get();
-            let now_timestamp = (TryInto::<u64>::try_into(now).ok().unwrap() / 1000) as i64; 
+        /// Expire or renew the due stakes, reading at most `MaxExpiriesPerBlock` queue entries
+        fn on_initialize(_n: T::BlockNumber) -> Weight {
+            let (buckets, reads, expiries) = Self::process_expiries();
+            T::WeightInfo::on_initialize(buckets, reads, expiries)
+        }
+
+        /// Queue the stakes made before `ExpiryQueue` existed
+        fn on_runtime_upgrade() -> Weight {
+            if ExpiryQueueBuilt::get() {
+                return T::DbWeight::get().reads(1);
+            }
             let users = StakeUsers::<T>::get();
-            for user in users {
-                let stakes = StakeInfos::<T>::get(user.clone());
-                
-                for (index, stake) in stakes.iter().enumerate() {
-                    // expires
-                    if now_timestamp > stake.expires_at {
-                        // 過期不自動續約
-                        if stake.will_expire {
-                             // nft_owner
-                            // let nft_owner = T::UniqueAssets::owner_of(&stake.nft_id.clone());
-
-                            let owner = T::OwnerAddress::get();
-                            // check balance
-                            if T::Balances::free_balance(&owner) <= stake.stake_amount {
-                                debug::info!("stake-nft owner餘額不足，無法進行退款, nft: {:?}", stake.nft_id.clone());
-                                return
-                            }
-
-                            debug::info!("stake-nft 過期,已註銷, nft: {:?}", stake.nft_id.clone());
-
-                            T::UniqueAssets::burn(&stake.nft_id.clone()).map_err(|err| debug::error!("err: {:?}", err)).ok();
-
-                            T::Lease::revoke(stake.nft_id.clone(), stake.pallet_id).map_err(|err| debug::error!("err: {:?}", err)).ok();
-
-                            T::Balances::transfer(&owner, &user, stake.stake_amount, ExistenceRequirement::KeepAlive).map_err(|err| debug::error!("err: {:?}", err)).ok();
-                
-                            // remove record
-                            StakeInfos::<T>::mutate(user.clone(), |stake_nft_data| {
-                                stake_nft_data.remove(index);
-                            });
-
-                            Self::deposit_event(RawEvent::Expire(user.clone(), stake.nft_id.clone()));        
-                            
-                        }else{
-                            debug::info!("stake-nft 過期,自動續約, nft: {:?}", stake.nft_id);
-
-                            let mut _programs_list = Programs::<T>::get();
-                            let _program = _programs_list.iter().find(|&&probe| probe.program_id == stake.program_id);
-                            // now time
-                            let now = pallet_timestamp::Pallet::<T>::get();
-                            let now_ms = TryInto::<u64>::try_into(now).ok().unwrap(); // convert to u64
-
-                            // add N day
-                            let n_day = _program.unwrap().valid_day_count as i64;
-                            let n_day_ms = u64::try_from(chrono::Duration::days(n_day).num_milliseconds()).ok().unwrap();
-                            let expires_at_ms = now_ms + n_day_ms;
-
-                            let expires_at = expires_at_ms / 1000;
-
-                            StakeInfos::<T>::mutate(user.clone(), |stake_info| {
-                                stake_info[index].expires_at = expires_at as i64;
-                            });
-                            Self::deposit_event(RawEvent::Renew(user.clone(), stake.nft_id.clone(), expires_at.to_string().into_bytes()));  
-                        }
-                       
-                    }
-                } 
+            let mut stakes: Weight = 0;
+            let mut probes: Weight = 0;
+            for user in users.iter() {
+                for stake in StakeInfos::<T>::get(user) {
+                    probes += Self::schedule_expiry(user, &stake) as Weight;
+                    stakes += 1;
+                }
+            }
+            ExpiryQueueBuilt::put(true);
+            T::DbWeight::get().reads_writes(2 + users.len() as Weight + stakes + probes, 1 + stakes * 2)
+        }
+    }
+}
+
+
+type StakeInfoOf<T> = StakeInfo<<T as Config>::ProgramId, PalletId<T>, BalanceOf<T>, NftId<T>>;
+
+impl<T: Config> Module<T> {
+    /// Timestamp of the block in seconds
+    fn now_seconds() -> i64 {
+        let now = pallet_timestamp::Pallet::<T>::get();
+        (TryInto::<u64>::try_into(now).ok().unwrap() / 1000) as i64
+    }
+
+    fn expiry_bucket(expires_at: i64) -> u64 {
+        (expires_at.max(0) / EXPIRY_BUCKET_SECONDS) as u64
+    }
+
+    /// Queue a staked stake again in the bucket of its expiry time, or the first later one with
+    /// room. Returns the buckets looked at.
+    fn schedule_expiry(who: &T::AccountId, stake: &StakeInfoOf<T>) -> u32 {
+        Self::requeue_expiry(who, &stake.nft_id, Self::expiry_bucket(stake.expires_at))
+    }
+
+    /// Queue a new stake in `bucket`, or the first later one with room. A bucket holds
+    /// `MaxExpiriesPerBlock` stakes so that reading one stays cheap, and at most
+    /// `MaxExpiryProbes` buckets are looked at. Returns the buckets looked at.
+    fn queue_expiry(who: &T::AccountId, nft_id: &NftId<T>, bucket: u64) -> Result<u32, dispatch::DispatchError> {
+        let (bucket, probes) = Self::bucket_with_room(bucket).ok_or(Error::<T>::ExpiryQueueFull)?;
+        Self::append_expiry(who, nft_id, bucket);
+        Ok(probes)
+    }
+
+    /// Queue a stake already staked like `queue_expiry`. It cannot be turned away, so when every
+    /// bucket looked at is full it goes to the last one. Returns the buckets looked at.
+    fn requeue_expiry(who: &T::AccountId, nft_id: &NftId<T>, bucket: u64) -> u32 {
+        let max_probes = T::MaxExpiryProbes::get().max(1);
+        let (bucket, probes) = Self::bucket_with_room(bucket)
+            .unwrap_or((bucket + max_probes as u64 - 1, max_probes));
+        Self::append_expiry(who, nft_id, bucket);
+        probes
+    }
+
+    /// The first bucket from `bucket` on with room, and the buckets looked at, within
+    /// `MaxExpiryProbes` buckets
+    fn bucket_with_room(mut bucket: u64) -> Option<(u64, u32)> {
+        let max_probes = T::MaxExpiryProbes::get().max(1);
+        for probes in 1..=max_probes {
+            if (ExpiryQueue::<T>::decode_len(bucket).unwrap_or(0) as u32) < T::MaxExpiriesPerBlock::get().max(1) {
+                return Some((bucket, probes));
+            }
+            bucket += 1;
+        }
+        None
+    }
+
+    fn append_expiry(who: &T::AccountId, nft_id: &NftId<T>, bucket: u64) {
+        ExpiryQueue::<T>::append(bucket, (who.clone(), nft_id.clone()));
+        match Self::next_expiry_bucket() {
+            Some(next) if next <= bucket => {},
+            _ => NextExpiryBucket::put(bucket),
+        }
+    }
+
+    /// Read the queue from `NextExpiryBucket` up to now and expire or renew the due stakes,
+    /// reading at most `MaxExpiriesPerBlock` entries, stale ones included. A stake the owner
+    /// cannot refund now moves to the bucket after now, so that it does not hold up the rest.
+    /// Returns the buckets read or looked at, the entries read and the stakes expired, renewed
+    /// or moved.
+    fn process_expiries() -> (u32, u32, u32) {
+        let mut bucket = match Self::next_expiry_bucket() {
+            Some(bucket) => bucket,
+            None => return (0, 0, 0),
+        };
+        let now = Self::now_seconds();
+        let now_bucket = Self::expiry_bucket(now);
+        let max_reads = T::MaxExpiriesPerBlock::get().max(1);
+        let mut buckets: u32 = 0;
+        let mut probes: u32 = 0;
+        let mut reads: u32 = 0;
+        let mut expiries: u32 = 0;
+
+        while bucket <= now_bucket && buckets < T::MaxExpiryBucketsPerBlock::get() && reads < max_reads {
+            buckets += 1;
+            let mut kept = Vec::new();
+            for (who, nft_id) in ExpiryQueue::<T>::take(bucket) {
+                if reads >= max_reads {
+                    kept.push((who, nft_id));
+                    continue;
+                }
+                reads += 1;
+                let stake = match Self::stake_infos(&who).into_iter().find(|probe| probe.nft_id == nft_id) {
+                    Some(stake) if Self::expiry_bucket(stake.expires_at) <= bucket => stake,
+                    _ => continue,
+                };
+                if now <= stake.expires_at {
+                    kept.push((who, nft_id));
+                    continue;
+                }
+                expiries += 1;
+                probes += match Self::expire(&who, &stake) {
+                    Some(renewal_probes) => renewal_probes,
+                    None => Self::requeue_expiry(&who, &nft_id, now_bucket + 1),
+                };
+            }
+
+            if !kept.is_empty() {
+                // the kept entries were taken from the bucket, so they fit in it again. Stakes
+                // renewed into it meanwhile go to the first bucket with room.
+                let renewed = ExpiryQueue::<T>::take(bucket);
+                ExpiryQueue::<T>::insert(bucket, kept);
+                for (who, nft_id) in renewed {
+                    probes += Self::requeue_expiry(&who, &nft_id, bucket);
+                }
+                break;
             }
-           
-           
+            if bucket == now_bucket {
+                break;
+            }
+            bucket += 1;
+        }
+
+        NextExpiryBucket::put(bucket);
+        (buckets.saturating_add(probes), reads, expiries)
+    }
+
+    /// Refund and burn a due stake that will expire, renew the others. Returns the buckets
+    /// looked at to queue a renewed stake again, none when the owner cannot refund the stake.
+    fn expire(user: &T::AccountId, stake: &StakeInfoOf<T>) -> Option<u32> {
+        // 過期不自動續約
+        if stake.will_expire {
+            let owner = T::OwnerAddress::get();
+            // check balance
+            if T::Balances::free_balance(&owner) <= stake.stake_amount {
+                debug::info!("stake-nft owner餘額不足，無法進行退款, nft: {:?}", stake.nft_id.clone());
+                return None;
+            }
+
+            debug::info!("stake-nft 過期,已註銷, nft: {:?}", stake.nft_id.clone());
+
+            T::UniqueAssets::burn(&stake.nft_id.clone()).map_err(|err| debug::error!("err: {:?}", err)).ok();
+
+            T::Lease::revoke(stake.nft_id.clone(), stake.pallet_id).map_err(|err| debug::error!("err: {:?}", err)).ok();
+
+            T::Balances::transfer(&owner, user, stake.stake_amount, ExistenceRequirement::KeepAlive).map_err(|err| debug::error!("err: {:?}", err)).ok();
+
+            // remove record
+            StakeInfos::<T>::mutate(user, |stake_nft_data| {
+                stake_nft_data.retain(|probe| probe.nft_id != stake.nft_id);
+            });
+
+            Self::deposit_event(RawEvent::Expire(user.clone(), stake.nft_id.clone()));
+        } else {
+            debug::info!("stake-nft 過期,自動續約, nft: {:?}", stake.nft_id);
+
+            let _programs_list = Programs::<T>::get();
+            let valid_day_count = match _programs_list.iter().find(|&&probe| probe.program_id == stake.program_id) {
+                Some(program) => program.valid_day_count,
+                // the program was deleted, the stake stays as it is and leaves the queue
+                None => {
+                    debug::error!("stake-nft program not found, nft: {:?}", stake.nft_id);
+                    return Some(0);
+                }
+            };
+            // now time
+            let now = pallet_timestamp::Pallet::<T>::get();
+            let now_ms = TryInto::<u64>::try_into(now).ok().unwrap(); // convert to u64
+
+            // add N day
+            let n_day_ms = u64::try_from(chrono::Duration::days(valid_day_count as i64).num_milliseconds()).ok().unwrap();
+            let expires_at = ((now_ms + n_day_ms) / 1000) as i64;
+
+            let mut renewed = stake.clone();
+            renewed.expires_at = expires_at;
+            StakeInfos::<T>::mutate(user, |stake_info| {
+                if let Some(probe) = stake_info.iter_mut().find(|probe| probe.nft_id == stake.nft_id) {
+                    probe.expires_at = expires_at;
+                }
+            });
+            let probes = Self::schedule_expiry(user, &renewed);
+            Self::deposit_event(RawEvent::Renew(user.clone(), stake.nft_id.clone(), expires_at.to_string().into_bytes()));
+            return Some(probes);
         }
+        Some(0)
     }
 }
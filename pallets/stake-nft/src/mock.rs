@@ -107,6 +107,11 @@ impl pallet_lease::Config for Test {
     type OwnerAddress = BridgeOwner;
 }
 
+parameter_types! {
+    pub const MaxExpiriesPerBlock: u32 = 2;
+    pub const MaxExpiryBucketsPerBlock: u32 = 3;
+    pub const MaxExpiryProbes: u32 = 3;
+}
 impl pallet_stake_nft::Config for Test {
     type ProgramId = u64;
     type PalletId = u64;
@@ -115,6 +120,10 @@ impl pallet_stake_nft::Config for Test {
     type Lease = Lease;
     type OwnerAddress = BridgeOwner;
     type Event = Event;
+    type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+    type MaxExpiryBucketsPerBlock = MaxExpiryBucketsPerBlock;
+    type MaxExpiryProbes = MaxExpiryProbes;
+    type WeightInfo = ();
 }
 
 impl pallet_timestamp::Config for Test {
@@ -2,8 +2,8 @@
 use crate::mock::{new_test_ext, SubgameNFT, Lease, Origin, System, Timestamp};
 use crate::mock::*;
 use crate::*;
-use frame_support::{assert_ok, assert_err,
-    traits::{OnFinalize, OnInitialize},
+use frame_support::{assert_ok, assert_err, assert_noop, StorageMap, StorageValue,
+    traits::{OnFinalize, OnInitialize, OnRuntimeUpgrade},
 };
 
 
@@ -16,9 +16,24 @@ fn run_to_block(n: u64, t: u64) {
         Timestamp::set_timestamp(t);
         System::on_initialize(System::block_number());
         SubgameNFT::on_initialize(System::block_number());
+        SubgameStakeNft::on_initialize(System::block_number());
     }
 }
 
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// A program of 100 for 1 day on pallet 1, staked at timestamp 0 by each user. Returns their nfts.
+fn stake_for_a_day(users: &[u64], will_expire: bool) -> Vec<NftId<Test>> {
+    assert_ok!(SubgameStakeNft::add_program(Origin::signed(3), 1, 100, 1));
+    assert_ok!(Lease::add_pallet(Origin::signed(3), 1, Vec::<u8>::from("test pallet")));
+    users.iter().map(|user| {
+        assert_ok!(SubgameStakeNft::stake(Origin::signed(*user), 1, 1));
+        let nft_id = SubgameStakeNft::stake_infos(user)[0].nft_id;
+        assert_ok!(SubgameStakeNft::set_stake_will_expire(Origin::signed(*user), nft_id, will_expire));
+        nft_id
+    }).collect()
+}
+
 
 // #[test]
 // fn stake() {
@@ -111,3 +126,232 @@ fn add_program_error_exist() {
         );
     });
 }
+
+#[test]
+fn stake_expires_from_queue() {
+    new_test_ext().execute_with(|| {
+        let user = 4;
+        let nft_id = stake_for_a_day(&[user], true)[0];
+
+        // 【Then】the stake is queued in the bucket of its expiry
+        let bucket = (DAY_MS / 1000) / EXPIRY_BUCKET_SECONDS as u64;
+        assert_eq!(vec![(user, nft_id)], SubgameStakeNft::expiry_queue(bucket));
+        assert_eq!(Some(bucket), SubgameStakeNft::next_expiry_bucket());
+        assert_eq!(1000000 - 100, Balances::free_balance(&user));
+
+        // 【When】the day is just over
+        run_to_block(2, DAY_MS);
+
+        // 【Then】it is not due yet
+        assert_eq!(1, SubgameStakeNft::stake_infos(user).len());
+
+        // 【When】a second later
+        run_to_block(3, DAY_MS + 1000);
+
+        // 【Then】it expired and was refunded
+        assert!(SubgameStakeNft::stake_infos(user).is_empty());
+        assert!(SubgameStakeNft::expiry_queue(bucket).is_empty());
+        assert_eq!(1000000, Balances::free_balance(&user));
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_stake_nft(RawEvent::Expire(user, nft_id))));
+    });
+}
+
+#[test]
+fn stake_renews_from_queue() {
+    new_test_ext().execute_with(|| {
+        let user = 4;
+        let nft_id = stake_for_a_day(&[user], false)[0];
+
+        // 【When】the day is over
+        run_to_block(2, DAY_MS + 1000);
+
+        // 【Then】the stake runs for another day and is queued again
+        let stakes = SubgameStakeNft::stake_infos(user);
+        let expires_at = ((2 * DAY_MS + 1000) / 1000) as i64;
+        assert_eq!(expires_at, stakes[0].expires_at);
+        let bucket = (expires_at / EXPIRY_BUCKET_SECONDS) as u64;
+        assert_eq!(vec![(user, nft_id)], SubgameStakeNft::expiry_queue(bucket));
+        assert!(SubgameStakeNft::expiry_queue(bucket - 1440).is_empty());
+        assert_eq!(1000000 - 100, Balances::free_balance(&user));
+    });
+}
+
+#[test]
+fn expiry_is_bounded_per_block() {
+    new_test_ext().execute_with(|| {
+        let users = [1, 2, 4];
+        stake_for_a_day(&users, true);
+
+        // 【Then】a bucket holds MaxExpiriesPerBlock stakes, the rest go to the next one
+        let bucket = (DAY_MS / 1000) / EXPIRY_BUCKET_SECONDS as u64;
+        assert_eq!(2, SubgameStakeNft::expiry_queue(bucket).len());
+        assert_eq!(1, SubgameStakeNft::expiry_queue(bucket + 1).len());
+
+        // 【When】all of them are due
+        run_to_block(2, DAY_MS + 2 * 60 * 1000);
+
+        // 【Then】only MaxExpiriesPerBlock of them expire in the block
+        let left = users.iter().filter(|user| !SubgameStakeNft::stake_infos(*user).is_empty()).count();
+        assert_eq!(1, left);
+        assert_eq!(Some(bucket + 1), SubgameStakeNft::next_expiry_bucket());
+
+        // 【When】the next block
+        run_to_block(3, DAY_MS + 2 * 60 * 1000);
+
+        // 【Then】the rest expire and the queue catches up with now
+        assert!(users.iter().all(|user| SubgameStakeNft::stake_infos(user).is_empty()));
+        assert_eq!(Some(bucket + 2), SubgameStakeNft::next_expiry_bucket());
+    });
+}
+
+#[test]
+fn stake_fails_when_the_buckets_looked_at_are_full() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(SubgameStakeNft::add_program(Origin::signed(3), 1, 100, 1));
+        assert_ok!(Lease::add_pallet(Origin::signed(3), 1, Vec::<u8>::from("test pallet")));
+        let bucket = (DAY_MS / 1000) / EXPIRY_BUCKET_SECONDS as u64;
+
+        // 【Given】the MaxExpiryProbes buckets from the expiry on are full
+        for full in bucket..bucket + 3 {
+            ExpiryQueue::<Test>::insert(full, vec![(1, NftId::<Test>::default()); 2]);
+        }
+
+        // 【When】【Then】the stake fails and nothing is staked
+        assert_noop!(SubgameStakeNft::stake(Origin::signed(4), 1, 1), Error::<Test>::ExpiryQueueFull);
+        assert!(SubgameStakeNft::expiry_queue(bucket + 3).is_empty());
+
+        // 【When】the last bucket looked at has room
+        ExpiryQueue::<Test>::mutate(bucket + 2, |entries| entries.pop());
+        assert_ok!(SubgameStakeNft::stake(Origin::signed(4), 1, 1));
+
+        // 【Then】the stake is queued in it
+        let nft_4 = SubgameStakeNft::stake_infos(4)[0].nft_id;
+        assert_eq!(Some(&(4, nft_4)), SubgameStakeNft::expiry_queue(bucket + 2).last());
+        assert_eq!(1000000 - 100, Balances::free_balance(&4));
+    });
+}
+
+#[test]
+fn unfunded_refund_does_not_hold_up_the_queue() {
+    new_test_ext().execute_with(|| {
+        let owner = 3;
+        assert_ok!(SubgameStakeNft::add_program(Origin::signed(owner), 1, 100, 1));
+        assert_ok!(Lease::add_pallet(Origin::signed(owner), 1, Vec::<u8>::from("test pallet")));
+
+        // 【Given】user 1 stakes to expire, user 2 stakes two minutes later to renew
+        assert_ok!(SubgameStakeNft::stake(Origin::signed(1), 1, 1));
+        let nft_1 = SubgameStakeNft::stake_infos(1)[0].nft_id;
+        assert_ok!(SubgameStakeNft::set_stake_will_expire(Origin::signed(1), nft_1, true));
+        Timestamp::set_timestamp(2 * 60 * 1000);
+        assert_ok!(SubgameStakeNft::stake(Origin::signed(2), 1, 1));
+        let bucket = (DAY_MS / 1000) / EXPIRY_BUCKET_SECONDS as u64;
+
+        // 【Given】the owner cannot refund
+        let owner_free = Balances::free_balance(&owner);
+        assert_ok!(Balances::transfer(Origin::signed(owner), 5, owner_free));
+
+        // 【When】both are due
+        run_to_block(2, DAY_MS + 3 * 60 * 1000 + 1000);
+
+        // 【Then】the unfunded stake moves to the bucket after now and the later one is renewed
+        assert_eq!(1, SubgameStakeNft::stake_infos(1).len());
+        assert_eq!(vec![(1, nft_1)], SubgameStakeNft::expiry_queue(bucket + 4));
+        assert!(SubgameStakeNft::expiry_queue(bucket).is_empty());
+        assert_eq!(((2 * DAY_MS + 3 * 60 * 1000 + 1000) / 1000) as i64, SubgameStakeNft::stake_infos(2)[0].expires_at);
+        assert_eq!(Some(bucket + 3), SubgameStakeNft::next_expiry_bucket());
+
+        // 【When】the owner is funded again and the retry is due
+        assert_ok!(Balances::transfer(Origin::signed(5), owner, 1000));
+        run_to_block(3, DAY_MS + 4 * 60 * 1000 + 1000);
+
+        // 【Then】the stake expires and is refunded
+        assert!(SubgameStakeNft::stake_infos(1).is_empty());
+        assert_eq!(1000000, Balances::free_balance(&1));
+    });
+}
+
+#[test]
+fn stale_entries_count_as_reads() {
+    new_test_ext().execute_with(|| {
+        stake_for_a_day(&[1, 2], true);
+        let bucket = (DAY_MS / 1000) / EXPIRY_BUCKET_SECONDS as u64;
+
+        // 【Given】both stakes are gone, their entries are stale, and a stake is due in the next bucket
+        StakeInfos::<Test>::remove(1);
+        StakeInfos::<Test>::remove(2);
+        Timestamp::set_timestamp(60 * 1000);
+        assert_ok!(SubgameStakeNft::stake(Origin::signed(4), 1, 1));
+        let nft_4 = SubgameStakeNft::stake_infos(4)[0].nft_id;
+        assert_ok!(SubgameStakeNft::set_stake_will_expire(Origin::signed(4), nft_4, true));
+
+        // 【When】all of them are due
+        run_to_block(2, DAY_MS + 2 * 60 * 1000);
+
+        // 【Then】reading the stale entries used up the block
+        assert_eq!(1, SubgameStakeNft::stake_infos(4).len());
+        assert!(SubgameStakeNft::expiry_queue(bucket).is_empty());
+        assert_eq!(Some(bucket + 1), SubgameStakeNft::next_expiry_bucket());
+
+        // 【When】the next block
+        run_to_block(3, DAY_MS + 2 * 60 * 1000);
+
+        // 【Then】the due stake expires
+        assert!(SubgameStakeNft::stake_infos(4).is_empty());
+    });
+}
+
+#[test]
+fn renewals_into_a_kept_bucket_stay_bounded() {
+    new_test_ext().execute_with(|| {
+        // 【Given】a program valid for 0 days, so a stake renews into the bucket of now
+        assert_ok!(SubgameStakeNft::add_program(Origin::signed(3), 1, 100, 0));
+        assert_ok!(Lease::add_pallet(Origin::signed(3), 1, Vec::<u8>::from("test pallet")));
+        Timestamp::set_timestamp(1000);
+        assert_ok!(SubgameStakeNft::stake(Origin::signed(1), 1, 1));
+        let nft_1 = SubgameStakeNft::stake_infos(1)[0].nft_id;
+        // 【Given】a stake due at the end of the same bucket
+        Timestamp::set_timestamp(59 * 1000);
+        assert_ok!(SubgameStakeNft::stake(Origin::signed(4), 1, 1));
+        let nft_4 = SubgameStakeNft::stake_infos(4)[0].nft_id;
+        assert_eq!(vec![(1, nft_1), (4, nft_4)], SubgameStakeNft::expiry_queue(0));
+
+        // 【When】only the first is due
+        run_to_block(2, 2000);
+
+        // 【Then】it renews behind the kept one and the bucket stays within MaxExpiriesPerBlock
+        assert_eq!(2, SubgameStakeNft::stake_infos(1)[0].expires_at);
+        assert_eq!(vec![(4, nft_4), (1, nft_1)], SubgameStakeNft::expiry_queue(0));
+        assert!(SubgameStakeNft::expiry_queue(1).is_empty());
+        assert_eq!(Some(0), SubgameStakeNft::next_expiry_bucket());
+    });
+}
+
+#[test]
+fn stakes_before_the_queue_are_queued_on_upgrade() {
+    new_test_ext().execute_with(|| {
+        let user = 4;
+        let nft_id = stake_for_a_day(&[user], true)[0];
+        let bucket = (DAY_MS / 1000) / EXPIRY_BUCKET_SECONDS as u64;
+
+        // 【Given】a stake made before the queue existed
+        ExpiryQueue::<Test>::remove(bucket);
+        NextExpiryBucket::kill();
+        assert!(!SubgameStakeNft::expiry_queue_built());
+
+        // 【When】the runtime is upgraded, twice
+        SubgameStakeNft::on_runtime_upgrade();
+        SubgameStakeNft::on_runtime_upgrade();
+
+        // 【Then】the stake is queued once
+        assert_eq!(vec![(user, nft_id)], SubgameStakeNft::expiry_queue(bucket));
+        assert_eq!(Some(bucket), SubgameStakeNft::next_expiry_bucket());
+        assert!(SubgameStakeNft::expiry_queue_built());
+
+        // 【When】it is due
+        run_to_block(2, DAY_MS + 1000);
+
+        // 【Then】it expires and is refunded
+        assert!(SubgameStakeNft::stake_infos(user).is_empty());
+        assert_eq!(1000000, Balances::free_balance(&user));
+    });
+}
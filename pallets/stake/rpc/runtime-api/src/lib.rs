@@ -0,0 +1,21 @@
+//! Runtime API definition for the Stake pallet, lets front-ends read the staking rewards and referrals
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait StakeApi<AccountId, Balance> where
+        AccountId: Codec,
+        Balance: Codec,
+    {
+        /// Reward the account could claim now
+        fn pending_reward(who: AccountId) -> Balance;
+        /// Accounts referred by the account, level by level
+        fn downline(who: AccountId) -> Vec<Vec<AccountId>>;
+        /// Commissions the account earned since sign up
+        fn referral_earnings(who: AccountId) -> Balance;
+    }
+}
@@ -0,0 +1,104 @@
+//! RPC interface for the Stake pallet, reads the staking rewards and referrals from the runtime
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use pallet_stake_runtime_api::StakeApi as StakeRuntimeApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Error code of a failed runtime call
+const RUNTIME_ERROR: i64 = 1;
+
+#[rpc]
+pub trait StakeApi<BlockHash, AccountId, Balance> {
+    /// Reward the account could claim now
+    #[rpc(name = "stake_pendingReward")]
+    fn pending_reward(
+        &self,
+        who: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Balance>;
+
+    /// Accounts referred by the account, level by level
+    #[rpc(name = "stake_downline")]
+    fn downline(
+        &self,
+        who: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<Vec<AccountId>>>;
+
+    /// Commissions the account earned since sign up
+    #[rpc(name = "stake_referralEarnings")]
+    fn referral_earnings(
+        &self,
+        who: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Balance>;
+}
+
+/// Implements the StakeApi RPC trait for the staking rewards
+pub struct Stake<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Stake<C, B> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+fn runtime_error(err: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query the stake.".into(),
+        data: Some(format!("{:?}", err).into()),
+    }
+}
+
+impl<C, Block, AccountId, Balance>
+    StakeApi<<Block as BlockT>::Hash, AccountId, Balance> for Stake<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: StakeRuntimeApi<Block, AccountId, Balance>,
+    AccountId: Codec,
+    Balance: Codec,
+{
+    fn pending_reward(
+        &self,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Balance> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.pending_reward(&at, who).map_err(runtime_error)
+    }
+
+    fn downline(
+        &self,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<Vec<AccountId>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.downline(&at, who).map_err(runtime_error)
+    }
+
+    fn referral_earnings(
+        &self,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Balance> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.referral_earnings(&at, who).map_err(runtime_error)
+    }
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 use frame_system::RawOrigin;
-use frame_benchmarking::{benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
 use sp_std::{vec, vec::Vec, boxed::Box};
 use sp_runtime::{
 	traits::{
@@ -15,8 +15,13 @@ use frame_support::{
 #[allow(unused)]
 use crate::Module as Pallet;
 
+/// Enough for the name deposit of `Usernames` as well
+fn default_balances<T: Config>() -> BalanceOf<T> {
+	1_000_000_000_000_000_000u64.saturated_into()
+}
+
 fn init<T: Config>() {
-	let default_balances: BalanceOf<T> = 1000000u64.saturated_into();
+	let default_balances: BalanceOf<T> = default_balances::<T>();
 	let owner: T::AccountId = T::OwnerAddress::get();
 	T::Currency::make_free_balance_be(&owner, default_balances);
 	let import_owner: T::AccountId = T::ImportAddress::get();
@@ -25,17 +30,30 @@ fn init<T: Config>() {
 	T::Currency::make_free_balance_be(&user, default_balances);
 }
 
+/// `l` accounts each referred by the next one, returns the account name of the first
+fn referral_chain<T: Config>(l: u32) -> Vec<u8> {
+	let mut referrer_account = ROOT_REFERRER.to_vec();
+	for i in (0 .. l).rev() {
+		let referrer: T::AccountId = account("referrer", i, 0);
+		T::Currency::make_free_balance_be(&referrer, default_balances::<T>());
+		let mut account_vec = "r000000".as_bytes().to_vec();
+		account_vec[6] += i as u8;
+		assert_ok!(Pallet::<T>::sign_up(RawOrigin::Signed(referrer).into(), account_vec.clone(), referrer_account));
+		referrer_account = account_vec;
+	}
+	referrer_account
+}
+
 benchmarks! {
 	sign_up {
 		init::<T>();
 		let user: T::AccountId = whitelisted_caller();
 		let account = "s234567";
         let account_vec = account.as_bytes().to_vec();
-        let referrer_account = "gametop";
-        let referrer_account_vec = referrer_account.as_bytes().to_vec();
-	}: _(RawOrigin::Signed(user), account_vec, referrer_account_vec)
+        let referrer_account_vec = referral_chain::<T>(1);
+	}: _(RawOrigin::Signed(user.clone()), account_vec, referrer_account_vec)
 	verify {
-		
+		assert!(Pallet::<T>::referrer(&user).is_some());
 	}
 
 	stake {
@@ -99,6 +117,7 @@ benchmarks! {
 	}
 
 	delete_user {
+		let d in 0 .. T::MaxDownline::get();
 		init::<T>();
 		let owner: T::AccountId = T::OwnerAddress::get();
 		let user: T::AccountId = whitelisted_caller();
@@ -107,9 +126,89 @@ benchmarks! {
         let referrer_account = "gametop";
         let referrer_account_vec = referrer_account.as_bytes().to_vec();
         assert_ok!(Pallet::<T>::sign_up(RawOrigin::Signed(user.clone()).into(), account_vec.clone(), referrer_account_vec.clone()));
+        for i in 0 .. d {
+            let referred: T::AccountId = frame_benchmarking::account("referred", i, 0);
+            T::Currency::make_free_balance_be(&referred, default_balances::<T>());
+            let mut referred_vec = "d000000".as_bytes().to_vec();
+            referred_vec[4] += (i / 100 % 10) as u8;
+            referred_vec[5] += (i / 10 % 10) as u8;
+            referred_vec[6] += (i % 10) as u8;
+            assert_ok!(Pallet::<T>::sign_up(RawOrigin::Signed(referred).into(), referred_vec, account_vec.clone()));
+        }
 	}: _(RawOrigin::Signed(owner), user.clone(), account_vec.clone())
 	verify {
-		
+		assert!(Pallet::<T>::downline(&user).is_empty());
+	}
+
+	unbond {
+		init::<T>();
+		let user: T::AccountId = whitelisted_caller();
+		let amount: u64 = 1000;
+        let account_vec = "s234567".as_bytes().to_vec();
+        let referrer_account_vec = "gametop".as_bytes().to_vec();
+        assert_ok!(Pallet::<T>::sign_up(RawOrigin::Signed(user.clone()).into(), account_vec, referrer_account_vec));
+        assert_ok!(Pallet::<T>::stake(RawOrigin::Signed(user.clone()).into(), amount.saturated_into()));
+        for _ in 1 .. T::MaxUnbondingChunks::get() {
+            assert_ok!(Pallet::<T>::unbond(RawOrigin::Signed(user.clone()).into(), 1u64.saturated_into()));
+        }
+	}: _(RawOrigin::Signed(user.clone()), 1u64.saturated_into())
+	verify {
+		assert_eq!(Pallet::<T>::unbonding_queue(&user).len() as u32, T::MaxUnbondingChunks::get());
+	}
+
+	withdraw_unbonded {
+		init::<T>();
+		let user: T::AccountId = whitelisted_caller();
+		let amount: u64 = 1000;
+        let account_vec = "s234567".as_bytes().to_vec();
+        let referrer_account_vec = "gametop".as_bytes().to_vec();
+        assert_ok!(Pallet::<T>::sign_up(RawOrigin::Signed(user.clone()).into(), account_vec, referrer_account_vec));
+        assert_ok!(Pallet::<T>::stake(RawOrigin::Signed(user.clone()).into(), amount.saturated_into()));
+        for _ in 0 .. T::MaxUnbondingChunks::get() {
+            assert_ok!(Pallet::<T>::unbond(RawOrigin::Signed(user.clone()).into(), 1u64.saturated_into()));
+        }
+        let now = frame_system::Module::<T>::block_number();
+        frame_system::Module::<T>::set_block_number(now + T::UnbondingPeriod::get());
+	}: _(RawOrigin::Signed(user.clone()))
+	verify {
+		assert!(Pallet::<T>::unbonding_queue(&user).is_empty());
+	}
+
+	set_reward_per_block {
+		init::<T>();
+		let owner: T::AccountId = T::OwnerAddress::get();
+	}: _(RawOrigin::Signed(owner), 10u64.saturated_into())
+	verify {
+		assert_eq!(Pallet::<T>::reward_per_block(), 10u64.saturated_into());
+	}
+
+	claim_reward {
+		let l in 0 .. T::MaxReferralDepth::get();
+		init::<T>();
+		let owner: T::AccountId = T::OwnerAddress::get();
+		let user: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&Pallet::<T>::account_id(), 1000000u64.saturated_into());
+        let referrer_account_vec = referral_chain::<T>(l);
+        assert_ok!(Pallet::<T>::set_commission_rates(RawOrigin::Signed(owner.clone()).into(), vec![Perbill::from_percent(1); l as usize]));
+        let account_vec = "s234567".as_bytes().to_vec();
+        assert_ok!(Pallet::<T>::sign_up(RawOrigin::Signed(user.clone()).into(), account_vec, referrer_account_vec));
+        assert_ok!(Pallet::<T>::stake(RawOrigin::Signed(user.clone()).into(), 1000u64.saturated_into()));
+        assert_ok!(Pallet::<T>::set_reward_per_block(RawOrigin::Signed(owner).into(), 10u64.saturated_into()));
+        let now = frame_system::Module::<T>::block_number();
+        frame_system::Module::<T>::set_block_number(now + 10u32.into());
+	}: _(RawOrigin::Signed(user.clone()))
+	verify {
+		assert!(Pallet::<T>::pending_reward(&user).is_zero());
+	}
+
+	set_commission_rates {
+		let l in 0 .. T::MaxReferralDepth::get();
+		init::<T>();
+		let owner: T::AccountId = T::OwnerAddress::get();
+		let rates = vec![Perbill::from_percent(1); l as usize];
+	}: _(RawOrigin::Signed(owner), rates.clone())
+	verify {
+		assert_eq!(Pallet::<T>::commission_rates(), rates);
 	}
 }
 
@@ -6,19 +6,19 @@ use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
 
 impl crate::WeightInfo for () {
     fn sign_up() -> Weight {
-		(48_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(2 as Weight))
-			.saturating_add(DbWeight::get().writes(2 as Weight))
+		(104_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
 	}
 	fn stake() -> Weight {
 		(78_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(3 as Weight))
-			.saturating_add(DbWeight::get().writes(2 as Weight))
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
 	}
 	fn unlock() -> Weight {
 		(84_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(3 as Weight))
-			.saturating_add(DbWeight::get().writes(2 as Weight))
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
 	}
 	fn withdraw() -> Weight {
 		(80_000_000 as Weight)
@@ -27,12 +27,43 @@ impl crate::WeightInfo for () {
 	}
 	fn import_stake() -> Weight {
 		(117_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn delete_user(d: u32, ) -> Weight {
+		(78_000_000 as Weight)
+			.saturating_add((3_000_000 as Weight).saturating_mul(d as Weight))
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(d as Weight)))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+			.saturating_add(DbWeight::get().writes((2 as Weight).saturating_mul(d as Weight)))
+	}
+	fn unbond() -> Weight {
+		(52_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn withdraw_unbonded() -> Weight {
+		(64_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn set_reward_per_block() -> Weight {
+		(36_000_000 as Weight)
 			.saturating_add(DbWeight::get().reads(4 as Weight))
 			.saturating_add(DbWeight::get().writes(3 as Weight))
 	}
-	fn delete_user() -> Weight {
-		(40_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(1 as Weight))
-			.saturating_add(DbWeight::get().writes(2 as Weight))
+	fn claim_reward(l: u32, ) -> Weight {
+		(98_000_000 as Weight)
+			.saturating_add((9_000_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().reads((2 as Weight).saturating_mul(l as Weight)))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+			.saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(l as Weight)))
+	}
+	fn set_commission_rates(l: u32, ) -> Weight {
+		(24_000_000 as Weight)
+			.saturating_add((50_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
 	}
 }
@@ -4,11 +4,16 @@
 
 use codec::{Decode, Encode};
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, 
+    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, transactional,
     traits::{Get, Currency, ReservableCurrency, ExistenceRequirement, Vec},
     weights::{Weight},
 };
 use frame_system::ensure_signed;
+use pallet_username::{OnNameTransfer, UsernameRegistry};
+use sp_runtime::{
+    ModuleId, Perbill,
+    traits::{AccountIdConversion, SaturatedConversion, Saturating, Zero},
+};
 
 #[cfg(test)]
 mod mock;
@@ -27,9 +32,24 @@ pub trait WeightInfo {
     fn unlock() -> Weight;
     fn withdraw() -> Weight;
     fn import_stake() -> Weight;
-    fn delete_user() -> Weight;
+    fn delete_user(d: u32) -> Weight;
+    fn unbond() -> Weight;
+    fn withdraw_unbonded() -> Weight;
+    fn set_reward_per_block() -> Weight;
+    fn claim_reward(l: u32) -> Weight;
+    fn set_commission_rates(l: u32) -> Weight;
 }
 
+/// Scale of `AccRewardPerShare`
+pub const ACC_REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// The stake's module id, its account pays the rewards and is funded by plain transfers
+const MODULE_ID: ModuleId = ModuleId(*b"sgb/stak");
+
+/// Referrer of the accounts signing up without a referrer, the root of the referral tree.
+/// It is reserved in `Usernames`, by the genesis config or on runtime upgrade.
+pub const ROOT_REFERRER: &[u8] = b"gametop";
+
 type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 #[derive(Encode, Decode, Default)]
@@ -38,6 +58,34 @@ pub struct UserInfo<Account, ReferrerAccount> {
     pub referrer_account: ReferrerAccount,
 }
 
+/// Stake on its way out, still reserved until `unlock_at`
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct UnbondingChunk<Balance, BlockNumber> {
+    pub amount: Balance,
+    pub unlock_at: BlockNumber,
+}
+
+/// Rewards of a staker
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StakerReward<Balance> {
+    /// Reward per share times the stake when it last changed, already counted
+    pub reward_debt: u128,
+    /// Rewards earned before the stake last changed and not claimed yet
+    pub unclaimed: Balance,
+}
+
+/// Commissions of a referrer
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ReferralReward<Balance> {
+    /// Commissions not claimed yet
+    pub unclaimed: Balance,
+    /// Commissions earned since sign up
+    pub total: Balance,
+}
+
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type Balances: Currency<Self::AccountId>;
@@ -45,14 +93,43 @@ pub trait Config: frame_system::Config {
     type ImportAddress: Get<Self::AccountId>;
     type WeightInfo: WeightInfo;
     type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+    /// Blocks between `unbond` and `withdraw_unbonded`
+    type UnbondingPeriod: Get<Self::BlockNumber>;
+    /// Unbonding chunks an account can have at once
+    type MaxUnbondingChunks: Get<u32>;
+    /// Referrer levels paid a commission, the size of `CommissionRates`
+    type MaxReferralDepth: Get<u32>;
+    /// Accounts a referrer can refer
+    type MaxDownline: Get<u32>;
+    /// Registry of the account names users sign up with
+    type Usernames: UsernameRegistry<Self::AccountId>;
 }
 
 decl_storage! {
     trait Store for Module<T: Config> as Chips {
         pub UserInfoMap get(fn user_info_map): map hasher(blake2_128_concat) T::AccountId => UserInfo<Vec<u8>, Vec<u8>>;
+        /// Account names of the users signed up before `Usernames`, moved there on runtime upgrade
         pub AccountMap get(fn account_map): map hasher(blake2_128_concat) Vec<u8> => T::AccountId;
         pub UserStake get(fn user_stake): map hasher(blake2_128_concat) T::AccountId => BalanceOf<T>;
         pub StakePool get(fn stake_pool): BalanceOf<T>;
+        /// Stake unbonded by the account, oldest first
+        pub UnbondingQueue get(fn unbonding_queue): map hasher(blake2_128_concat) T::AccountId => Vec<UnbondingChunk<BalanceOf<T>, T::BlockNumber>>;
+        /// Reward shared by the stakers every block
+        pub RewardPerBlock get(fn reward_per_block): BalanceOf<T>;
+        /// Reward earned by one staked unit since rewards started, times `ACC_REWARD_PRECISION`
+        pub AccRewardPerShare get(fn acc_reward_per_share): u128;
+        /// Block `AccRewardPerShare` runs up to
+        pub LastRewardBlock get(fn last_reward_block): T::BlockNumber;
+        pub StakerRewards get(fn staker_reward): map hasher(blake2_128_concat) T::AccountId => StakerReward<BalanceOf<T>>;
+        /// Account the account signed up with as referrer, none for `ROOT_REFERRER`
+        pub Referrer get(fn referrer): map hasher(blake2_128_concat) T::AccountId => Option<T::AccountId>;
+        /// Accounts the account referred, at most `MaxDownline`
+        pub Downline get(fn downline): map hasher(blake2_128_concat) T::AccountId => Vec<T::AccountId>;
+        /// Share of a claimed staking reward paid to the referrers, the nearest first
+        pub CommissionRates get(fn commission_rates): Vec<Perbill>;
+        pub ReferralRewards get(fn referral_reward): map hasher(blake2_128_concat) T::AccountId => ReferralReward<BalanceOf<T>>;
+        /// The names in `AccountMap` have been moved to `Usernames`
+        AccountNamesMigrated get(fn account_names_migrated): bool;
     }
 }
 
@@ -61,23 +138,51 @@ decl_event!(
     where
         AccountId = <T as frame_system::Config>::AccountId,
         Balance = BalanceOf<T>,
+        BlockNumber = <T as frame_system::Config>::BlockNumber,
     {
         SignUp(AccountId, Vec<u8>, Vec<u8>),
         Stake(AccountId, Balance),
         Unlock(AccountId, Balance),
         Withdraw(AccountId, Balance),
         DeleteUser(AccountId, Vec<u8>),
+        /// Stake unbonded, withdrawable from the block
+        Unbond(AccountId, Balance, BlockNumber),
+        WithdrawUnbonded(AccountId, Balance),
+        RewardPerBlockSet(Balance),
+        RewardClaimed(AccountId, Balance),
+        CommissionRatesSet(Vec<Perbill>),
+        /// Referrer, staker who claimed, commission
+        ReferralCommission(AccountId, AccountId, Balance),
     }
 );
 
 decl_error! {
     pub enum Error for Module<T: Config> {
         UserExists,
+        /// The account name is not the user's
         AccountFormatIsWrong,
         UserNotExists,
         MoneyNotEnough,
         PermissionDenied,
         StakeAmountWrong,
+        /// The account has `MaxUnbondingChunks` chunks unbonding already
+        TooManyUnbondingChunks,
+        /// No unbonding chunk is past its unbonding period
+        NothingToWithdraw,
+        /// No reward to claim
+        NoReward,
+        /// The reward account cannot pay the reward now
+        RewardNotEnough,
+        /// The referrer account name is not signed up
+        ReferrerNotExists,
+        /// The referrer referred `MaxDownline` accounts already
+        TooManyReferrals,
+        /// More commission rates than `MaxReferralDepth`
+        TooManyCommissionLevels,
+        /// Commission rates adding up to more than the reward
+        InvalidCommissionRates,
+        /// The name is the one the user signed up with
+        NameInUse,
     }
 }
 
@@ -86,27 +191,46 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            Self::migrate_account_names()
+        }
+
+        /// Sign up with an account name, registered for the caller in `Usernames` unless the
+        /// caller owns it there already, and the account name of the referrer
         #[weight = T::WeightInfo::sign_up()]
+        #[transactional]
         pub fn sign_up(origin, account: Vec<u8>, referrer_account: Vec<u8>) -> dispatch::DispatchResult {
             let _who = ensure_signed(origin)?;
-            
-            let _account_str = core::str::from_utf8(&account).unwrap().to_lowercase();
-            ensure!(_account_str.len() == 7, Error::<T>::AccountFormatIsWrong);
-            ensure!(_account_str != "gametop", Error::<T>::AccountFormatIsWrong);
-            let _account = _account_str.as_bytes().to_vec();
-            
-            let _referrer_account_str = core::str::from_utf8(&referrer_account).unwrap().to_lowercase();
-            let _referrer_account = _referrer_account_str.as_bytes().to_vec();
-
             ensure!(!UserInfoMap::<T>::contains_key(&_who), Error::<T>::UserExists);
-            ensure!(!AccountMap::<T>::contains_key(_account.clone()), Error::<T>::UserExists);
+            if let Some(owner) = T::Usernames::lookup(&account) {
+                ensure!(owner == _who, Error::<T>::UserExists);
+            }
+
+            let _referrer_account = referrer_account.to_ascii_lowercase();
+            let referrer = if _referrer_account == ROOT_REFERRER {
+                None
+            } else {
+                let referrer = T::Usernames::lookup(&_referrer_account)
+                    .filter(|referrer| UserInfoMap::<T>::contains_key(referrer))
+                    .ok_or(Error::<T>::ReferrerNotExists)?;
+                Some(referrer)
+            };
+
+            let _account = T::Usernames::register(&_who, &account)?;
+
+            if let Some(referrer) = referrer {
+                let mut downline = Self::downline(&referrer);
+                ensure!((downline.len() as u32) < T::MaxDownline::get(), Error::<T>::TooManyReferrals);
+                downline.push(_who.clone());
+                <Downline::<T>>::insert(&referrer, downline);
+                <Referrer::<T>>::insert(&_who, referrer);
+            }
 
             let user_info = UserInfo{
                 account: _account.clone(),
                 referrer_account: _referrer_account.clone(),
             };
             <UserInfoMap::<T>>::insert(&_who, user_info);
-            <AccountMap::<T>>::insert(_account.clone(), &_who);
 
             Self::deposit_event(RawEvent::SignUp(_who, _account, _referrer_account));
             Ok(())
@@ -118,13 +242,61 @@ decl_module! {
             ensure!(UserInfoMap::<T>::contains_key(&_who), Error::<T>::UserNotExists);
 
             T::Currency::reserve(&_who, amount).map_err(|_| Error::<T>::MoneyNotEnough )?;
+            Self::set_user_stake(&_who, Self::user_stake(&_who) + amount);
             <StakePool::<T>>::put(Self::stake_pool() + amount);
-            <UserStake::<T>>::insert(&_who, Self::user_stake(&_who) + amount);
 
             Self::deposit_event(RawEvent::Stake(_who, amount));
             Ok(())
         }
 
+        /// Start unbonding `amount` of the caller's stake. It stops counting as stake now and
+        /// stays reserved until `withdraw_unbonded` after `UnbondingPeriod` blocks.
+        #[weight = T::WeightInfo::unbond()]
+        pub fn unbond(origin, amount: BalanceOf<T>) -> dispatch::DispatchResult {
+            let _who = ensure_signed(origin)?;
+            ensure!(UserInfoMap::<T>::contains_key(&_who), Error::<T>::UserNotExists);
+            ensure!(!amount.is_zero(), Error::<T>::StakeAmountWrong);
+
+            let user_stake = Self::user_stake(&_who);
+            ensure!(user_stake >= amount, Error::<T>::MoneyNotEnough);
+            let mut queue = Self::unbonding_queue(&_who);
+            ensure!((queue.len() as u32) < T::MaxUnbondingChunks::get(), Error::<T>::TooManyUnbondingChunks);
+
+            let unlock_at = frame_system::Module::<T>::block_number().saturating_add(T::UnbondingPeriod::get());
+            queue.push(UnbondingChunk { amount, unlock_at });
+            <UnbondingQueue::<T>>::insert(&_who, queue);
+            Self::set_user_stake(&_who, user_stake - amount);
+            <StakePool::<T>>::put(Self::stake_pool().saturating_sub(amount));
+
+            Self::deposit_event(RawEvent::Unbond(_who, amount, unlock_at));
+            Ok(())
+        }
+
+        /// Unreserve every unbonding chunk of the caller past its unbonding period
+        #[weight = T::WeightInfo::withdraw_unbonded()]
+        pub fn withdraw_unbonded(origin) -> dispatch::DispatchResult {
+            let _who = ensure_signed(origin)?;
+
+            let now = frame_system::Module::<T>::block_number();
+            let (unlocked, queue): (Vec<_>, Vec<_>) = Self::unbonding_queue(&_who)
+                .into_iter()
+                .partition(|chunk| chunk.unlock_at <= now);
+            let amount = unlocked.iter().fold(BalanceOf::<T>::zero(), |sum, chunk| sum.saturating_add(chunk.amount));
+            ensure!(!amount.is_zero(), Error::<T>::NothingToWithdraw);
+
+            T::Currency::unreserve(&_who, amount);
+            if queue.is_empty() {
+                <UnbondingQueue::<T>>::remove(&_who);
+            } else {
+                <UnbondingQueue::<T>>::insert(&_who, queue);
+            }
+
+            Self::deposit_event(RawEvent::WithdrawUnbonded(_who, amount));
+            Ok(())
+        }
+
+        /// Emergency path: the owner unreserves stake at once, without the unbonding period.
+        /// Users leave with `unbond` and `withdraw_unbonded`.
         #[weight = T::WeightInfo::unlock()]
         pub fn unlock(origin, _who: T::AccountId, amount: BalanceOf<T>) -> dispatch::DispatchResult {
             let sender = ensure_signed(origin)?;
@@ -140,8 +312,8 @@ decl_module! {
             ensure!(stake_pool >= amount, Error::<T>::MoneyNotEnough);
 
             T::Currency::unreserve(&_who, amount);
+            Self::set_user_stake(&_who, user_stake - amount);
             <StakePool::<T>>::put(stake_pool - amount);
-            <UserStake::<T>>::insert(&_who, user_stake - amount);
 
             Self::deposit_event(RawEvent::Unlock(_who, amount));
             Ok(())
@@ -172,34 +344,232 @@ decl_module! {
             T::Currency::transfer(&import_owner, &_who, amount, ExistenceRequirement::KeepAlive).map_err(|_| Error::<T>::MoneyNotEnough )?;
 
             T::Currency::reserve(&_who, amount).map_err(|_| Error::<T>::MoneyNotEnough )?;
+            Self::set_user_stake(&_who, Self::user_stake(&_who) + amount);
             <StakePool::<T>>::put(Self::stake_pool() + amount);
-            <UserStake::<T>>::insert(&_who, Self::user_stake(&_who) + amount);
 
             Self::deposit_event(RawEvent::Stake(_who, amount));
             Ok(())
         }
 
-        #[weight = T::WeightInfo::delete_user()]
+        /// The owner removes a user, releasing its account name in `Usernames`. The user leaves
+        /// the downline of its referrer and the accounts it referred move under the root.
+        #[weight = T::WeightInfo::delete_user(T::MaxDownline::get())]
+        #[transactional]
         pub fn delete_user(origin, _who: T::AccountId, account: Vec<u8>) -> dispatch::DispatchResult {
             let sender = ensure_signed(origin)?;
             let owner = T::OwnerAddress::get();
             ensure!(owner == sender, Error::<T>::PermissionDenied);
             ensure!(UserInfoMap::<T>::contains_key(&_who), Error::<T>::UserNotExists);
 
-            let _account_str = core::str::from_utf8(&account).unwrap().to_lowercase();
-            ensure!(_account_str.len() == 7, Error::<T>::AccountFormatIsWrong);
-            ensure!(_account_str != "gametop", Error::<T>::AccountFormatIsWrong);
-            let _account = _account_str.as_bytes().to_vec();
+            let _account = Self::user_info_map(&_who).account;
+            ensure!(account.to_ascii_lowercase() == _account, Error::<T>::AccountFormatIsWrong);
 
             <UserInfoMap::<T>>::remove(&_who);
-            <AccountMap::<T>>::remove(_account.clone());
+            if let Some(referrer) = <Referrer::<T>>::take(&_who) {
+                <Downline::<T>>::mutate(&referrer, |downline| downline.retain(|account| account != &_who));
+            }
+            for referred in <Downline::<T>>::take(&_who) {
+                <Referrer::<T>>::remove(&referred);
+                <UserInfoMap::<T>>::mutate(&referred, |info| info.referrer_account = ROOT_REFERRER.to_vec());
+            }
+            if T::Usernames::name_of(&_who) == Some(_account) {
+                T::Usernames::release(&_who)?;
+            }
 
             Self::deposit_event(RawEvent::DeleteUser(_who, account));
             Ok(())
         }
+
+        /// The owner sets the reward shared by the stakers every block from now on
+        #[weight = T::WeightInfo::set_reward_per_block()]
+        pub fn set_reward_per_block(origin, amount: BalanceOf<T>) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let owner = T::OwnerAddress::get();
+            ensure!(owner == sender, Error::<T>::PermissionDenied);
+
+            Self::update_pool();
+            <RewardPerBlock::<T>>::put(amount);
+
+            Self::deposit_event(RawEvent::RewardPerBlockSet(amount));
+            Ok(())
+        }
+
+        /// Pay the caller the rewards and commissions earned so far from the reward account.
+        /// The caller's referrers are credited their commission on the staking rewards paid.
+        #[weight = T::WeightInfo::claim_reward(T::MaxReferralDepth::get())]
+        #[transactional]
+        pub fn claim_reward(origin) -> dispatch::DispatchResult {
+            let _who = ensure_signed(origin)?;
+
+            Self::settle(&_who);
+            let mut reward = Self::staker_reward(&_who);
+            let mut referral = Self::referral_reward(&_who);
+            let staking_reward = reward.unclaimed;
+            let amount = staking_reward.saturating_add(referral.unclaimed);
+            ensure!(!amount.is_zero(), Error::<T>::NoReward);
+
+            T::Currency::transfer(&Self::account_id(), &_who, amount, ExistenceRequirement::AllowDeath)
+                .map_err(|_| Error::<T>::RewardNotEnough)?;
+            reward.unclaimed = Zero::zero();
+            <StakerRewards::<T>>::insert(&_who, reward);
+            if !referral.unclaimed.is_zero() {
+                referral.unclaimed = Zero::zero();
+                <ReferralRewards::<T>>::insert(&_who, referral);
+            }
+            Self::pay_commissions(&_who, staking_reward);
+
+            Self::deposit_event(RawEvent::RewardClaimed(_who, amount));
+            Ok(())
+        }
+
+        /// The owner sets the commission of each referrer level, the nearest first
+        #[weight = T::WeightInfo::set_commission_rates(rates.len() as u32)]
+        pub fn set_commission_rates(origin, rates: Vec<Perbill>) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let owner = T::OwnerAddress::get();
+            ensure!(owner == sender, Error::<T>::PermissionDenied);
+            ensure!((rates.len() as u32) <= T::MaxReferralDepth::get(), Error::<T>::TooManyCommissionLevels);
+            let total = rates.iter().fold(0u64, |sum, rate| sum + rate.deconstruct() as u64);
+            ensure!(total <= Perbill::one().deconstruct() as u64, Error::<T>::InvalidCommissionRates);
+
+            <CommissionRates>::put(rates.clone());
+
+            Self::deposit_event(RawEvent::CommissionRatesSet(rates));
+            Ok(())
+        }
     }
 }
 
 impl<T: Config> Module<T> {
+    /// Give the users signed up before `Usernames` their account names there and reserve
+    /// `ROOT_REFERRER`. A name someone registered in the meantime stays theirs.
+    fn migrate_account_names() -> Weight {
+        if Self::account_names_migrated() {
+            return T::DbWeight::get().reads(1);
+        }
+        let mut reads: u64 = 2;
+        let mut writes: u64 = 2;
+        for (name, who) in AccountMap::<T>::drain() {
+            reads += 3;
+            writes += 1;
+            if T::Usernames::register_existing(&who, &name).is_ok() {
+                writes += 2;
+            }
+        }
+        if T::Usernames::reserve(ROOT_REFERRER).is_ok() {
+            writes += 1;
+        }
+        AccountNamesMigrated::put(true);
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+
+    /// Account paying the rewards
+    pub fn account_id() -> T::AccountId {
+        MODULE_ID.into_account()
+    }
+
+    /// Rewards and commissions the account could claim now
+    pub fn pending_reward(who: &T::AccountId) -> BalanceOf<T> {
+        let reward = Self::staker_reward(who);
+        let earned = Self::earned(Self::user_stake(who), Self::current_acc_reward_per_share(), &reward);
+        reward.unclaimed.saturating_add(earned).saturating_add(Self::referral_reward(who).unclaimed)
+    }
+
+    /// Accounts referred by the account, level by level for `MaxReferralDepth` levels
+    pub fn downline_of(who: &T::AccountId) -> Vec<Vec<T::AccountId>> {
+        let mut levels = Vec::new();
+        let mut level = Self::downline(who);
+        for _ in 0..T::MaxReferralDepth::get() {
+            if level.is_empty() {
+                break;
+            }
+            let next = level.iter().flat_map(Self::downline).collect();
+            levels.push(level);
+            level = next;
+        }
+        levels
+    }
+
+    /// Commissions the account earned since sign up
+    pub fn referral_earnings(who: &T::AccountId) -> BalanceOf<T> {
+        Self::referral_reward(who).total
+    }
+
+    /// Credit the referrers of the staker their commission on the staking reward it claimed
+    fn pay_commissions(staker: &T::AccountId, reward: BalanceOf<T>) {
+        let mut account = staker.clone();
+        for rate in Self::commission_rates() {
+            let referrer = match Self::referrer(&account) {
+                Some(referrer) => referrer,
+                None => break,
+            };
+            let commission = rate.mul_floor(reward);
+            if !commission.is_zero() {
+                <ReferralRewards::<T>>::mutate(&referrer, |referral| {
+                    referral.unclaimed = referral.unclaimed.saturating_add(commission);
+                    referral.total = referral.total.saturating_add(commission);
+                });
+                Self::deposit_event(RawEvent::ReferralCommission(referrer.clone(), staker.clone(), commission));
+            }
+            account = referrer;
+        }
+    }
+
+    /// `AccRewardPerShare` run up to now, not written back. Nobody earns the reward of the
+    /// blocks without any stake, it stays in the reward account.
+    fn current_acc_reward_per_share() -> u128 {
+        let now = frame_system::Module::<T>::block_number();
+        let last = Self::last_reward_block();
+        let stake_pool: u128 = Self::stake_pool().saturated_into();
+        let acc = Self::acc_reward_per_share();
+        if now <= last || stake_pool == 0 {
+            return acc;
+        }
+        let blocks: u128 = (now - last).saturated_into();
+        let reward_per_block: u128 = Self::reward_per_block().saturated_into();
+        let reward = reward_per_block.saturating_mul(blocks);
+        acc.saturating_add(reward.saturating_mul(ACC_REWARD_PRECISION) / stake_pool)
+    }
 
-}
\ No newline at end of file
+    /// Run `AccRewardPerShare` up to now
+    fn update_pool() {
+        AccRewardPerShare::put(Self::current_acc_reward_per_share());
+        <LastRewardBlock::<T>>::put(frame_system::Module::<T>::block_number());
+    }
+
+    fn earned(stake: BalanceOf<T>, acc_reward_per_share: u128, reward: &StakerReward<BalanceOf<T>>) -> BalanceOf<T> {
+        let stake: u128 = stake.saturated_into();
+        let earned = stake.saturating_mul(acc_reward_per_share) / ACC_REWARD_PRECISION;
+        earned.saturating_sub(reward.reward_debt).saturated_into()
+    }
+
+    /// Move the reward earned on the current stake of the account into its unclaimed rewards
+    fn settle(who: &T::AccountId) {
+        Self::update_pool();
+        let acc = Self::acc_reward_per_share();
+        let stake = Self::user_stake(who);
+        <StakerRewards::<T>>::mutate(who, |reward| {
+            reward.unclaimed = reward.unclaimed.saturating_add(Self::earned(stake, acc, reward));
+            let stake: u128 = stake.saturated_into();
+            reward.reward_debt = stake.saturating_mul(acc) / ACC_REWARD_PRECISION;
+        });
+    }
+
+    /// Change the stake of the account, settling its rewards on the old stake first.
+    /// Called before `StakePool` changes.
+    fn set_user_stake(who: &T::AccountId, stake: BalanceOf<T>) {
+        Self::settle(who);
+        <UserStake::<T>>::insert(who, stake);
+        let stake: u128 = stake.saturated_into();
+        let reward_debt = stake.saturating_mul(Self::acc_reward_per_share()) / ACC_REWARD_PRECISION;
+        <StakerRewards::<T>>::mutate(who, |reward| reward.reward_debt = reward_debt);
+    }
+}
+
+impl<T: Config> OnNameTransfer<T::AccountId> for Module<T> {
+    /// Users are found by the name they signed up with, so they keep it
+    fn on_name_transfer(from: &T::AccountId, _: &T::AccountId, name: &[u8]) -> dispatch::DispatchResult {
+        ensure!(Self::user_info_map(from).account != name, Error::<T>::NameInUse);
+        Ok(())
+    }
+}
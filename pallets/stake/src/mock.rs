@@ -2,6 +2,7 @@ use crate as pallet_stake;
 use balances;
 use frame_support::parameter_types;
 use frame_system as system;
+use frame_system::EnsureRoot;
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
@@ -21,6 +22,7 @@ frame_support::construct_runtime!(
         System: frame_system::{Module, Call, Config, Storage, Event<T>},
         Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
         SubGameStake: pallet_stake::{Module, Call, Storage, Event<T>},
+        Username: pallet_username::{Module, Call, Storage, Event<T>, Config},
     }
 );
 
@@ -69,6 +71,10 @@ impl balances::Config for Test {
 
 parameter_types! {
     pub const StakeOwner: u64 = 1;
+    pub const UnbondingPeriod: u64 = 10;
+    pub const MaxUnbondingChunks: u32 = 3;
+    pub const MaxReferralDepth: u32 = 3;
+    pub const MaxDownline: u32 = 2;
 }
 impl pallet_stake::Config for Test {
     type Event = Event;
@@ -77,6 +83,28 @@ impl pallet_stake::Config for Test {
     type ImportAddress = StakeOwner;
     type WeightInfo = ();
     type Currency = balances::Module<Self>;
+    type UnbondingPeriod = UnbondingPeriod;
+    type MaxUnbondingChunks = MaxUnbondingChunks;
+    type MaxReferralDepth = MaxReferralDepth;
+    type MaxDownline = MaxDownline;
+    type Usernames = Username;
+}
+
+parameter_types! {
+    pub const NameDeposit: u64 = 0;
+    pub const MinNameLength: u32 = 3;
+    pub const MaxNameLength: u32 = 16;
+}
+impl pallet_username::Config for Test {
+    type Event = Event;
+    type Currency = balances::Module<Self>;
+    type NameDeposit = NameDeposit;
+    type MinNameLength = MinNameLength;
+    type MaxNameLength = MaxNameLength;
+    type Charset = pallet_username::LowercaseAlphanumeric;
+    type ReservedOrigin = EnsureRoot<Self::AccountId>;
+    type OnNameTransfer = SubGameStake;
+    type WeightInfo = ();
 }
 
 /// Build genesis storage according to the mock runtime.
@@ -96,6 +124,11 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
     }
     .assimilate_storage(&mut t)
     .unwrap();
+    pallet_username::GenesisConfig {
+        reserved_names: vec![crate::ROOT_REFERRER.to_vec()],
+    }
+    .assimilate_storage::<Test>(&mut t)
+    .unwrap();
     let mut ext: sp_io::TestExternalities = t.into();
     ext.execute_with(|| System::set_block_number(1));
     ext
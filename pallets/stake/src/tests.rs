@@ -1,5 +1,7 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_noop, assert_ok};
+use crate::{Error, ReferralReward, UnbondingChunk, UserInfo, mock::*};
+use pallet_username::UsernameRegistry;
+use sp_runtime::Perbill;
+use frame_support::{assert_noop, assert_ok, StorageMap, traits::OnRuntimeUpgrade};
 
 #[test]
 fn sign_up() {
@@ -43,17 +45,24 @@ fn sign_up_account_format_is_wrong() {
     new_test_ext().execute_with(|| {
         let user = 2;
         
-        let account = "ABCDEFG8";
+        let account = "ABCDEFGHIJKLMNOPQ";
         let account_vec = account.clone().as_bytes().to_vec();
 
         let referrer_account = "gametop";
         let referrer_account_vec = referrer_account.as_bytes().to_vec();
 
-        assert_noop!(SubGameStake::sign_up(Origin::signed(user.clone()), account_vec.clone(), referrer_account_vec.clone()), Error::<Test>::AccountFormatIsWrong);
+        assert_noop!(SubGameStake::sign_up(Origin::signed(user.clone()), account_vec.clone(), referrer_account_vec.clone()), pallet_username::Error::<Test>::InvalidLength);
         
         let account = "gametop";
         let account_vec = account.clone().as_bytes().to_vec();
-        assert_noop!(SubGameStake::sign_up(Origin::signed(user.clone()), account_vec.clone(), referrer_account_vec.clone()), Error::<Test>::AccountFormatIsWrong);
+        assert_noop!(SubGameStake::sign_up(Origin::signed(user.clone()), account_vec.clone(), referrer_account_vec.clone()), pallet_username::Error::<Test>::NameReserved);
+
+        // 【Then】names that are not UTF-8 fail instead of panicking
+        assert_noop!(SubGameStake::sign_up(Origin::signed(user.clone()), vec![b's', 0xc3, 0x28], referrer_account_vec.clone()), pallet_username::Error::<Test>::InvalidCharacter);
+        assert_noop!(SubGameStake::sign_up(Origin::signed(user.clone()), "s234567".as_bytes().to_vec(), vec![0xff]), Error::<Test>::ReferrerNotExists);
+
+        // 【Then】the 7-character rule is gone
+        assert_ok!(SubGameStake::sign_up(Origin::signed(user.clone()), "Player2".as_bytes().to_vec(), referrer_account_vec.clone()));
     });
 }
 
@@ -184,5 +193,345 @@ fn delete_user() {
 
         let want_account = "".as_bytes().to_vec();
         assert_eq!(want_account, SubGameStake::user_info_map(user.clone()).account);
+
+        // 【Then】the name is released in the registry
+        assert_eq!(None, Username::name(user));
+        assert_eq!(None, Username::registration(account_vec));
+    });
+}
+
+#[test]
+fn sign_up_with_registered_name() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+
+        // 【Given】user 2 owns a name in the registry
+        assert_ok!(Username::register(Origin::signed(user), "player2".as_bytes().to_vec()));
+
+        // 【Then】only user 2 signs up with it
+        assert_noop!(SubGameStake::sign_up(Origin::signed(3), "Player2".as_bytes().to_vec(), "gametop".as_bytes().to_vec()), Error::<Test>::UserExists);
+        assert_noop!(SubGameStake::sign_up(Origin::signed(user), "player3".as_bytes().to_vec(), "gametop".as_bytes().to_vec()), pallet_username::Error::<Test>::AlreadyHasName);
+        assert_ok!(SubGameStake::sign_up(Origin::signed(user), "Player2".as_bytes().to_vec(), "gametop".as_bytes().to_vec()));
+
+        assert_eq!("player2".as_bytes().to_vec(), SubGameStake::user_info_map(user).account);
+        assert_eq!(Some(user), <Username as UsernameRegistry<u64>>::lookup(b"player2"));
+    });
+}
+
+#[test]
+fn sign_up_with_transferred_name() {
+    new_test_ext().execute_with(|| {
+        // 【Given】user 2 registers a name without signing up and gives it to user 3
+        assert_ok!(Username::register(Origin::signed(2), "s234567".as_bytes().to_vec()));
+        assert_ok!(Username::transfer(Origin::signed(2), 3));
+
+        // 【Then】user 3 signs up with it and referrals by the name go to user 3
+        assert_ok!(SubGameStake::sign_up(Origin::signed(3), "s234567".as_bytes().to_vec(), "gametop".as_bytes().to_vec()));
+        assert_eq!("s234567".as_bytes().to_vec(), SubGameStake::user_info_map(3).account);
+        sign_up_referred(4, "s456789", "s234567");
+        assert_eq!(Some(3), SubGameStake::referrer(4));
+
+        // 【Then】a name whose owner is not signed up refers nobody
+        assert_ok!(Username::register(Origin::signed(5), "s567890".as_bytes().to_vec()));
+        assert_noop!(SubGameStake::sign_up(Origin::signed(1), "s111111".as_bytes().to_vec(), "s567890".as_bytes().to_vec()), Error::<Test>::ReferrerNotExists);
+    });
+}
+
+#[test]
+fn transfer_of_signed_up_name_fails() {
+    new_test_ext().execute_with(|| {
+        // 【Given】user 2 signs up
+        sign_up_referred(2, "s234567", "gametop");
+
+        // 【When】user 2 gives its name to user 3
+        // 【Then】the transfer is refused and the name stays with user 2
+        assert_noop!(Username::transfer(Origin::signed(2), 3), Error::<Test>::NameInUse);
+        assert_eq!(Some(2), <Username as UsernameRegistry<u64>>::lookup(b"s234567"));
+        sign_up_referred(4, "s456789", "s234567");
+        assert_eq!(Some(2), SubGameStake::referrer(4));
+    });
+}
+
+#[test]
+fn account_names_migration() {
+    new_test_ext().execute_with(|| {
+        // 【Given】users signed up before the registry, which does not reserve the root yet
+        for (user, account) in [(2u64, "s234567"), (3, "s345678")].iter() {
+            let account = account.as_bytes().to_vec();
+            crate::UserInfoMap::<Test>::insert(user, UserInfo { account: account.clone(), referrer_account: "gametop".as_bytes().to_vec() });
+            crate::AccountMap::<Test>::insert(account, user);
+        }
+        pallet_username::Reserved::remove("gametop".as_bytes().to_vec());
+        // 【Given】someone registered one of the names in the meantime
+        assert_ok!(Username::register(Origin::signed(5), "s345678".as_bytes().to_vec()));
+
+        // 【When】the runtime is upgraded
+        SubGameStake::on_runtime_upgrade();
+
+        // 【Then】the free names are registered without a deposit and the root is reserved
+        assert_eq!(Some(2), <Username as UsernameRegistry<u64>>::lookup(b"s234567"));
+        assert_eq!(0, Balances::reserved_balance(&2));
+        assert_eq!(Some(5), <Username as UsernameRegistry<u64>>::lookup(b"s345678"));
+        assert!(Username::is_reserved("gametop".as_bytes().to_vec()));
+        assert!(!crate::AccountMap::<Test>::contains_key("s234567".as_bytes().to_vec()));
+
+        // 【Then】the migrated users refer by their names
+        sign_up_referred(4, "s456789", "s234567");
+        assert_eq!(Some(2), SubGameStake::referrer(4));
+
+        // 【When】governance frees the root and the runtime is upgraded again
+        assert_ok!(Username::remove_reserved(Origin::root(), "gametop".as_bytes().to_vec()));
+        SubGameStake::on_runtime_upgrade();
+
+        // 【Then】the migration does not run again
+        assert!(SubGameStake::account_names_migrated());
+        assert!(!Username::is_reserved("gametop".as_bytes().to_vec()));
+    });
+}
+
+fn sign_up_and_stake(user: u64, account: &str, amount: u64) {
+    let account_vec = account.as_bytes().to_vec();
+    let referrer_account_vec = "gametop".as_bytes().to_vec();
+    assert_ok!(SubGameStake::sign_up(Origin::signed(user), account_vec, referrer_account_vec));
+    assert_ok!(SubGameStake::stake(Origin::signed(user), amount));
+}
+
+#[test]
+fn unbond() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+        sign_up_and_stake(user, "s234567", 1000);
+
+        // 【Then】only staked, non-zero amounts unbond
+        assert_noop!(SubGameStake::unbond(Origin::signed(3), 100), Error::<Test>::UserNotExists);
+        assert_noop!(SubGameStake::unbond(Origin::signed(user), 0), Error::<Test>::StakeAmountWrong);
+        assert_noop!(SubGameStake::unbond(Origin::signed(user), 1001), Error::<Test>::MoneyNotEnough);
+
+        // 【When】the user unbonds 400
+        assert_ok!(SubGameStake::unbond(Origin::signed(user), 400));
+
+        // 【Then】it leaves the stake but stays reserved for the unbonding period
+        assert_eq!(600, SubGameStake::user_stake(user));
+        assert_eq!(600, SubGameStake::stake_pool());
+        assert_eq!(1000, Balances::reserved_balance(&user));
+        assert_eq!(vec![UnbondingChunk { amount: 400, unlock_at: 11 }], SubGameStake::unbonding_queue(user));
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_stake(crate::RawEvent::Unbond(user, 400, 11))));
+
+        // 【Then】an account has at most MaxUnbondingChunks chunks
+        assert_ok!(SubGameStake::unbond(Origin::signed(user), 100));
+        assert_ok!(SubGameStake::unbond(Origin::signed(user), 100));
+        assert_noop!(SubGameStake::unbond(Origin::signed(user), 100), Error::<Test>::TooManyUnbondingChunks);
+    });
+}
+
+#[test]
+fn withdraw_unbonded() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+        let default_balance: u64 = 1000000;
+        sign_up_and_stake(user, "s234567", 1000);
+
+        // 【Given】400 unbonded at block 1 and 100 at block 5
+        assert_ok!(SubGameStake::unbond(Origin::signed(user), 400));
+        System::set_block_number(5);
+        assert_ok!(SubGameStake::unbond(Origin::signed(user), 100));
+
+        // 【Then】nothing is withdrawable before the unbonding period
+        System::set_block_number(10);
+        assert_noop!(SubGameStake::withdraw_unbonded(Origin::signed(user)), Error::<Test>::NothingToWithdraw);
+
+        // 【When】the first chunk is due
+        System::set_block_number(11);
+        assert_ok!(SubGameStake::withdraw_unbonded(Origin::signed(user)));
+
+        // 【Then】only it is unreserved
+        assert_eq!(600, Balances::reserved_balance(&user));
+        assert_eq!(default_balance - 600, Balances::free_balance(&user));
+        assert_eq!(vec![UnbondingChunk { amount: 100, unlock_at: 15 }], SubGameStake::unbonding_queue(user));
+
+        // 【When】the second chunk is due
+        System::set_block_number(20);
+        assert_ok!(SubGameStake::withdraw_unbonded(Origin::signed(user)));
+
+        // 【Then】the queue is gone and the rest is still staked
+        assert_eq!(500, Balances::reserved_balance(&user));
+        assert!(SubGameStake::unbonding_queue(user).is_empty());
+        assert_eq!(500, SubGameStake::user_stake(user));
+        assert_noop!(SubGameStake::withdraw_unbonded(Origin::signed(user)), Error::<Test>::NothingToWithdraw);
+    });
+}
+
+#[test]
+fn rewards_are_shared_by_stake() {
+    new_test_ext().execute_with(|| {
+        let owner = 1;
+        assert_ok!(Balances::transfer(Origin::signed(5), SubGameStake::account_id(), 100000));
+
+        // 【Given】user 2 stakes 1000 and the owner pays 10 a block
+        sign_up_and_stake(2, "s234567", 1000);
+        assert_noop!(SubGameStake::set_reward_per_block(Origin::signed(2), 10), Error::<Test>::PermissionDenied);
+        assert_ok!(SubGameStake::set_reward_per_block(Origin::signed(owner), 10));
+
+        // 【Then】user 2 earns all of it alone
+        System::set_block_number(11);
+        assert_eq!(100, SubGameStake::pending_reward(&2));
+
+        // 【When】user 3 stakes 3000
+        sign_up_and_stake(3, "s345678", 3000);
+        System::set_block_number(21);
+
+        // 【Then】the next 10 blocks are shared 1 to 3
+        assert_eq!(125, SubGameStake::pending_reward(&2));
+        assert_eq!(75, SubGameStake::pending_reward(&3));
+
+        // 【When】user 2 claims and user 3 unbonds everything
+        let before = Balances::free_balance(&2);
+        assert_ok!(SubGameStake::claim_reward(Origin::signed(2)));
+        assert_ok!(SubGameStake::unbond(Origin::signed(3), 3000));
+
+        // 【Then】user 2 is paid and user 3 keeps what they earned, earning no more
+        assert_eq!(before + 125, Balances::free_balance(&2));
+        assert_eq!(0, SubGameStake::pending_reward(&2));
+        System::set_block_number(31);
+        assert_eq!(100, SubGameStake::pending_reward(&2));
+        assert_eq!(75, SubGameStake::pending_reward(&3));
+        assert_ok!(SubGameStake::claim_reward(Origin::signed(3)));
+        assert_eq!(100000 - 200, Balances::free_balance(&SubGameStake::account_id()));
+        assert_noop!(SubGameStake::claim_reward(Origin::signed(3)), Error::<Test>::NoReward);
+    });
+}
+
+#[test]
+fn claim_reward_unfunded() {
+    new_test_ext().execute_with(|| {
+        sign_up_and_stake(2, "s234567", 1000);
+        assert_ok!(SubGameStake::set_reward_per_block(Origin::signed(1), 10));
+        System::set_block_number(11);
+
+        // 【Then】the reward waits for the reward account to be funded
+        assert_noop!(SubGameStake::claim_reward(Origin::signed(2)), Error::<Test>::RewardNotEnough);
+        assert_eq!(100, SubGameStake::pending_reward(&2));
+
+        assert_ok!(Balances::transfer(Origin::signed(5), SubGameStake::account_id(), 1000));
+        assert_ok!(SubGameStake::claim_reward(Origin::signed(2)));
+        assert_eq!(0, SubGameStake::pending_reward(&2));
+    });
+}
+
+fn sign_up_referred(user: u64, account: &str, referrer_account: &str) {
+    assert_ok!(SubGameStake::sign_up(Origin::signed(user), account.as_bytes().to_vec(), referrer_account.as_bytes().to_vec()));
+}
+
+#[test]
+fn referral_tree() {
+    new_test_ext().execute_with(|| {
+        // 【Then】the referrer is the root or a signed up account
+        assert_noop!(SubGameStake::sign_up(Origin::signed(2), "s234567".as_bytes().to_vec(), "s999999".as_bytes().to_vec()), Error::<Test>::ReferrerNotExists);
+
+        // 【When】user 2 refers users 3 and 5, and user 3 refers user 4
+        sign_up_referred(2, "s234567", "gametop");
+        sign_up_referred(3, "s345678", "S234567");
+        sign_up_referred(5, "s567890", "s234567");
+        sign_up_referred(4, "s456789", "s345678");
+
+        // 【Then】the tree links them
+        assert_eq!(None, SubGameStake::referrer(2));
+        assert_eq!(Some(2), SubGameStake::referrer(3));
+        assert_eq!(Some(3), SubGameStake::referrer(4));
+        assert_eq!(vec![3, 5], SubGameStake::downline(2));
+        assert_eq!(vec![vec![3, 5], vec![4]], SubGameStake::downline_of(&2));
+        assert_eq!(vec![vec![4]], SubGameStake::downline_of(&3));
+        assert!(SubGameStake::downline_of(&4).is_empty());
+
+        // 【Then】a referrer refers at most MaxDownline accounts
+        assert_noop!(SubGameStake::sign_up(Origin::signed(6), "s678901".as_bytes().to_vec(), "s234567".as_bytes().to_vec()), Error::<Test>::TooManyReferrals);
+    });
+}
+
+#[test]
+fn delete_user_leaves_the_referral_tree() {
+    new_test_ext().execute_with(|| {
+        let owner = 1;
+
+        // 【Given】user 2 refers users 3 and 5, and user 3 refers user 4
+        sign_up_referred(2, "s234567", "gametop");
+        sign_up_referred(3, "s345678", "s234567");
+        sign_up_referred(5, "s567890", "s234567");
+        sign_up_referred(4, "s456789", "s345678");
+
+        // 【When】user 3 is deleted
+        assert_ok!(SubGameStake::delete_user(Origin::signed(owner), 3, "s345678".as_bytes().to_vec()));
+
+        // 【Then】it leaves the downline of user 2 and user 4 moves under the root
+        assert_eq!(None, SubGameStake::referrer(3));
+        assert_eq!(vec![5], SubGameStake::downline(2));
+        assert!(SubGameStake::downline(3).is_empty());
+        assert_eq!(None, SubGameStake::referrer(4));
+        assert_eq!("gametop".as_bytes().to_vec(), SubGameStake::user_info_map(4).referrer_account);
+        assert_eq!("s234567".as_bytes().to_vec(), SubGameStake::user_info_map(5).referrer_account);
+        assert_eq!(vec![vec![5]], SubGameStake::downline_of(&2));
+
+        // 【When】user 3 signs up again under user 2
+        sign_up_referred(3, "s345678", "s234567");
+
+        // 【Then】it is referred once, without its old downline
+        assert_eq!(Some(2), SubGameStake::referrer(3));
+        assert_eq!(vec![5, 3], SubGameStake::downline(2));
+        assert_eq!(vec![vec![5, 3]], SubGameStake::downline_of(&2));
+        assert_eq!(Some(3), <Username as UsernameRegistry<u64>>::lookup(b"s345678"));
+    });
+}
+
+#[test]
+fn set_commission_rates() {
+    new_test_ext().execute_with(|| {
+        let owner = 1;
+        let rates = vec![Perbill::from_percent(10), Perbill::from_percent(5)];
+
+        // 【Then】only the owner sets at most MaxReferralDepth rates, adding up to at most the reward
+        assert_noop!(SubGameStake::set_commission_rates(Origin::signed(2), rates.clone()), Error::<Test>::PermissionDenied);
+        assert_noop!(SubGameStake::set_commission_rates(Origin::signed(owner), vec![Perbill::from_percent(1); 4]), Error::<Test>::TooManyCommissionLevels);
+        assert_noop!(SubGameStake::set_commission_rates(Origin::signed(owner), vec![Perbill::from_percent(60), Perbill::from_percent(50)]), Error::<Test>::InvalidCommissionRates);
+
+        assert_ok!(SubGameStake::set_commission_rates(Origin::signed(owner), rates.clone()));
+        assert_eq!(rates, SubGameStake::commission_rates());
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_stake(crate::RawEvent::CommissionRatesSet(rates.clone()))));
+    });
+}
+
+#[test]
+fn referrers_earn_commission() {
+    new_test_ext().execute_with(|| {
+        let owner = 1;
+        assert_ok!(Balances::transfer(Origin::signed(5), SubGameStake::account_id(), 100000));
+        assert_ok!(SubGameStake::set_commission_rates(Origin::signed(owner), vec![Perbill::from_percent(10), Perbill::from_percent(5)]));
+
+        // 【Given】user 2 refers user 3, who refers user 4, who stakes alone for 10 blocks at 10 a block
+        sign_up_referred(2, "s234567", "gametop");
+        sign_up_referred(3, "s345678", "s234567");
+        sign_up_referred(4, "s456789", "s345678");
+        assert_ok!(SubGameStake::stake(Origin::signed(4), 1000));
+        assert_ok!(SubGameStake::set_reward_per_block(Origin::signed(owner), 10));
+        System::set_block_number(11);
+
+        // 【When】user 4 claims its 100
+        assert_ok!(SubGameStake::claim_reward(Origin::signed(4)));
+
+        // 【Then】user 3 earns 10% of it and user 2 5%
+        assert_eq!(ReferralReward { unclaimed: 10, total: 10 }, SubGameStake::referral_reward(3));
+        assert_eq!(ReferralReward { unclaimed: 5, total: 5 }, SubGameStake::referral_reward(2));
+        assert_eq!(10, SubGameStake::pending_reward(&3));
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_stake(crate::RawEvent::ReferralCommission(3, 4, 10))));
+
+        // 【When】user 3 claims its commission
+        let before = Balances::free_balance(&3);
+        assert_ok!(SubGameStake::claim_reward(Origin::signed(3)));
+
+        // 【Then】it is paid, the total stays and no commission is paid on commissions
+        assert_eq!(before + 10, Balances::free_balance(&3));
+        assert_eq!(0, SubGameStake::pending_reward(&3));
+        assert_eq!(10, SubGameStake::referral_earnings(&3));
+        assert_eq!(5, SubGameStake::referral_earnings(&2));
+        assert_eq!(100000 - 110, Balances::free_balance(&SubGameStake::account_id()));
     });
 }
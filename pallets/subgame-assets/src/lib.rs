@@ -115,7 +115,7 @@ mod benchmarking;
 pub mod weights;
 
 use sp_std::{fmt::Debug, prelude::*};
-use sp_runtime::{RuntimeDebug, traits::{
+use sp_runtime::{RuntimeDebug, SaturatedConversion, traits::{
 	Member, AtLeast32BitUnsigned, Zero, Saturating, CheckedSub, CheckedAdd
 }};
 use codec::{Encode, Decode, HasCompact};
@@ -130,6 +130,10 @@ type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Con
 
 use sp_std::convert::TryInto;
 
+/// Asset ids from here up are reserved for assets the runtime creates, like the swap LP tokens.
+/// They are handed out in order by `_force_create_reserved` and can't be created by users.
+pub const RESERVED_ASSET_ID_START: u32 = 0x8000_0000;
+
 /// The module configuration trait.
 pub trait Config: frame_system::Config {
 	/// The overarching event type.
@@ -244,6 +248,9 @@ decl_storage! {
 
 		/// Metadata of an asset.
 		pub Metadata get(fn metadata): map hasher(blake2_128_concat)  T::AssetId => SubGameAssetMetadata<BalanceOf<T>>;
+
+		/// The next reserved asset id to hand out, `RESERVED_ASSET_ID_START` until the first one is.
+		pub NextReservedAssetId get(fn next_reserved_asset_id): Option<T::AssetId>;
 	}
 }
 
@@ -316,6 +323,10 @@ decl_error! {
 		BadState,
 		/// Invalid metadata given.
 		BadMetadata,
+		/// The asset ID is in the reserved range.
+		ReservedAssetId,
+		/// The reserved asset ID range is used up.
+		NoReservedAssetId,
 	}
 }
 
@@ -388,6 +399,7 @@ decl_module! {
 			min_balance: T::SGAssetBalance,
 		) -> DispatchResult {
 			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(!Self::is_reserved(id), Error::<T>::ReservedAssetId);
 			Self::_force_create(id, owner, max_zombies, min_balance)
 		}
 
@@ -775,6 +787,7 @@ impl<T: Config> Module<T> {
 		max_zombies: u32,
 		min_balance: T::SGAssetBalance,
 	) -> DispatchResult {
+		ensure!(!Self::is_reserved(id), Error::<T>::ReservedAssetId);
 		ensure!(!Asset::<T>::contains_key(id), Error::<T>::InUse);
 		ensure!(!min_balance.is_zero(), Error::<T>::MinBalanceZero);
 
@@ -826,6 +839,27 @@ impl<T: Config> Module<T> {
 		Ok(())
 	}
 
+	/// Whether the id is in the range kept for `_force_create_reserved`
+	pub fn is_reserved(id: T::AssetId) -> bool {
+		id >= RESERVED_ASSET_ID_START.into()
+	}
+
+	/// Force create an asset with the next free reserved id, and return that id
+	pub fn _force_create_reserved(
+		owner: T::AccountId,
+		max_zombies: u32,
+		min_balance: T::SGAssetBalance,
+	) -> Result<T::AssetId, DispatchError> {
+		let mut id = NextReservedAssetId::<T>::get().unwrap_or_else(|| RESERVED_ASSET_ID_START.into());
+		// Skip ids that were taken before the range was reserved
+		while Asset::<T>::contains_key(id) {
+			id = id.checked_add(&1u32.into()).ok_or(Error::<T>::NoReservedAssetId)?;
+		}
+		Self::_force_create(id, owner, max_zombies, min_balance)?;
+		NextReservedAssetId::<T>::put(id.saturating_add(1u32.into()));
+		Ok(id)
+	}
+
 	pub fn _destroy(
 		origin: T::AccountId,
 		id: T::AssetId,
@@ -1262,6 +1296,39 @@ pub trait AssetsTransfer<AccountId, AssetId>: AssetsTrait {
 		who: AccountId,
 		amount: u64
 	) -> DispatchResult;
+	fn asset_balance(
+		id: AssetId,
+		who: AccountId
+	) -> u64;
+	fn transfer_asset(
+		from: AccountId,
+		id: AssetId,
+		to: AccountId,
+		amount: u64
+	) -> DispatchResult;
+}
+
+const ASSETS_NOT_SUPPORTED: &str = "AssetsNotSupported";
+
+/// No assets, for runtimes that only use the native currency
+impl AssetsTrait for () {}
+
+impl<AccountId, AssetId> AssetsTransfer<AccountId, AssetId> for () {
+	fn mint(_: AccountId, _: AssetId, _: AccountId, _: u64) -> DispatchResult {
+		Err(DispatchError::Other(ASSETS_NOT_SUPPORTED))
+	}
+
+	fn burn(_: AccountId, _: AssetId, _: AccountId, _: u64) -> DispatchResult {
+		Err(DispatchError::Other(ASSETS_NOT_SUPPORTED))
+	}
+
+	fn asset_balance(_: AssetId, _: AccountId) -> u64 {
+		0
+	}
+
+	fn transfer_asset(_: AccountId, _: AssetId, _: AccountId, _: u64) -> DispatchResult {
+		Err(DispatchError::Other(ASSETS_NOT_SUPPORTED))
+	}
 }
 
 impl<T: Config> AssetsTrait for Module<T> {
@@ -1303,6 +1370,23 @@ impl<T: Config> AssetsTransfer<T::AccountId, T::AssetId> for Module<T> {
 		Self::_burn(sender, id, who, balance.unwrap())?;
         Ok(())
 	}
+
+	fn asset_balance(
+		id: T::AssetId,
+		who: T::AccountId
+	) -> u64 {
+		Self::balance(id, who).saturated_into()
+	}
+
+	fn transfer_asset(
+		from: T::AccountId,
+		id: T::AssetId,
+		to: T::AccountId,
+		amount: u64
+	) -> DispatchResult {
+		let balance: T::SGAssetBalance = amount.try_into().map_err(|_| Error::<T>::Overflow)?;
+		Self::_transfer(from, id, to, balance)
+	}
 }
 #[cfg(test)]
 mod tests {
@@ -1795,4 +1879,28 @@ mod tests {
 			assert!(!Metadata::<Test>::contains_key(0));
 		});
 	}
+
+	#[test]
+	fn reserved_asset_ids_should_work() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&1, 100);
+			let start = RESERVED_ASSET_ID_START;
+
+			// Users can't take reserved ids
+			assert_noop!(Assets::create(Origin::signed(1), start, 1, 3, 1), Error::<Test>::ReservedAssetId);
+			assert_noop!(Assets::force_create(Origin::root(), start + 5, 1, 3, 1), Error::<Test>::ReservedAssetId);
+			assert_ok!(Assets::create(Origin::signed(1), start - 1, 1, 3, 1));
+
+			// Reserved ids are handed out in order
+			assert_eq!(Assets::_force_create_reserved(2, 10, 1), Ok(start));
+			assert_eq!(Assets::_force_create_reserved(2, 10, 1), Ok(start + 1));
+			assert_eq!(Assets::next_reserved_asset_id(), Some(start + 2));
+
+			// Ids taken before the range was reserved are skipped
+			assert_ok!(Assets::_force_create(start + 2, 2, 10, 1));
+			assert_eq!(Assets::_force_create_reserved(2, 10, 1), Ok(start + 3));
+			assert!(Assets::is_reserved(start + 3));
+			assert!(!Assets::is_reserved(start - 1));
+		});
+	}
 }
\ No newline at end of file
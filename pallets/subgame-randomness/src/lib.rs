@@ -0,0 +1,42 @@
+//! On-chain randomness for the game pallets: RandomnessCollectiveFlip or BABE mixed with a per-call nonce
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{decl_module, decl_storage, traits::Randomness};
+use sp_runtime::traits::Hash;
+
+pub use common::RandomSource;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub trait Config: frame_system::Config {
+    /// The underlying randomness, e.g. RandomnessCollectiveFlip or BABE
+    type Randomness: Randomness<Self::Hash>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Config> as SubgameRandomness {
+        /// Incremented on every call, so two rolls in the same block differ
+        pub Nonce get(fn nonce): u64;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Config> for enum Call where origin: T::Origin {
+    }
+}
+
+impl<T: Config> RandomSource<T::Hash> for Module<T> {
+    fn random(subject: &[u8]) -> T::Hash {
+        let nonce = Nonce::mutate(|nonce| {
+            let current = *nonce;
+            *nonce = nonce.wrapping_add(1);
+            current
+        });
+        let random = T::Randomness::random(subject);
+        T::Hashing::hash_of(&(random, nonce))
+    }
+}
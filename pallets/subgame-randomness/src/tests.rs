@@ -0,0 +1,56 @@
+use crate::mock::{new_test_ext, BlockRandomness, SubgameRandomness, System};
+use crate::RandomSource;
+use frame_support::traits::Randomness;
+
+/// 【Scenario】The underlying randomness repeats within a block
+#[test]
+fn block_randomness_repeats_in_block() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            BlockRandomness::random(b"roll"),
+            BlockRandomness::random(b"roll")
+        );
+    });
+}
+
+/// 【Scenario】Two rolls in the same block differ
+#[test]
+fn two_rolls_in_same_block_differ() {
+    new_test_ext().execute_with(|| {
+        // 【Given】Arrange
+        let block_number = System::block_number();
+
+        // 【When】Act
+        let first = SubgameRandomness::random(b"roll");
+        let second = SubgameRandomness::random(b"roll");
+
+        // 【Then】Assert
+        assert_eq!(System::block_number(), block_number);
+        assert_ne!(first, second);
+        // Every call uses the next nonce
+        assert_eq!(SubgameRandomness::nonce(), 2);
+    });
+}
+
+/// 【Scenario】Ranged rolls stay in range and do not repeat within a block
+#[test]
+fn range_rolls_in_same_block_differ() {
+    new_test_ext().execute_with(|| {
+        // 【When】Act
+        let rolls: Vec<u32> = (0..20)
+            .map(|_| SubgameRandomness::random_range(b"roll", 1, 1_000_000))
+            .collect();
+
+        // 【Then】Assert
+        assert!(rolls.iter().all(|roll| *roll >= 1 && *roll < 1_000_000));
+        assert!(rolls.windows(2).any(|pair| pair[0] != pair[1]));
+    });
+}
+
+/// 【Scenario】An empty range gives the minimum
+#[test]
+fn range_roll_of_empty_range_is_min() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(SubgameRandomness::random_range(b"roll", 5, 5), 5);
+    });
+}
@@ -0,0 +1,33 @@
+//! Runtime API definition for the Swap pallet, lets front-ends quote swaps without reimplementing the pool math
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+pub use pallet_swap::{Curve, LpValue, PoolReserves};
+use sp_runtime::FixedU128;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait SwapApi<AccountId, AssetId, SwapId, BlockNumber> where
+        AccountId: Codec,
+        AssetId: Codec,
+        SwapId: Codec,
+        BlockNumber: Codec,
+    {
+        /// Output of selling `input_amount` of `input_asset` into the pool, what `swap` pays out
+        fn get_amount_out(swap_id: SwapId, input_asset: AssetId, input_amount: u128) -> Option<u128>;
+        /// Input of the other asset needed to buy `output_amount` of `output_asset` from the pool
+        fn get_amount_in(swap_id: SwapId, output_asset: AssetId, output_amount: u128) -> Option<u128>;
+        /// Reserves and LP supply of the pool
+        fn pool_reserves(swap_id: SwapId) -> Option<PoolReserves<AssetId>>;
+        /// LP balance of the account and what removing it would pay out
+        fn lp_value(swap_id: SwapId, who: AccountId) -> Option<LpValue>;
+        /// Path from `asset_in` to `asset_out` with the most output for `amount_in`, and that output
+        fn best_path_exact_in(asset_in: AssetId, asset_out: AssetId, amount_in: u128) -> Option<(Vec<AssetId>, u128)>;
+        /// Path from `asset_in` to `asset_out` with the least input for `amount_out`, and that input
+        fn best_path_exact_out(asset_in: AssetId, asset_out: AssetId, amount_out: u128) -> Option<(Vec<AssetId>, u128)>;
+        /// Average price of `base` in `quote` over at least the last `window` blocks
+        fn twap(base: AssetId, quote: AssetId, window: BlockNumber) -> Option<FixedU128>;
+    }
+}
@@ -0,0 +1,205 @@
+//! RPC interface for the Swap pallet, quotes swaps with the same math as the extrinsics
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use pallet_swap_runtime_api::SwapApi as SwapRuntimeApi;
+use pallet_swap_runtime_api::{LpValue, PoolReserves};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, FixedU128};
+
+/// Error code of a failed runtime call
+const RUNTIME_ERROR: i64 = 1;
+
+#[rpc]
+pub trait SwapApi<BlockHash, AccountId, AssetId, SwapId, BlockNumber> {
+    /// Output of selling `input_amount` of `input_asset` into the pool
+    #[rpc(name = "swap_getAmountOut")]
+    fn get_amount_out(
+        &self,
+        swap_id: SwapId,
+        input_asset: AssetId,
+        input_amount: u128,
+        at: Option<BlockHash>,
+    ) -> Result<Option<u128>>;
+
+    /// Input needed to buy `output_amount` of `output_asset` from the pool
+    #[rpc(name = "swap_getAmountIn")]
+    fn get_amount_in(
+        &self,
+        swap_id: SwapId,
+        output_asset: AssetId,
+        output_amount: u128,
+        at: Option<BlockHash>,
+    ) -> Result<Option<u128>>;
+
+    /// Reserves and LP supply of the pool
+    #[rpc(name = "swap_poolReserves")]
+    fn pool_reserves(
+        &self,
+        swap_id: SwapId,
+        at: Option<BlockHash>,
+    ) -> Result<Option<PoolReserves<AssetId>>>;
+
+    /// LP balance of the account and what removing it would pay out
+    #[rpc(name = "swap_lpValue")]
+    fn lp_value(
+        &self,
+        swap_id: SwapId,
+        who: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Option<LpValue>>;
+
+    /// Path with the most output for `amount_in`, and that output
+    #[rpc(name = "swap_bestPathExactIn")]
+    fn best_path_exact_in(
+        &self,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        amount_in: u128,
+        at: Option<BlockHash>,
+    ) -> Result<Option<(Vec<AssetId>, u128)>>;
+
+    /// Path with the least input for `amount_out`, and that input
+    #[rpc(name = "swap_bestPathExactOut")]
+    fn best_path_exact_out(
+        &self,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        amount_out: u128,
+        at: Option<BlockHash>,
+    ) -> Result<Option<(Vec<AssetId>, u128)>>;
+
+    /// Average price of `base` in `quote` over at least the last `window` blocks
+    #[rpc(name = "swap_twap")]
+    fn twap(
+        &self,
+        base: AssetId,
+        quote: AssetId,
+        window: BlockNumber,
+        at: Option<BlockHash>,
+    ) -> Result<Option<FixedU128>>;
+}
+
+/// Implements the SwapApi RPC trait for the swap quotes
+pub struct Swap<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Swap<C, B> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+fn runtime_error(err: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query the swap pools.".into(),
+        data: Some(format!("{:?}", err).into()),
+    }
+}
+
+impl<C, Block, AccountId, AssetId, SwapId, BlockNumber>
+    SwapApi<<Block as BlockT>::Hash, AccountId, AssetId, SwapId, BlockNumber> for Swap<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: SwapRuntimeApi<Block, AccountId, AssetId, SwapId, BlockNumber>,
+    AccountId: Codec,
+    AssetId: Codec,
+    SwapId: Codec,
+    BlockNumber: Codec,
+{
+    fn get_amount_out(
+        &self,
+        swap_id: SwapId,
+        input_asset: AssetId,
+        input_amount: u128,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<u128>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.get_amount_out(&at, swap_id, input_asset, input_amount)
+            .map_err(runtime_error)
+    }
+
+    fn get_amount_in(
+        &self,
+        swap_id: SwapId,
+        output_asset: AssetId,
+        output_amount: u128,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<u128>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.get_amount_in(&at, swap_id, output_asset, output_amount)
+            .map_err(runtime_error)
+    }
+
+    fn pool_reserves(
+        &self,
+        swap_id: SwapId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<PoolReserves<AssetId>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.pool_reserves(&at, swap_id).map_err(runtime_error)
+    }
+
+    fn lp_value(
+        &self,
+        swap_id: SwapId,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<LpValue>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.lp_value(&at, swap_id, who).map_err(runtime_error)
+    }
+
+    fn best_path_exact_in(
+        &self,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        amount_in: u128,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<(Vec<AssetId>, u128)>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.best_path_exact_in(&at, asset_in, asset_out, amount_in)
+            .map_err(runtime_error)
+    }
+
+    fn best_path_exact_out(
+        &self,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        amount_out: u128,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<(Vec<AssetId>, u128)>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.best_path_exact_out(&at, asset_in, asset_out, amount_out)
+            .map_err(runtime_error)
+    }
+
+    fn twap(
+        &self,
+        base: AssetId,
+        quote: AssetId,
+        window: BlockNumber,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<FixedU128>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.twap(&at, base, quote, window).map_err(runtime_error)
+    }
+}
@@ -19,8 +19,6 @@ pub const SGB_DECIMALS: u64 = 10_000_000_000;
 pub const USDT_DECIMALS: u64 = 1_000_000;
 pub const GOGO_DECIMALS: u64 = 1_000_000;
 
-type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
-
 fn init<T: Config>() {
 	let default_balances: BalanceOf<T> = 10000000000000000u64.saturated_into();
 	let user: T::AccountId = whitelisted_caller();
@@ -49,6 +47,27 @@ fn init<T: Config>() {
     assert_ok!(SubGameAssets::Module::<T>::_mint(user.clone(), asset_id.saturated_into(), user.clone(), mint_balance.saturated_into()));
 }
 
+/// `n` new assets with a pool between every two neighbours, returns the path through them
+fn init_path<T: Config>(n: u32) -> Vec<T::AssetId> {
+	init::<T>();
+	let user: T::AccountId = whitelisted_caller();
+	let mut path: Vec<T::AssetId> = Vec::new();
+	for i in 0..n {
+		let asset_id: T::AssetId = (100 + i).saturated_into();
+		let mint_balance = 100000000 * USDT_DECIMALS;
+		assert_ok!(SubGameAssets::Module::<T>::_force_create(asset_id, user.clone(), 10, 1u32.saturated_into()));
+		assert_ok!(SubGameAssets::Module::<T>::_force_set_metadata(user.clone(), asset_id, b"PATH".to_vec(), b"PATH".to_vec(), 6));
+		assert_ok!(SubGameAssets::Module::<T>::_mint(user.clone(), asset_id, user.clone(), mint_balance.saturated_into()));
+
+		if let Some(&last) = path.last() {
+			let amount: u64 = 1000 * USDT_DECIMALS;
+			assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), last, amount.saturated_into(), asset_id, amount.saturated_into(), 30, Curve::ConstantProduct));
+		}
+		path.push(asset_id);
+	}
+	path
+}
+
 benchmarks! {
 	create_pool {
 		init::<T>();
@@ -57,7 +76,7 @@ benchmarks! {
         let x: u64 = 11 * SGB_DECIMALS;
         let asset_y: u32 = 7;
         let y: u64 = 1 * USDT_DECIMALS;
-	}: _(RawOrigin::Signed(user), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into())
+	}: _(RawOrigin::Signed(user), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into(), 30)
 	verify {
 		
 	}
@@ -70,7 +89,7 @@ benchmarks! {
         let x: u64 = 1 * GOGO_DECIMALS;
         let asset_y: u32 = 0;
         let y: u64 = 11 * SGB_DECIMALS;
-        assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into()));
+        assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into(), 30, Curve::ConstantProduct));
 
 		let swap_id: u32 = 1;
         let dx: u64 = 2 * GOGO_DECIMALS;
@@ -88,11 +107,11 @@ benchmarks! {
         let x: u64 = 1 * SGB_DECIMALS;
         let asset_y: u32 = 7;
         let y: u64 = 11 * USDT_DECIMALS; 
-        assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into()));
+        assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into(), 30, Curve::ConstantProduct));
 
 		let swap_id: u32 = 1;
         let lp_balance: u64 = 1;
-	}: _(RawOrigin::Signed(user), swap_id.saturated_into(), lp_balance.saturated_into())
+	}: _(RawOrigin::Signed(user), swap_id.saturated_into(), lp_balance.saturated_into(), 0, 0)
 	verify {
 		
 	}
@@ -105,7 +124,7 @@ benchmarks! {
         let x: u64 = 1 * GOGO_DECIMALS;
         let asset_y: u32 = 7;
         let y: u64 = 11 * USDT_DECIMALS; 
-		assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into()));
+		assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), asset_x.saturated_into(), x.saturated_into(), asset_y.saturated_into(), y.saturated_into(), 30, Curve::ConstantProduct));
 
 		let swap_id: u32 = 1;
         let input_asset: u32 = 8;
@@ -118,6 +137,69 @@ benchmarks! {
 	verify {
 		
 	}
+
+	set_protocol_fee {
+		let share = Perbill::from_percent(20);
+	}: _(RawOrigin::Root, share)
+	verify {
+		assert_eq!(Pallet::<T>::protocol_fee(), share);
+	}
+
+	close_pool {
+		let n in 1 .. T::MaxObservations::get();
+		init::<T>();
+		let user: T::AccountId = whitelisted_caller();
+
+		let amount: u64 = 1000 * USDT_DECIMALS;
+		assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), 8u32.saturated_into(), amount.saturated_into(), 7u32.saturated_into(), amount.saturated_into(), 30, Curve::ConstantProduct));
+		let swap_id: T::SwapId = 1u32.saturated_into();
+		for i in 1 .. n {
+			frame_system::Module::<T>::set_block_number(i.saturated_into());
+			assert_ok!(Pallet::<T>::swap(RawOrigin::Signed(user.clone()).into(), swap_id, 8u32.saturated_into(), 1000, 7u32.saturated_into(), 1, 0, 0u32.saturated_into()));
+		}
+		let lp_balance = Pallet::<T>::lp_value(swap_id, &user).unwrap().lp_balance;
+		assert_ok!(Pallet::<T>::remove_liquidity(RawOrigin::Signed(user).into(), swap_id, lp_balance, 0, 0));
+	}: _(RawOrigin::Root, swap_id)
+	verify {
+		assert_eq!(Pallet::<T>::pool_reserves(swap_id), None);
+	}
+
+	flash_swap {
+		init::<T>();
+		let user: T::AccountId = whitelisted_caller();
+
+		let amount: u64 = 1000 * USDT_DECIMALS;
+		assert_ok!(Pallet::<T>::create_pool(RawOrigin::Signed(user.clone()).into(), 8u32.saturated_into(), amount.saturated_into(), 7u32.saturated_into(), amount.saturated_into(), 30, Curve::ConstantProduct));
+		let swap_id: T::SwapId = 1u32.saturated_into();
+		let pool_account = SwapPool::<T>::get(swap_id).account;
+		let amount_out: u64 = 100 * GOGO_DECIMALS;
+		let repayment = math::flash_repayment(amount_out.into(), 30).unwrap();
+		let call: <T as Config>::Call = SubGameAssets::Call::<T>::transfer(8u32.saturated_into(), pool_account, repayment.saturated_into()).into();
+	}: _(RawOrigin::Signed(user.clone()), swap_id, amount_out.into(), 0, Box::new(call))
+	verify {
+		assert!(!Pallet::<T>::flash_locked(swap_id));
+	}
+
+	swap_exact_in_by_path {
+		let n in 2 .. T::MaxPathLength::get();
+		let user: T::AccountId = whitelisted_caller();
+		let path = init_path::<T>(n);
+		let amount_in: u64 = 1 * USDT_DECIMALS;
+	}: _(RawOrigin::Signed(user), path, amount_in.saturated_into(), 1u32.saturated_into(), 0u32.saturated_into())
+	verify {
+
+	}
+
+	swap_exact_out_by_path {
+		let n in 2 .. T::MaxPathLength::get();
+		let user: T::AccountId = whitelisted_caller();
+		let path = init_path::<T>(n);
+		let amount_out: u64 = 1 * USDT_DECIMALS;
+		let max_amount_in: u64 = 10 * USDT_DECIMALS;
+	}: _(RawOrigin::Signed(user), path, amount_out.saturated_into(), max_amount_in.saturated_into(), 0u32.saturated_into())
+	verify {
+
+	}
 }
 
 impl_benchmark_test_suite!(
@@ -6,23 +6,53 @@ use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
 
 impl crate::WeightInfo for () {
     fn create_pool() -> Weight {
-		(302_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(12 as Weight))
-			.saturating_add(DbWeight::get().writes(11 as Weight))
+		(306_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(14 as Weight))
+			.saturating_add(DbWeight::get().writes(15 as Weight))
 	}
 	fn add_liquidity() -> Weight {
-		(195_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(8 as Weight))
-			.saturating_add(DbWeight::get().writes(6 as Weight))
+		(248_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().writes(12 as Weight))
 	}
 	fn remove_liquidity() -> Weight {
-		(194_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(8 as Weight))
-			.saturating_add(DbWeight::get().writes(6 as Weight))
+		(246_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().writes(12 as Weight))
 	}
 	fn swap() -> Weight {
-		(151_000_000 as Weight)
-			.saturating_add(DbWeight::get().reads(8 as Weight))
-			.saturating_add(DbWeight::get().writes(6 as Weight))
+		(159_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(11 as Weight))
+			.saturating_add(DbWeight::get().writes(9 as Weight))
+	}
+	fn swap_exact_in_by_path(n: u32, ) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((166_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().reads((12 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes((9 as Weight).saturating_mul(n as Weight)))
+	}
+	fn swap_exact_out_by_path(n: u32, ) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((168_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().reads((12 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes((9 as Weight).saturating_mul(n as Weight)))
+	}
+	fn set_protocol_fee() -> Weight {
+		(18_000_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn close_pool(n: u32, ) -> Weight {
+		(186_000_000 as Weight)
+			.saturating_add((1_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().writes(16 as Weight))
+			.saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+	fn flash_swap() -> Weight {
+		(152_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(8 as Weight))
 	}
 }
@@ -4,23 +4,23 @@
 
 use codec::{Decode, Encode};
 use frame_support::{
-	decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, Parameter,
-	traits::{Currency, ReservableCurrency, ExistenceRequirement},
-	weights::{Weight},
+	decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, Parameter, transactional,
+	traits::{Currency, ReservableCurrency, ExistenceRequirement, Get, EnsureOrigin},
+	weights::{GetDispatchInfo, PostDispatchInfo, Weight},
 };
 use sp_runtime::{
-	ModuleId,
+	FixedPointNumber, FixedU128, ModuleId, Perbill,
 	traits::{
-		Member, Zero, AtLeast32Bit, MaybeSerializeDeserialize, CheckedAdd,
-		AccountIdConversion, SaturatedConversion, 
+		Member, AtLeast32Bit, MaybeSerializeDeserialize, CheckedAdd,
+		AccountIdConversion, SaturatedConversion, Saturating, Zero, Dispatchable,
 	}
 };
+use sp_std::{boxed::Box, convert::TryFrom, vec::Vec};
 use frame_system::ensure_signed;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use pallet_subgame_assets::{self as SubGameAssets};
 
-#[allow(unused_imports)]
-use num_traits::float::FloatCore;
-
 #[cfg(test)]
 mod mock;
 
@@ -34,11 +34,17 @@ extern crate alloc;
 mod benchmarking;
 
 mod default_weight;
+pub mod math;
 pub trait WeightInfo {
 	fn create_pool() -> Weight;
 	fn add_liquidity() -> Weight;
 	fn remove_liquidity() -> Weight;
 	fn swap() -> Weight;
+	fn swap_exact_in_by_path(n: u32) -> Weight;
+	fn swap_exact_out_by_path(n: u32) -> Weight;
+	fn set_protocol_fee() -> Weight;
+	fn close_pool(n: u32, ) -> Weight;
+	fn flash_swap() -> Weight;
 }
 
 #[derive(Clone, Eq, PartialEq, Encode, Decode, Default)]
@@ -58,22 +64,118 @@ pub struct SwapPoolDetails<SwapId, AccountId, AssetId> {
 	swap_k: u128
 }
 
-/// There is a 0.3% fee for swapping tokens.
-const FEE: f64 = 0.003;
+/// Invariant a pool trades on
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum Curve {
+	/// `x * y = k`, for any pair
+	ConstantProduct,
+	/// Curve stableswap with the amplification coefficient `A`, for pairs trading near 1:1.
+	/// The higher `A`, the flatter the curve around the balanced point.
+	Stable { amplification: u32 },
+}
+
+impl Default for Curve {
+	fn default() -> Self {
+		Curve::ConstantProduct
+	}
+}
+
+/// Reserves of a pool, the native coin is read from the pool account's free balance
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct PoolReserves<AssetId> {
+	pub asset_x: AssetId,
+	pub reserve_x: u128,
+	pub asset_y: AssetId,
+	pub reserve_y: u128,
+	pub asset_lp: AssetId,
+	pub lp_supply: u128,
+	/// Swap fee in basis points
+	pub fee: u32,
+	/// Curve the pool trades on
+	pub curve: Curve,
+}
+
+/// What the LP tokens of an account would pay out if removed now
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct LpValue {
+	pub lp_balance: u128,
+	pub lp_supply: u128,
+	pub amount_x: u128,
+	pub amount_y: u128,
+}
+
+/// Prices of a pool summed over every block, in `FixedU128` inner units. The sums wrap on
+/// overflow, only their difference over a window is meaningful.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PriceCumulative<BlockNumber> {
+	/// Price of asset x in asset y
+	pub price_x_cumulative: u128,
+	/// Price of asset y in asset x
+	pub price_y_cumulative: u128,
+	/// Block the sums run up to
+	pub block_number: BlockNumber,
+	/// Reserves of asset x and asset y after the last update, the sums run up with them until
+	/// the next one
+	pub reserve_x: u128,
+	pub reserve_y: u128,
+}
+
+/// Pool prices for other pallets
+pub trait Oracle<AssetId, BlockNumber> {
+	/// Price of `base` in `quote` from the current reserves
+	fn spot_price(base: AssetId, quote: AssetId) -> Option<FixedU128>;
+	/// Average price of `base` in `quote` over at least the last `window` blocks,
+	/// `None` without a pool, enough history or for an empty window
+	fn twap(base: AssetId, quote: AssetId, window: BlockNumber) -> Option<FixedU128>;
+}
+
+impl<AssetId, BlockNumber> Oracle<AssetId, BlockNumber> for () {
+	fn spot_price(_base: AssetId, _quote: AssetId) -> Option<FixedU128> {
+		None
+	}
+	fn twap(_base: AssetId, _quote: AssetId, _window: BlockNumber) -> Option<FixedU128> {
+		None
+	}
+}
 
 /// The swap's module id, used for deriving sovereign account IDs.
 const MODULE_ID: ModuleId = ModuleId(*b"mtg/swap");
 
-/// LP token decimals
+/// SGB decimals
 pub const SGB_DECIMALS: u64 = 10_000_000_000;
+/// SGB decimal places
+pub const SGB_DECIMAL_PLACES: u32 = 10;
 /// LP token decimals
 pub const LP_DECIMALS: u64 = 1_000_000;
+/// Storage layout the runtime upgrade migrates to
+pub const STORAGE_VERSION: u32 = 1;
+
+type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 pub trait Config: frame_system::Config + SubGameAssets::Config {
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
 	type WeightInfo: WeightInfo;
 	type SwapId: Parameter + Member + AtLeast32Bit + Default + Copy + MaybeSerializeDeserialize;
 	type Currency: ReservableCurrency<Self::AccountId>;
+	/// The most assets a routed swap path may go through
+	type MaxPathLength: Get<u32>;
+	/// Origin allowed to set the protocol fee share
+	type FeeOrigin: EnsureOrigin<Self::Origin>;
+	/// Account the protocol fee LP tokens are minted to
+	type ProtocolFeeTo: Get<Self::AccountId>;
+	/// Origin allowed to close pools
+	type PoolOrigin: EnsureOrigin<Self::Origin>;
+	/// The call a flash swap dispatches with the borrowed assets
+	type Call: Parameter
+		+ Dispatchable<Origin = Self::Origin, PostInfo = PostDispatchInfo>
+		+ GetDispatchInfo
+		+ From<SubGameAssets::Call<Self>>;
+	/// Price observations kept per pool for the TWAP, at most one is taken per block
+	type MaxObservations: Get<u32>;
 }
 
 decl_storage! {
@@ -81,6 +183,24 @@ decl_storage! {
 		pub SwapPair get(fn swap_pair): map hasher(blake2_128_concat) (T::AssetId, T::AssetId) => T::SwapId;
 		pub SwapPoolCount get(fn swap_pool_count): T::SwapId;
 		pub SwapPool get(fn swap_pool): map hasher(blake2_128_concat) T::SwapId => SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>;
+		/// Swap fee of the pool in basis points, pools without one charge `math::DEFAULT_FEE`
+		pub PoolFee: map hasher(blake2_128_concat) T::SwapId => Option<u32>;
+		/// Curve of the pool, pools created before curves are constant product
+		pub PoolCurve get(fn pool_curve): map hasher(blake2_128_concat) T::SwapId => Curve;
+		/// Share of the swap fees that goes to `ProtocolFeeTo`
+		pub ProtocolFee get(fn protocol_fee): Perbill;
+		/// Pool of an LP asset
+		pub LpAssetSwap get(fn swap_id_of_lp): map hasher(blake2_128_concat) T::AssetId => Option<T::SwapId>;
+		/// Price accumulators of the pool, run up before every change of its reserves
+		pub PriceCumulatives get(fn price_cumulative): map hasher(blake2_128_concat) T::SwapId => PriceCumulative<T::BlockNumber>;
+		/// Observations taken of the pool, only the latest `MaxObservations` are kept
+		pub ObservationCount get(fn observation_count): map hasher(blake2_128_concat) T::SwapId => u32;
+		/// Accumulators of the pool by observation number modulo `MaxObservations`
+		pub Observations: double_map hasher(blake2_128_concat) T::SwapId, hasher(twox_64_concat) u32 => PriceCumulative<T::BlockNumber>;
+		/// Pools lent out by a flash swap in progress, nothing else may touch them until it is repaid
+		pub FlashLocked get(fn flash_locked): map hasher(blake2_128_concat) T::SwapId => bool;
+		/// Storage layout the pallet has been migrated to
+		pub StorageVersion get(fn storage_version): u32;
 	}
 }
 
@@ -92,14 +212,27 @@ decl_event!(
 		SwapAssetX = <T as SubGameAssets::Config>::AssetId,
 		SwapAssetY = <T as SubGameAssets::Config>::AssetId,
 		SwapId = <T as Config>::SwapId,
-		SwapAmountX = <T as SubGameAssets::Config>::SGAssetBalance,
-		SwapAmountY = <T as SubGameAssets::Config>::SGAssetBalance,
-		SwapAmountLP = <T as SubGameAssets::Config>::SGAssetBalance,
+		SwapAmountX = u128,
+		SwapAmountY = u128,
+		SwapAmountLP = u128,
+		SwapPath = Vec<<T as SubGameAssets::Config>::AssetId>,
 	{
 		CreatePool(SwapSender, SwapId, SwapAssetX, SwapAmountX, SwapAssetY, SwapAmountY, SwapPoolOwner),
 		LiquidityAdded(SwapId, SwapSender, SwapAmountX, SwapAmountY),
 		LiquidityRemoved(SwapId, SwapSender, SwapAmountLP, SwapAmountX, SwapAmountY),
 		Swap(SwapId, SwapSender, SwapAssetX, SwapAmountX, SwapAssetY, SwapAmountY),
+		/// A routed swap went through every pool of the path (sender, path, amount in, amount out)
+		PathSwap(SwapSender, SwapPath, SwapAmountX, SwapAmountY),
+		/// Fee of a swap left in the pool (swap id, input asset, fee)
+		SwapFee(SwapId, SwapAssetX, SwapAmountX),
+		/// LP minted to the protocol for its share of the fees (swap id, fee account, LP amount)
+		ProtocolFeeMinted(SwapId, SwapPoolOwner, SwapAmountLP),
+		/// The protocol fee share was set
+		ProtocolFeeSet(Perbill),
+		/// The pool was closed and what was left of it paid to the protocol (swap id, x, y)
+		PoolClosed(SwapId, SwapAmountX, SwapAmountY),
+		/// A flash swap was repaid (swap id, borrower, x out, y out, x in, y in)
+		FlashSwap(SwapId, SwapSender, SwapAmountX, SwapAmountY, SwapAmountX, SwapAmountY),
 	}
 );
 
@@ -131,8 +264,30 @@ decl_error! {
 		Slipage,
 		/// expected swap output amount can not be zero.
 		ZeroExpectedAmount,
-		/// Too many LP token.
-		TooManyLPToken
+		/// Too many LP token. No longer returned, the whole LP supply can be removed.
+		TooManyLPToken,
+		/// The amount does not fit the balance type.
+		AmountOverflow,
+		/// The path is too short, too long, repeats an asset or misses a pool.
+		InvalidPath,
+		/// Less than the minimum output is paid out.
+		InsufficientOutputAmount,
+		/// The path costs more than the maximum input.
+		ExcessiveInputAmount,
+		/// The fee is not one of `math::FEE_TIERS`.
+		InvalidFeeTier,
+		/// The amplification of a stable pool is zero or above `math::MAX_AMPLIFICATION`.
+		InvalidAmplification,
+		/// The first deposit mints no more LP than `math::MINIMUM_LIQUIDITY`.
+		InsufficientLiquidityMinted,
+		/// LP is still held outside the pool account.
+		PoolNotEmpty,
+		/// The pool is lent out by a flash swap in progress.
+		PoolLocked,
+		/// Nothing was paid back to the pool.
+		InsufficientInputAmount,
+		/// What was paid back does not cover the borrowed amounts and the fee.
+		FlashSwapKError,
 	}
 }
 
@@ -141,62 +296,57 @@ decl_module! {
 		type Error = Error<T>;
 		fn deposit_event() = default;
 
+		fn on_runtime_upgrade() -> Weight {
+			if Self::storage_version() >= STORAGE_VERSION {
+				return T::DbWeight::get().reads(1);
+			}
+			StorageVersion::put(STORAGE_VERSION);
+			Self::migrate_lp_lookup()
+				.saturating_add(Self::migrate_price_cumulatives())
+				.saturating_add(Self::migrate_k_last())
+				.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+		}
+
+		/// Create the pool of a pair with its first deposit, trading on `curve` with `fee` basis points
 		#[weight = <T as Config>::WeightInfo::create_pool()]
+		#[transactional]
 		pub fn create_pool(
 			origin, 
 			asset_x: T::AssetId,
-			x: T::SGAssetBalance,
+			x: u128,
 			asset_y: T::AssetId,
-			y: T::SGAssetBalance
+			y: u128,
+			fee: u32,
+			curve: Curve,
 		) -> dispatch::DispatchResult
 		{
 			let sender = ensure_signed(origin)?;
 			ensure!(asset_x != asset_y, Error::<T>::DuplicateAssetId);
+			ensure!(math::FEE_TIERS.contains(&fee), Error::<T>::InvalidFeeTier);
+			if let Curve::Stable { amplification } = curve {
+				ensure!(amplification > 0 && amplification <= math::MAX_AMPLIFICATION, Error::<T>::InvalidAmplification);
+			}
 			ensure!(!SwapPair::<T>::contains_key((asset_x, asset_y)), Error::<T>::SwapAlreadyExists);
 			ensure!(!SwapPair::<T>::contains_key((asset_y, asset_x)), Error::<T>::SwapAlreadyExists);
-			ensure!(x.saturated_into::<u64>() > 0, Error::<T>::ZeroBalance);
-			ensure!(y.saturated_into::<u64>() > 0, Error::<T>::ZeroBalance);
-			
-			let origin_coin: T::AssetId = 0u32.into();
-
-			if asset_x == origin_coin {
-				ensure!(<T as Config>::Currency::free_balance(&sender).saturated_into::<u64>() >= x.saturated_into::<u64>(), Error::<T>::NotEnoughBalance);
-			} else {
-				ensure!(SubGameAssets::Module::<T>::balance(asset_x, sender.clone()) >= x, Error::<T>::NotEnoughBalance);
-			}
+			ensure!(x > 0, Error::<T>::ZeroBalance);
+			ensure!(y > 0, Error::<T>::ZeroBalance);
 
-			if asset_y == origin_coin {
-				ensure!(<T as Config>::Currency::free_balance(&sender).saturated_into::<u64>() >= y.saturated_into::<u64>(), Error::<T>::NotEnoughBalance);
-			} else {
-				ensure!(SubGameAssets::Module::<T>::balance(asset_y, sender.clone()) >= y, Error::<T>::NotEnoughBalance);
-			}
+			ensure!(Self::balance_of(asset_x, &sender) >= x, Error::<T>::NotEnoughBalance);
+			ensure!(Self::balance_of(asset_y, &sender) >= y, Error::<T>::NotEnoughBalance);
 
 			let new_pool_id = SwapPoolCount::<T>::get().checked_add(&1u32.into()).ok_or(Error::<T>::PoolCountError)?;
 			let pool_account: T::AccountId = MODULE_ID.into_sub_account(new_pool_id);
-			
-			let mut _no_decimal_x: f64 = x.saturated_into::<u64>() as f64 / SGB_DECIMALS as f64;
-			let metadata_x = SubGameAssets::Metadata::<T>::get(asset_x);
-			if asset_x != origin_coin {
-				ensure!(metadata_x.decimals > 0, Error::<T>::AssetNotFound);
-				
-				let base_decimal: i64 = 10;
-				_no_decimal_x = x.saturated_into::<u64>() as f64 / base_decimal.pow(metadata_x.decimals.saturated_into::<u32>()) as f64;
-			}
 
-			let mut _no_decimal_y: f64 = y.saturated_into::<u64>() as f64 / SGB_DECIMALS as f64;
-			let metadata_y = SubGameAssets::Metadata::<T>::get(asset_y);
-			if asset_y != origin_coin {
-				ensure!(metadata_y.decimals > 0, Error::<T>::AssetNotFound);
-
-				let base_decimal: i64 = 10;
-				_no_decimal_y = y.saturated_into::<u64>() as f64 / base_decimal.pow(metadata_y.decimals.saturated_into::<u32>()) as f64;
-			}
-			
 			// LP token balance
-			let lp_balance: u64 = (libm::sqrt(_no_decimal_x as f64 * _no_decimal_y as f64) * LP_DECIMALS as f64).floor() as u64;
+			let decimals_x = Self::decimals_of(asset_x)?;
+			let decimals_y = Self::decimals_of(asset_y)?;
+			let lp_balance = math::initial_liquidity(x, decimals_x, y, decimals_y).ok_or(Error::<T>::AmountOverflow)?;
+			ensure!(lp_balance > math::MINIMUM_LIQUIDITY, Error::<T>::InsufficientLiquidityMinted);
 			
-			// LP asset id
-			let lp_asset_id: T::AssetId = frame_system::Module::<T>::block_number().saturated_into::<u32>().into();
+			// Create LP Token, its id comes from the range reserved in SubGameAssets
+			let max_zombies: u32 = 999999999;
+        	let min_balance: u32 = 1;
+			let lp_asset_id = SubGameAssets::Module::<T>::_force_create_reserved(pool_account.clone(), max_zombies, min_balance.into())?;
 
 			// SwapPool struct
 			let pool_details = SwapPoolDetails{
@@ -205,42 +355,17 @@ decl_module! {
 				asset_x: asset_x,
 				asset_y: asset_y,
 				asset_lp: lp_asset_id,
-				swap_k: (x.saturated_into::<u128>() * y.saturated_into::<u128>())
+				swap_k: 0
 			};
 			
-			// Create LP Token
-			let max_zombies: u32 = 999999999;
-        	let min_balance: u32 = 1;
-			SubGameAssets::Module::<T>::_force_create(lp_asset_id, pool_account.clone(), max_zombies, min_balance.into())?;
-			let mut symbol_x = "SGB";
-			if asset_x != origin_coin {
-				symbol_x = core::str::from_utf8(&metadata_x.symbol).unwrap();
-			}
-			let mut symbol_y = "SGB";
-			if asset_y != origin_coin {
-				symbol_y = core::str::from_utf8(&metadata_y.symbol).unwrap();
-			}
-			let lp_name = format!("{}-{} LP", symbol_x, symbol_y);
+			let lp_name = format!("{}-{} LP", Self::symbol_of(asset_x), Self::symbol_of(asset_y));
 			SubGameAssets::Module::<T>::_force_set_metadata(pool_account.clone(), lp_asset_id, lp_name.as_bytes().to_vec(), lp_name.as_bytes().to_vec(), 6)?;
-			SubGameAssets::Module::<T>::_mint(pool_account.clone(), lp_asset_id, sender.clone(), lp_balance.saturated_into())?;
+			Self::mint_initial_liquidity(&pool_account, lp_asset_id, &sender, lp_balance)?;
 
 			// transfer x
-			if asset_x == origin_coin {
-				let _balance: u64 = x.saturated_into::<u64>();
-				let _pool_account = pool_account.clone();
-				<T as Config>::Currency::transfer(&sender, &_pool_account, _balance.saturated_into(), ExistenceRequirement::KeepAlive)?;
-			} else {
-				SubGameAssets::Module::<T>::_transfer(sender.clone(), asset_x, pool_account.clone(), x)?;
-			}
-
+			Self::transfer(asset_x, &sender, &pool_account, x, ExistenceRequirement::KeepAlive)?;
 			// transfer y
-			if asset_y == origin_coin {
-				let _balance: u64 = y.saturated_into::<u64>();
-				let _pool_account = pool_account.clone();
-				<T as Config>::Currency::transfer(&sender, &_pool_account, _balance.saturated_into(), ExistenceRequirement::KeepAlive)?;
-			} else {
-				SubGameAssets::Module::<T>::_transfer(sender.clone(), asset_y, pool_account.clone(), y)?;
-			}
+			Self::transfer(asset_y, &sender, &pool_account, y, ExistenceRequirement::KeepAlive)?;
 
 			// SwapPoolCount
 			SwapPoolCount::<T>::put(new_pool_id);
@@ -251,272 +376,901 @@ decl_module! {
 
 			// SwapPool
 			SwapPool::<T>::insert(new_pool_id, pool_details);
+			PoolFee::<T>::insert(new_pool_id, fee);
+			PoolCurve::<T>::insert(new_pool_id, curve);
+			Self::update_k_last(new_pool_id, !ProtocolFee::get().is_zero(), x, y)?;
+			LpAssetSwap::<T>::insert(lp_asset_id, new_pool_id);
+			Self::observe(new_pool_id, PriceCumulative {
+				block_number: frame_system::Module::<T>::block_number(),
+				reserve_x: x,
+				reserve_y: y,
+				..Default::default()
+			});
 
 			Self::deposit_event(RawEvent::CreatePool(sender, new_pool_id, asset_x, x, asset_y, y, pool_account));
 			Ok(())
 		}
 
 		#[weight = <T as Config>::WeightInfo::add_liquidity()]
+		#[transactional]
 		pub fn add_liquidity(
 			origin,
 		    swap_id: T::SwapId,
-			dx: T::SGAssetBalance,
-			dy: T::SGAssetBalance
+			dx: u128,
+			dy: u128
 		) -> dispatch::DispatchResult
 		{
 		    let sender = ensure_signed(origin.clone())?;
 
 			let swap_pool = SwapPool::<T>::get(swap_id);
 			ensure!(swap_pool.swap_id == swap_id, Error::<T>::NoSwapExists);
-			ensure!(dx.saturated_into::<u64>() > 0 && dy.saturated_into::<u64>() > 0, Error::<T>::ZeroBalance);
-			
-			let origin_coin: T::AssetId = 0u32.into();
+			Self::ensure_unlocked(swap_id)?;
+			ensure!(dx > 0 && dy > 0, Error::<T>::ZeroBalance);
 
-			if swap_pool.asset_x == origin_coin {
-				ensure!(<T as Config>::Currency::free_balance(&sender).saturated_into::<u64>() >= dx.saturated_into::<u64>(), Error::<T>::NotEnoughBalance);
-			} else {
-				ensure!(SubGameAssets::Module::<T>::balance(swap_pool.asset_x, sender.clone()) >= dx, Error::<T>::NotEnoughBalance);
-			}
+			ensure!(Self::balance_of(swap_pool.asset_x, &sender) >= dx, Error::<T>::NotEnoughBalance);
+			ensure!(Self::balance_of(swap_pool.asset_y, &sender) >= dy, Error::<T>::NotEnoughBalance);
 
-			if swap_pool.asset_y == origin_coin {
-				ensure!(<T as Config>::Currency::free_balance(&sender).saturated_into::<u64>() >= dy.saturated_into::<u64>(), Error::<T>::NotEnoughBalance);
-			} else {
-				ensure!(SubGameAssets::Module::<T>::balance(swap_pool.asset_y, sender.clone()) >= dy, Error::<T>::NotEnoughBalance);
-			}
-
-			let mut x: u64 = SubGameAssets::Module::<T>::balance(swap_pool.asset_x, swap_pool.account.clone()).saturated_into::<u64>();
-			if swap_pool.asset_x == origin_coin {
-				x = <T as Config>::Currency::free_balance(&swap_pool.account.clone()).saturated_into::<u64>();
-			}
-
-			let mut y: u64 = SubGameAssets::Module::<T>::balance(swap_pool.asset_y, swap_pool.account.clone()).saturated_into::<u64>();
-			if swap_pool.asset_y == origin_coin {
-				y = <T as Config>::Currency::free_balance(&swap_pool.account.clone()).saturated_into::<u64>();
-			}
-			
-			let total_liquidity = x + y;
-			if total_liquidity > Zero::zero() {
-				// LP total supply
-				let lp_total_supply = SubGameAssets::Module::<T>::total_supply(swap_pool.asset_lp);
-
-				// mint LP token y
-				let new_lp_balance_x: u64 = (dx.saturated_into::<u64>() as f64 / x as f64 * lp_total_supply.saturated_into::<u64>() as f64).floor() as u64;
-				let want_dy: u64 = (new_lp_balance_x as f64 / lp_total_supply.saturated_into::<u64>() as f64 * y as f64).floor() as u64;
-
-				// mint LP token x
-				let new_lp_balance_y: u64 = (dy.saturated_into::<u64>() as f64 / y as f64 * lp_total_supply.saturated_into::<u64>() as f64).floor() as u64;
-				let want_dx: u64 = (new_lp_balance_y as f64 / lp_total_supply.saturated_into::<u64>() as f64 * x as f64).floor() as u64;
+			let (x, y) = Self::reserves(&swap_pool);
+			// LP total supply, after the protocol took its fee
+			let fee_on = Self::mint_protocol_fee(&swap_pool, x, y)?;
+			let lp_total_supply: u128 = SubGameAssets::Module::<T>::total_supply(swap_pool.asset_lp).saturated_into();
 
+			let new_lp_balance = if x > 0 && y > 0 && lp_total_supply > 0 {
 				// Check K
-				ensure!(want_dy == dy.saturated_into::<u64>() || want_dx == dx.saturated_into::<u64>(), Error::<T>::LiquidityKError);
-				
-				let mut new_lp_balance = new_lp_balance_x;
-				if want_dx == dx.saturated_into::<u64>() {
-					new_lp_balance = new_lp_balance_y;
-				}
-
-				// transfer x
-				if swap_pool.asset_x == origin_coin {
-					let _balance: u64 = dx.saturated_into::<u64>();
-					<T as Config>::Currency::transfer(&sender, &swap_pool.account.clone(), _balance.saturated_into(), ExistenceRequirement::KeepAlive)?;
-				} else {
-					SubGameAssets::Module::<T>::_transfer(sender.clone(), swap_pool.asset_x, swap_pool.account.clone(), dx)?;
-				}
-
-				// transfer y
-				if swap_pool.asset_y == origin_coin {
-					let _balance: u64 = dy.saturated_into::<u64>();
-					<T as Config>::Currency::transfer(&sender, &swap_pool.account.clone(), _balance.saturated_into(), ExistenceRequirement::KeepAlive)?;
-				} else {
-					SubGameAssets::Module::<T>::_transfer(sender.clone(), swap_pool.asset_y, swap_pool.account.clone(), dy)?;
-				}
-
-				// mint LP token
-				SubGameAssets::Module::<T>::_mint(swap_pool.account.clone(), swap_pool.asset_lp, sender.clone(), new_lp_balance.saturated_into())?;
+				math::liquidity_for_deposit(dx, dy, x, y, lp_total_supply).ok_or(Error::<T>::LiquidityKError)?
 			} else {
-				// transfer x
-				if swap_pool.asset_x == origin_coin {
-					let _balance: u64 = dx.saturated_into::<u64>();
-					<T as Config>::Currency::transfer(&sender, &swap_pool.account.clone(), _balance.saturated_into(), ExistenceRequirement::KeepAlive)?;
-				} else {
-					SubGameAssets::Module::<T>::_transfer(sender.clone(), swap_pool.asset_x, swap_pool.account.clone(), dx)?;
-				}
-
-				// transfer y
-				if swap_pool.asset_y == origin_coin {
-					let _balance: u64 = dy.saturated_into::<u64>();
-					<T as Config>::Currency::transfer(&sender, &swap_pool.account.clone(), _balance.saturated_into(), ExistenceRequirement::KeepAlive)?;
-				} else {
-					SubGameAssets::Module::<T>::_transfer(sender.clone(), swap_pool.asset_y, swap_pool.account.clone(), dy)?;
-				}
-
-				let mut _no_decimal_x: f64 = dx.saturated_into::<u64>() as f64 / SGB_DECIMALS as f64;
-				let metadata_x = SubGameAssets::Metadata::<T>::get(swap_pool.asset_x);
-				if swap_pool.asset_x != origin_coin {					
-					let base_decimal: i64 = 10;
-					_no_decimal_x = dx.saturated_into::<u64>() as f64 / base_decimal.pow(metadata_x.decimals.saturated_into::<u32>()) as f64;
-				}
+				let decimals_x = Self::decimals_of(swap_pool.asset_x)?;
+				let decimals_y = Self::decimals_of(swap_pool.asset_y)?;
+				math::initial_liquidity(dx, decimals_x, dy, decimals_y).ok_or(Error::<T>::AmountOverflow)?
+			};
+			ensure!(new_lp_balance > 0, Error::<T>::ZeroBalance);
 
-				let mut _no_decimal_y: f64 = dy.saturated_into::<u64>() as f64 / SGB_DECIMALS as f64;
-				let metadata_y = SubGameAssets::Metadata::<T>::get(swap_pool.asset_y);
-				if swap_pool.asset_y != origin_coin {
-					let base_decimal: i64 = 10;
-					_no_decimal_y = dy.saturated_into::<u64>() as f64 / base_decimal.pow(metadata_y.decimals.saturated_into::<u32>()) as f64;
-				}
+			// transfer x
+			Self::transfer(swap_pool.asset_x, &sender, &swap_pool.account, dx, ExistenceRequirement::KeepAlive)?;
+			// transfer y
+			Self::transfer(swap_pool.asset_y, &sender, &swap_pool.account, dy, ExistenceRequirement::KeepAlive)?;
 
-				// mint LP token
-				let new_lp_balance: u64 = (libm::sqrt(_no_decimal_x as f64 * _no_decimal_y as f64) * LP_DECIMALS as f64).floor() as u64;
-				SubGameAssets::Module::<T>::_mint(swap_pool.account.clone(), swap_pool.asset_lp, sender.clone(), new_lp_balance.saturated_into())?;
+			// mint LP token, an emptied pool locks the minimum again
+			if lp_total_supply > 0 {
+				SubGameAssets::Module::<T>::_mint(swap_pool.account.clone(), swap_pool.asset_lp, sender.clone(), Self::to_asset_balance(new_lp_balance)?)?;
+			} else {
+				ensure!(new_lp_balance > math::MINIMUM_LIQUIDITY, Error::<T>::InsufficientLiquidityMinted);
+				Self::mint_initial_liquidity(&swap_pool.account, swap_pool.asset_lp, &sender, new_lp_balance)?;
 			}
+			Self::update_k_last(swap_id, fee_on, x.saturating_add(dx), y.saturating_add(dy))?;
+			Self::update_price(swap_id, x.saturating_add(dx), y.saturating_add(dy));
 
 			Self::deposit_event(RawEvent::LiquidityAdded(swap_id, sender.clone(), dx, dy));
 			Ok(())
 		}
 
+		/// Burn `lp_amount` of the pool's LP for at least `min_amount_x` and `min_amount_y` of
+		/// its assets. The whole LP of the account can be removed, only the locked minimum stays.
 		#[weight = <T as Config>::WeightInfo::remove_liquidity()]
+		#[transactional]
 		pub fn remove_liquidity(
 			origin,
 		    swap_id: T::SwapId,
-			lp_amount: T::SGAssetBalance
+			lp_amount: u128,
+			min_amount_x: u128,
+			min_amount_y: u128
 		) -> dispatch::DispatchResult
 		{
 		    let sender = ensure_signed(origin.clone())?;
-			ensure!(lp_amount.saturated_into::<u64>() > 0, Error::<T>::ZeroBalance);
+			ensure!(lp_amount > 0, Error::<T>::ZeroBalance);
 
 			let swap_pool = SwapPool::<T>::get(swap_id);
 			ensure!(swap_pool.swap_id == swap_id, Error::<T>::NoSwapExists);
+			Self::ensure_unlocked(swap_id)?;
 
-			let sender_lp_balance = SubGameAssets::Module::<T>::balance(swap_pool.asset_lp, sender.clone());
+			let sender_lp_balance: u128 = SubGameAssets::Module::<T>::balance(swap_pool.asset_lp, sender.clone()).saturated_into();
 			ensure!(sender_lp_balance >= lp_amount, Error::<T>::NotEnoughLPToken);
-			
-			let origin_coin: T::AssetId = 0u32.into();
 
-			let mut x: u64 = SubGameAssets::Module::<T>::balance(swap_pool.asset_x, swap_pool.account.clone()).saturated_into::<u64>();
-			if swap_pool.asset_x == origin_coin {
-				x = <T as Config>::Currency::free_balance(&swap_pool.account.clone()).saturated_into::<u64>();
-			}
-			ensure!(!x.is_zero(), Error::<T>::NotEnoughLiquidity);
+			let (x, y) = Self::reserves(&swap_pool);
+			ensure!(x > 0, Error::<T>::NotEnoughLiquidity);
+			ensure!(y > 0, Error::<T>::NotEnoughLiquidity);
 
-			let mut y: u64 = SubGameAssets::Module::<T>::balance(swap_pool.asset_y, swap_pool.account.clone()).saturated_into::<u64>();
-			if swap_pool.asset_y == origin_coin {
-				y = <T as Config>::Currency::free_balance(&swap_pool.account.clone()).saturated_into::<u64>();
-			}
-			ensure!(!y.is_zero(), Error::<T>::NotEnoughLiquidity);
+			// LP total supply, after the protocol took its fee
+			let fee_on = Self::mint_protocol_fee(&swap_pool, x, y)?;
+			let lp_total_supply: u128 = SubGameAssets::Module::<T>::total_supply(swap_pool.asset_lp).saturated_into();
 
-			// LP total supply
-			let lp_total_supply = SubGameAssets::Module::<T>::total_supply(swap_pool.asset_lp).saturated_into::<u64>();
-
-			// Check LP token limit
-			let limit_lp: f64 = lp_amount.saturated_into::<u64>() as f64 / lp_total_supply as f64;
-			ensure!(limit_lp < 1.0f64, Error::<T>::TooManyLPToken);
-
-			let dx: T::SGAssetBalance = ((lp_amount.saturated_into::<u64>() as f64 / lp_total_supply as f64 * x as f64).floor() as u64).saturated_into();
-			let dy: T::SGAssetBalance = ((lp_amount.saturated_into::<u64>() as f64 / lp_total_supply as f64 * y as f64).floor() as u64).saturated_into();
-			ensure!(!dx.is_zero() && !dy.is_zero(), Error::<T>::ZeroBalance);
+			let (dx, dy) = math::amounts_for_liquidity(lp_amount, x, y, lp_total_supply).ok_or(Error::<T>::AmountOverflow)?;
+			ensure!(dx > 0 && dy > 0, Error::<T>::ZeroBalance);
+			ensure!(dx >= min_amount_x && dy >= min_amount_y, Error::<T>::InsufficientOutputAmount);
 			
 			// transfer x
-			if swap_pool.asset_x == origin_coin {
-				let _balance: u64 = dx.saturated_into::<u64>();
-				<T as Config>::Currency::transfer(&swap_pool.account.clone(), &sender, _balance.saturated_into(), ExistenceRequirement::AllowDeath)?;
-			} else {
-				SubGameAssets::Module::<T>::_transfer(swap_pool.account.clone(), swap_pool.asset_x, sender.clone(), dx)?;
-			}
-
+			Self::transfer(swap_pool.asset_x, &swap_pool.account, &sender, dx, ExistenceRequirement::AllowDeath)?;
 			// transfer y
-			if swap_pool.asset_y == origin_coin {
-				let _balance: u64 = dy.saturated_into::<u64>();
-				<T as Config>::Currency::transfer(&swap_pool.account.clone(), &sender, _balance.saturated_into(), ExistenceRequirement::AllowDeath)?;
-			} else {
-				SubGameAssets::Module::<T>::_transfer(swap_pool.account.clone(), swap_pool.asset_y, sender.clone(), dy)?;
-			}
+			Self::transfer(swap_pool.asset_y, &swap_pool.account, &sender, dy, ExistenceRequirement::AllowDeath)?;
 
 			// burn LP token
-			SubGameAssets::Module::<T>::_burn(swap_pool.account.clone(), swap_pool.asset_lp, sender.clone(), lp_amount)?;
+			SubGameAssets::Module::<T>::_burn(swap_pool.account.clone(), swap_pool.asset_lp, sender.clone(), Self::to_asset_balance(lp_amount)?)?;
+			Self::update_k_last(swap_id, fee_on, x - dx, y - dy)?;
+			Self::update_price(swap_id, x - dx, y - dy);
 
 			Self::deposit_event(RawEvent::LiquidityRemoved(swap_id, sender.clone(), lp_amount, dx, dy));
 			Ok(())
 		}
 
 		#[weight = <T as Config>::WeightInfo::swap()]
+		#[transactional]
 		pub fn swap(
 			origin,
 		    swap_id: T::SwapId,
 			input_asset: T::AssetId,
-			input_amount: T::SGAssetBalance,
+			input_amount: u128,
 			output_asset: T::AssetId,
-			expected_output_amount: T::SGAssetBalance,
+			expected_output_amount: u128,
 			slipage: u64,
 			deadline: T::BlockNumber,
 		) -> dispatch::DispatchResult
 		{
-			if deadline.saturated_into::<u32>() > 0u32 {
-				let now = frame_system::Module::<T>::block_number();
-				ensure!(deadline >= now, Error::<T>::Deadline);
-			}
+			Self::ensure_deadline(deadline)?;
 
 			let sender = ensure_signed(origin.clone())?;
 
 			let swap_pool = SwapPool::<T>::get(swap_id);
 			ensure!(swap_pool.swap_id == swap_id, Error::<T>::NoSwapExists);
+			Self::ensure_unlocked(swap_id)?;
 			ensure!(input_asset != output_asset, Error::<T>::DuplicateAssetId);
-			ensure!(swap_pool.asset_x == input_asset || swap_pool.asset_y == input_asset, Error::<T>::AssetNotFound);
 			ensure!(swap_pool.asset_x == output_asset || swap_pool.asset_y == output_asset, Error::<T>::AssetNotFound);
-			ensure!(expected_output_amount.saturated_into::<u64>() > 0u64, Error::<T>::ZeroExpectedAmount);
-			ensure!(input_amount.saturated_into::<u64>() > 0u64, Error::<T>::ZeroBalance);
-			
-			let origin_coin: T::AssetId = 0u32.into();
+			ensure!(expected_output_amount > 0, Error::<T>::ZeroExpectedAmount);
 
-			let mut input_balance: u64 = SubGameAssets::Module::<T>::balance(input_asset, swap_pool.account.clone()).saturated_into::<u64>();
-			if input_asset == origin_coin {
-				input_balance = <T as Config>::Currency::free_balance(&swap_pool.account.clone()).saturated_into::<u64>();
-			}
+			let (output_asset, output_amount) = Self::quote_amount_out(swap_id, input_asset, input_amount)?;
 
-			let mut output_balance: u64 = SubGameAssets::Module::<T>::balance(output_asset, swap_pool.account.clone()).saturated_into::<u64>();
-			if output_asset == origin_coin {
-				output_balance = <T as Config>::Currency::free_balance(&swap_pool.account.clone()).saturated_into::<u64>();
+			// slipage
+			if slipage > 0u64 {
+				ensure!(math::within_slipage(expected_output_amount, output_amount, slipage), Error::<T>::Slipage);
 			}
 
-			// $r = 1 - 0.003
-			// $a = $dx / $x
-			// $dy = ($a * $r) / (1 + ($a * $r)) * $y
-			let x: f64 = input_balance as f64;
-			let y: f64 = output_balance as f64;
-			let dx: f64 = input_amount.saturated_into::<u64>() as f64;
-			let r: f64 = 1.0 - FEE;
-			let a: f64 = dx / x;
-			let dy: f64 = (a * r) / (1.0 + (a * r)) * y;
-			let output_amount: u64 = (dy.floor() as u64).saturated_into();
-			ensure!(output_amount > 0u64.into(), Error::<T>::NotEnoughLiquidity);
+			// transfer input
+			Self::transfer(input_asset, &sender, &swap_pool.account, input_amount, ExistenceRequirement::AllowDeath)?;
+			// transfer output
+			Self::transfer(output_asset, &swap_pool.account, &sender, output_amount, ExistenceRequirement::AllowDeath)?;
 
-			// slipage
-			let _expected_output_amount: u64 = expected_output_amount.saturated_into::<u64>();
-			if _expected_output_amount > 0u64 && slipage > 0u64 {
-				let got_slipage: f64 = (_expected_output_amount as i64 - output_amount as i64).abs() as f64 / _expected_output_amount as f64 * 100f64;
-				let _slipage: f64 = slipage as f64 / 100f64;
-				ensure!(_slipage as f64 >= got_slipage  , Error::<T>::Slipage);
+			let (x, y) = Self::reserves(&swap_pool);
+			Self::update_price(swap_id, x, y);
+
+			Self::deposit_swap_event(swap_id, &sender, input_asset, input_amount, output_asset, output_amount);
+			Ok(())
+		}
+
+		/// Sell exactly `amount_in` of `path[0]` for at least `min_amount_out` of the last asset,
+		/// going through the pool of every pair of neighbouring assets in `path`.
+		#[weight = <T as Config>::WeightInfo::swap_exact_in_by_path(path.len() as u32)]
+		#[transactional]
+		pub fn swap_exact_in_by_path(
+			origin,
+			path: Vec<T::AssetId>,
+			amount_in: u128,
+			min_amount_out: u128,
+			deadline: T::BlockNumber,
+		) -> dispatch::DispatchResult
+		{
+			let sender = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			ensure!(amount_in > 0, Error::<T>::ZeroBalance);
+
+			let amounts = Self::get_amounts_out(amount_in, &path)?;
+			let amount_out = amounts[amounts.len() - 1];
+			ensure!(amount_out > 0, Error::<T>::NotEnoughLiquidity);
+			ensure!(amount_out >= min_amount_out, Error::<T>::InsufficientOutputAmount);
+
+			Self::swap_by_path(&sender, &path, &amounts)?;
+
+			Self::deposit_event(RawEvent::PathSwap(sender, path, amount_in, amount_out));
+			Ok(())
+		}
+
+		/// Buy exactly `amount_out` of the last asset of `path` for at most `max_amount_in` of `path[0]`,
+		/// going through the pool of every pair of neighbouring assets in `path`.
+		#[weight = <T as Config>::WeightInfo::swap_exact_out_by_path(path.len() as u32)]
+		#[transactional]
+		pub fn swap_exact_out_by_path(
+			origin,
+			path: Vec<T::AssetId>,
+			amount_out: u128,
+			max_amount_in: u128,
+			deadline: T::BlockNumber,
+		) -> dispatch::DispatchResult
+		{
+			let sender = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			ensure!(amount_out > 0, Error::<T>::ZeroExpectedAmount);
+
+			let amounts = Self::get_amounts_in(amount_out, &path)?;
+			let amount_in = amounts[0];
+			ensure!(amount_in <= max_amount_in, Error::<T>::ExcessiveInputAmount);
+
+			Self::swap_by_path(&sender, &path, &amounts)?;
+
+			Self::deposit_event(RawEvent::PathSwap(sender, path, amount_in, amount_out));
+			Ok(())
+		}
+
+		/// Set the share of the swap fees minted to `ProtocolFeeTo` as LP, zero turns it off.
+		/// Pools only start accruing it on their next add or remove of liquidity.
+		#[weight = <T as Config>::WeightInfo::set_protocol_fee()]
+		pub fn set_protocol_fee(origin, share: Perbill) -> dispatch::DispatchResult {
+			T::FeeOrigin::ensure_origin(origin)?;
+			ProtocolFee::put(share);
+			Self::deposit_event(RawEvent::ProtocolFeeSet(share));
+			Ok(())
+		}
+
+		/// Retire a pool once all of its LP but the locked minimum has been removed. What is left
+		/// of the reserves goes to `ProtocolFeeTo`, the LP asset is destroyed and the pair can be
+		/// created again.
+		#[weight = <T as Config>::WeightInfo::close_pool(T::MaxObservations::get())]
+		#[transactional]
+		pub fn close_pool(origin, swap_id: T::SwapId) -> dispatch::DispatchResult {
+			T::PoolOrigin::ensure_origin(origin)?;
+
+			let swap_pool = SwapPool::<T>::get(swap_id);
+			ensure!(swap_pool.swap_id == swap_id, Error::<T>::NoSwapExists);
+			Self::ensure_unlocked(swap_id)?;
+			let lp_supply = SubGameAssets::Module::<T>::total_supply(swap_pool.asset_lp);
+			let locked = SubGameAssets::Module::<T>::balance(swap_pool.asset_lp, swap_pool.account.clone());
+			ensure!(lp_supply == locked, Error::<T>::PoolNotEmpty);
+
+			// drain the reserves
+			let (x, y) = Self::reserves(&swap_pool);
+			let to = T::ProtocolFeeTo::get();
+			if x > 0 {
+				Self::transfer(swap_pool.asset_x, &swap_pool.account, &to, x, ExistenceRequirement::AllowDeath)?;
+			}
+			if y > 0 {
+				Self::transfer(swap_pool.asset_y, &swap_pool.account, &to, y, ExistenceRequirement::AllowDeath)?;
 			}
 
-			// transfer input
-			if input_asset == origin_coin {
-				let _balance: u64 = input_amount.saturated_into::<u64>();
-				<T as Config>::Currency::transfer(&sender, &swap_pool.account.clone(), _balance.saturated_into(), ExistenceRequirement::AllowDeath)?;
-			} else {
-				SubGameAssets::Module::<T>::_transfer(sender.clone(), input_asset, swap_pool.account.clone(), input_amount)?;
+			// destroy the LP asset
+			if !locked.is_zero() {
+				SubGameAssets::Module::<T>::_burn(swap_pool.account.clone(), swap_pool.asset_lp, swap_pool.account.clone(), locked)?;
 			}
+			SubGameAssets::Module::<T>::_force_destroy(swap_pool.asset_lp, 0)?;
+
+			SwapPair::<T>::remove((swap_pool.asset_x, swap_pool.asset_y));
+			SwapPair::<T>::remove((swap_pool.asset_y, swap_pool.asset_x));
+			SwapPool::<T>::remove(swap_id);
+			PoolFee::<T>::remove(swap_id);
+			PoolCurve::<T>::remove(swap_id);
+			LpAssetSwap::<T>::remove(swap_pool.asset_lp);
+			PriceCumulatives::<T>::remove(swap_id);
+			ObservationCount::<T>::remove(swap_id);
+			Observations::<T>::remove_prefix(swap_id);
+
+			Self::deposit_event(RawEvent::PoolClosed(swap_id, x, y));
+			Ok(())
+		}
 
-			// transfer output
-			if output_asset == origin_coin {
-				<T as Config>::Currency::transfer(&swap_pool.account.clone(), &sender, output_amount.saturated_into(), ExistenceRequirement::AllowDeath)?;
-			} else {
-				SubGameAssets::Module::<T>::_transfer(swap_pool.account.clone(), output_asset, sender.clone(), output_amount.saturated_into())?;
+		/// Borrow `amount_x_out` and `amount_y_out` from the pool and dispatch `call` as the sender
+		/// with them. By the end of the call the pool has to be paid back in either asset so that,
+		/// after the swap fee on what was paid in, `x * y` did not decrease. Otherwise the whole
+		/// flash swap, the call included, is reverted.
+		#[weight = {
+			let dispatch_info = call.get_dispatch_info();
+			(<T as Config>::WeightInfo::flash_swap().saturating_add(dispatch_info.weight), dispatch_info.class)
+		}]
+		#[transactional]
+		pub fn flash_swap(
+			origin,
+			swap_id: T::SwapId,
+			amount_x_out: u128,
+			amount_y_out: u128,
+			call: Box<<T as Config>::Call>,
+		) -> dispatch::DispatchResult
+		{
+			let sender = ensure_signed(origin.clone())?;
+
+			let swap_pool = SwapPool::<T>::get(swap_id);
+			ensure!(swap_pool.swap_id == swap_id, Error::<T>::NoSwapExists);
+			Self::ensure_unlocked(swap_id)?;
+			ensure!(amount_x_out > 0 || amount_y_out > 0, Error::<T>::ZeroBalance);
+
+			let (x, y) = Self::reserves(&swap_pool);
+			ensure!(amount_x_out < x && amount_y_out < y, Error::<T>::NotEnoughLiquidity);
+
+			// lend
+			if amount_x_out > 0 {
+				Self::transfer(swap_pool.asset_x, &swap_pool.account, &sender, amount_x_out, ExistenceRequirement::AllowDeath)?;
+			}
+			if amount_y_out > 0 {
+				Self::transfer(swap_pool.asset_y, &swap_pool.account, &sender, amount_y_out, ExistenceRequirement::AllowDeath)?;
 			}
 
-			Self::deposit_event(RawEvent::Swap(swap_id, sender.clone(), input_asset, input_amount, output_asset, output_amount.saturated_into()));
+			FlashLocked::<T>::insert(swap_id, true);
+			let result = call.dispatch(origin);
+			FlashLocked::<T>::remove(swap_id);
+			result.map_err(|e| e.error)?;
+
+			// check the repayment
+			let (new_x, new_y) = Self::reserves(&swap_pool);
+			let amount_x_in = new_x.saturating_sub(x - amount_x_out);
+			let amount_y_in = new_y.saturating_sub(y - amount_y_out);
+			ensure!(amount_x_in > 0 || amount_y_in > 0, Error::<T>::InsufficientInputAmount);
+			let fee = Self::pool_fee(swap_id);
+			let k_holds = match Self::stable_of(&swap_pool)? {
+				None => math::flash_k_holds(x, y, new_x, new_y, amount_x_in, amount_y_in, fee),
+				Some(stable) => math::stable_flash_d_holds((x, y), (new_x, new_y), (amount_x_in, amount_y_in), fee, stable),
+			}.ok_or(Error::<T>::AmountOverflow)?;
+			ensure!(k_holds, Error::<T>::FlashSwapKError);
+			Self::update_price(swap_id, new_x, new_y);
+
+			Self::deposit_event(RawEvent::FlashSwap(swap_id, sender, amount_x_out, amount_y_out, amount_x_in, amount_y_in));
 			Ok(())
 		}
 	}
 }
 
 impl<T: Config> Module<T> {
-	
-}
\ No newline at end of file
+	/// The native currency, it is not a `pallet_subgame_assets` asset
+	pub fn origin_coin() -> T::AssetId {
+		0u32.into()
+	}
+
+	/// Balance of the asset held by the account
+	pub fn balance_of(asset: T::AssetId, who: &T::AccountId) -> u128 {
+		if asset == Self::origin_coin() {
+			<T as Config>::Currency::free_balance(who).saturated_into()
+		} else {
+			SubGameAssets::Module::<T>::balance(asset, who.clone()).saturated_into()
+		}
+	}
+
+	/// Reserves of both assets of the pool
+	pub fn reserves(swap_pool: &SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>) -> (u128, u128) {
+		(
+			Self::balance_of(swap_pool.asset_x, &swap_pool.account),
+			Self::balance_of(swap_pool.asset_y, &swap_pool.account),
+		)
+	}
+
+	/// Output of selling `input_amount` of `input_asset` into the pool, and the output asset.
+	/// `swap` pays out exactly this.
+	pub fn quote_amount_out(swap_id: T::SwapId, input_asset: T::AssetId, input_amount: u128) -> Result<(T::AssetId, u128), dispatch::DispatchError> {
+		let swap_pool = SwapPool::<T>::get(swap_id);
+		ensure!(swap_pool.swap_id == swap_id, Error::<T>::NoSwapExists);
+		let output_asset = Self::other_asset(&swap_pool, input_asset)?;
+		ensure!(input_amount > 0, Error::<T>::ZeroBalance);
+
+		let output_amount = Self::amount_out(&swap_pool, input_asset, output_asset, input_amount)?;
+		ensure!(output_amount > 0, Error::<T>::NotEnoughLiquidity);
+		Ok((output_asset, output_amount))
+	}
+
+	/// Input of the other asset needed to buy `output_amount` of `output_asset` from the pool, and the input asset
+	pub fn quote_amount_in(swap_id: T::SwapId, output_asset: T::AssetId, output_amount: u128) -> Result<(T::AssetId, u128), dispatch::DispatchError> {
+		let swap_pool = SwapPool::<T>::get(swap_id);
+		ensure!(swap_pool.swap_id == swap_id, Error::<T>::NoSwapExists);
+		let input_asset = Self::other_asset(&swap_pool, output_asset)?;
+		ensure!(output_amount > 0, Error::<T>::ZeroExpectedAmount);
+
+		let input_amount = Self::amount_in(&swap_pool, input_asset, output_asset, output_amount)?;
+		Ok((input_asset, input_amount))
+	}
+
+	/// Reserves and LP supply of the pool
+	pub fn pool_reserves(swap_id: T::SwapId) -> Option<PoolReserves<T::AssetId>> {
+		let swap_pool = SwapPool::<T>::get(swap_id);
+		if swap_pool.swap_id != swap_id {
+			return None;
+		}
+		let (reserve_x, reserve_y) = Self::reserves(&swap_pool);
+		Some(PoolReserves {
+			asset_x: swap_pool.asset_x,
+			reserve_x,
+			asset_y: swap_pool.asset_y,
+			reserve_y,
+			asset_lp: swap_pool.asset_lp,
+			lp_supply: SubGameAssets::Module::<T>::total_supply(swap_pool.asset_lp).saturated_into(),
+			fee: Self::pool_fee(swap_id),
+			curve: Self::pool_curve(swap_id),
+		})
+	}
+
+	/// LP balance of the account and what `remove_liquidity` would pay out for it
+	pub fn lp_value(swap_id: T::SwapId, who: &T::AccountId) -> Option<LpValue> {
+		let reserves = Self::pool_reserves(swap_id)?;
+		let lp_balance: u128 = SubGameAssets::Module::<T>::balance(reserves.asset_lp, who.clone()).saturated_into();
+		let (amount_x, amount_y) = if lp_balance > 0 {
+			math::amounts_for_liquidity(lp_balance, reserves.reserve_x, reserves.reserve_y, reserves.lp_supply)?
+		} else {
+			(0, 0)
+		};
+		Some(LpValue {
+			lp_balance,
+			lp_supply: reserves.lp_supply,
+			amount_x,
+			amount_y,
+		})
+	}
+
+	/// Fill `LpAssetSwap` for the pools created before it existed. Their LP assets keep the
+	/// block number ids they were created with, new pools take reserved ids.
+	fn migrate_lp_lookup() -> Weight {
+		let mut reads: u64 = 0;
+		let mut writes: u64 = 0;
+		for (swap_id, swap_pool) in SwapPool::<T>::iter() {
+			reads += 2;
+			if !LpAssetSwap::<T>::contains_key(swap_pool.asset_lp) {
+				LpAssetSwap::<T>::insert(swap_pool.asset_lp, swap_id);
+				writes += 1;
+			}
+		}
+		T::DbWeight::get().reads_writes(reads, writes)
+	}
+
+	/// Clear `swap_k` of the pools created before fee tiers. It held `x * y` and is read as the
+	/// k of the last liquidity change since, so the protocol fee would be minted on all the
+	/// growth since the pool was created. Those pools get the default fee set, which also marks
+	/// them done.
+	fn migrate_k_last() -> Weight {
+		let mut reads: u64 = 0;
+		let mut writes: u64 = 0;
+		for (swap_id, mut swap_pool) in SwapPool::<T>::iter() {
+			reads += 2;
+			if !PoolFee::<T>::contains_key(swap_id) {
+				swap_pool.swap_k = 0;
+				SwapPool::<T>::insert(swap_id, swap_pool);
+				PoolFee::<T>::insert(swap_id, math::DEFAULT_FEE);
+				writes += 2;
+			}
+		}
+		T::DbWeight::get().reads_writes(reads, writes)
+	}
+
+	/// Start the price accumulators of the pools created before they existed
+	fn migrate_price_cumulatives() -> Weight {
+		let now = frame_system::Module::<T>::block_number();
+		let mut reads: u64 = 0;
+		let mut writes: u64 = 0;
+		for (swap_id, swap_pool) in SwapPool::<T>::iter() {
+			reads += 1;
+			if !ObservationCount::<T>::contains_key(swap_id) {
+				let (reserve_x, reserve_y) = Self::reserves(&swap_pool);
+				Self::observe(swap_id, PriceCumulative { block_number: now, reserve_x, reserve_y, ..Default::default() });
+				reads += 2;
+				writes += 3;
+			}
+		}
+		T::DbWeight::get().reads_writes(reads, writes)
+	}
+
+	/// Accumulators of the pool run up to now with the reserves of its last update, without
+	/// writing them. Assets sent to the pool account since do not move the price.
+	pub fn current_cumulative(swap_id: T::SwapId) -> Option<PriceCumulative<T::BlockNumber>> {
+		if !SwapPool::<T>::contains_key(swap_id) {
+			return None;
+		}
+		let now = frame_system::Module::<T>::block_number();
+		Some(Self::accumulate(swap_id, Self::price_cumulative(swap_id), now))
+	}
+
+	/// Average prices of asset x in asset y and of asset y in asset x over at least the last
+	/// `window` blocks, measured from the newest observation that is old enough. An empty
+	/// window has no average.
+	pub fn twap_of(swap_id: T::SwapId, window: T::BlockNumber) -> Option<(FixedU128, FixedU128)> {
+		let current = Self::current_cumulative(swap_id)?;
+		let target = current.block_number.saturating_sub(window);
+
+		// Observations are kept in block order, binary search the newest one at or before `target`
+		let max = T::MaxObservations::get().max(1);
+		let count = Self::observation_count(swap_id);
+		let kept = count.min(max);
+		let first = count - kept;
+		let observation = |i: u32| Observations::<T>::get(swap_id, (first + i) % max);
+		if kept == 0 || observation(0).block_number > target {
+			return None;
+		}
+		let (mut low, mut high) = (0u32, kept - 1);
+		while low < high {
+			let mid = low + (high - low + 1) / 2;
+			if observation(mid).block_number <= target {
+				low = mid;
+			} else {
+				high = mid - 1;
+			}
+		}
+		let start = observation(low);
+
+		let elapsed: u128 = (current.block_number - start.block_number).saturated_into();
+		if elapsed == 0 {
+			return None;
+		}
+		let price_x = current.price_x_cumulative.wrapping_sub(start.price_x_cumulative) / elapsed;
+		let price_y = current.price_y_cumulative.wrapping_sub(start.price_y_cumulative) / elapsed;
+		Some((FixedU128::from_inner(price_x), FixedU128::from_inner(price_y)))
+	}
+
+	/// Run the accumulators of the pool up to now with the reserves of the last update, then
+	/// record the reserves `x` and `y` it holds after a change, Uniswap v2 style. The first call
+	/// in a block takes an observation.
+	fn update_price(swap_id: T::SwapId, x: u128, y: u128) {
+		let now = frame_system::Module::<T>::block_number();
+		let cumulative = Self::price_cumulative(swap_id);
+		let observe = cumulative.block_number < now;
+		let mut cumulative = Self::accumulate(swap_id, cumulative, now);
+		cumulative.reserve_x = x;
+		cumulative.reserve_y = y;
+		if observe {
+			Self::observe(swap_id, cumulative);
+		} else {
+			PriceCumulatives::<T>::insert(swap_id, cumulative);
+		}
+	}
+
+	fn accumulate(
+		swap_id: T::SwapId,
+		mut cumulative: PriceCumulative<T::BlockNumber>,
+		now: T::BlockNumber,
+	) -> PriceCumulative<T::BlockNumber> {
+		let elapsed: u128 = now.saturating_sub(cumulative.block_number).saturated_into();
+		let (x, y) = Self::price_reserves(swap_id, cumulative.reserve_x, cumulative.reserve_y);
+		if let (Some(price_x), Some(price_y)) = (FixedU128::checked_from_rational(y, x), FixedU128::checked_from_rational(x, y)) {
+			cumulative.price_x_cumulative = cumulative.price_x_cumulative.wrapping_add(price_x.into_inner().wrapping_mul(elapsed));
+			cumulative.price_y_cumulative = cumulative.price_y_cumulative.wrapping_add(price_y.into_inner().wrapping_mul(elapsed));
+		}
+		cumulative.block_number = now;
+		cumulative
+	}
+
+	fn observe(swap_id: T::SwapId, cumulative: PriceCumulative<T::BlockNumber>) {
+		let max = T::MaxObservations::get().max(1);
+		let count = Self::observation_count(swap_id);
+		Observations::<T>::insert(swap_id, count % max, cumulative.clone());
+		ObservationCount::<T>::insert(swap_id, count.wrapping_add(1));
+		PriceCumulatives::<T>::insert(swap_id, cumulative);
+	}
+
+	/// Swap fee of the pool in basis points
+	pub fn pool_fee(swap_id: T::SwapId) -> u32 {
+		PoolFee::<T>::get(swap_id).unwrap_or(math::DEFAULT_FEE)
+	}
+
+	fn deposit_swap_event(
+		swap_id: T::SwapId,
+		sender: &T::AccountId,
+		input_asset: T::AssetId,
+		input_amount: u128,
+		output_asset: T::AssetId,
+		output_amount: u128,
+	) {
+		Self::deposit_event(RawEvent::Swap(swap_id, sender.clone(), input_asset, input_amount, output_asset, output_amount));
+		let fee = math::fee_of(input_amount, Self::pool_fee(swap_id)).unwrap_or_default();
+		Self::deposit_event(RawEvent::SwapFee(swap_id, input_asset, fee));
+	}
+
+	/// Mint the protocol its share of the fees collected since the last add or remove of liquidity,
+	/// Uniswap v2 style. Returns whether the protocol fee is on.
+	fn mint_protocol_fee(swap_pool: &SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>, x: u128, y: u128) -> Result<bool, dispatch::DispatchError> {
+		let share = ProtocolFee::get();
+		if share.is_zero() {
+			return Ok(false);
+		}
+		if swap_pool.swap_k > 0 {
+			let lp_supply: u128 = SubGameAssets::Module::<T>::total_supply(swap_pool.asset_lp).saturated_into();
+			let root_k_last = math::root_k(swap_pool.swap_k, 1);
+			let root_k = math::root_k(Self::pool_k(swap_pool, x, y)?, 1);
+			let liquidity = math::protocol_fee_liquidity(lp_supply, root_k, root_k_last, share)
+				.ok_or(Error::<T>::AmountOverflow)?;
+			if liquidity > 0 {
+				let fee_to = T::ProtocolFeeTo::get();
+				SubGameAssets::Module::<T>::_mint(swap_pool.account.clone(), swap_pool.asset_lp, fee_to.clone(), Self::to_asset_balance(liquidity)?)?;
+				Self::deposit_event(RawEvent::ProtocolFeeMinted(swap_pool.swap_id, fee_to, liquidity));
+			}
+		}
+		Ok(true)
+	}
+
+	/// `k` of the reserves after the liquidity change, only kept while the protocol fee is on
+	/// Mint the LP of the first deposit, `math::MINIMUM_LIQUIDITY` of it stays in the pool account
+	fn mint_initial_liquidity(
+		pool_account: &T::AccountId,
+		asset_lp: T::AssetId,
+		who: &T::AccountId,
+		lp_balance: u128,
+	) -> dispatch::DispatchResult {
+		SubGameAssets::Module::<T>::_mint(pool_account.clone(), asset_lp, pool_account.clone(), Self::to_asset_balance(math::MINIMUM_LIQUIDITY)?)?;
+		SubGameAssets::Module::<T>::_mint(pool_account.clone(), asset_lp, who.clone(), Self::to_asset_balance(lp_balance - math::MINIMUM_LIQUIDITY)?)
+	}
+
+	fn update_k_last(swap_id: T::SwapId, fee_on: bool, x: u128, y: u128) -> dispatch::DispatchResult {
+		let swap_k = if fee_on { Self::pool_k(&SwapPool::<T>::get(swap_id), x, y)? } else { 0 };
+		SwapPool::<T>::mutate(swap_id, |swap_pool| swap_pool.swap_k = swap_k);
+		Ok(())
+	}
+
+	/// `k` of the pool: `x * y` on the constant product curve and `D²` on the stable curve,
+	/// so that its root grows with the fees on both
+	fn pool_k(swap_pool: &SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>, x: u128, y: u128) -> Result<u128, dispatch::DispatchError> {
+		match Self::stable_of(swap_pool)? {
+			None => Ok(x.saturating_mul(y)),
+			Some(stable) => {
+				let d = math::stable_invariant(x, y, stable).ok_or(Error::<T>::AmountOverflow)?;
+				Ok(u128::try_from(d.saturating_mul(d)).unwrap_or(u128::MAX))
+			}
+		}
+	}
+
+	/// Amplification and decimal multipliers of a stable pool, `None` for a constant product pool
+	fn stable_of(swap_pool: &SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>) -> Result<Option<math::Stable>, dispatch::DispatchError> {
+		let amplification = match Self::pool_curve(swap_pool.swap_id) {
+			Curve::ConstantProduct => return Ok(None),
+			Curve::Stable { amplification } => amplification,
+		};
+		let decimals_x = Self::decimals_of(swap_pool.asset_x)?;
+		let decimals_y = Self::decimals_of(swap_pool.asset_y)?;
+		let decimals = decimals_x.max(decimals_y);
+		let precision = |decimals_of: u32| 10u128.checked_pow(decimals - decimals_of).ok_or(Error::<T>::AmountOverflow);
+		Ok(Some(math::Stable {
+			amplification,
+			precision_x: precision(decimals_x)?,
+			precision_y: precision(decimals_y)?,
+		}))
+	}
+
+	/// Output of selling `input_amount` of `input_asset` into the pool, on the curve of the pool
+	fn amount_out(
+		swap_pool: &SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>,
+		input_asset: T::AssetId,
+		output_asset: T::AssetId,
+		input_amount: u128,
+	) -> Result<u128, dispatch::DispatchError> {
+		let reserve_in = Self::balance_of(input_asset, &swap_pool.account);
+		let reserve_out = Self::balance_of(output_asset, &swap_pool.account);
+		let fee = Self::pool_fee(swap_pool.swap_id);
+		let amount = match Self::stable_of(swap_pool)? {
+			// dy = dx * (1 - fee) * y / (x + dx * (1 - fee))
+			None => math::get_amount_out(input_amount, reserve_in, reserve_out, fee),
+			Some(stable) => {
+				let stable = if swap_pool.asset_x == input_asset { stable } else { stable.flip() };
+				math::stable_amount_out(input_amount, reserve_in, reserve_out, fee, stable)
+			}
+		};
+		amount.ok_or_else(|| Error::<T>::NotEnoughLiquidity.into())
+	}
+
+	/// Input of `input_asset` needed to buy `output_amount` of `output_asset` from the pool, on the curve of the pool
+	fn amount_in(
+		swap_pool: &SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>,
+		input_asset: T::AssetId,
+		output_asset: T::AssetId,
+		output_amount: u128,
+	) -> Result<u128, dispatch::DispatchError> {
+		let reserve_in = Self::balance_of(input_asset, &swap_pool.account);
+		let reserve_out = Self::balance_of(output_asset, &swap_pool.account);
+		let fee = Self::pool_fee(swap_pool.swap_id);
+		let amount = match Self::stable_of(swap_pool)? {
+			None => math::get_amount_in(output_amount, reserve_in, reserve_out, fee),
+			Some(stable) => {
+				let stable = if swap_pool.asset_x == input_asset { stable } else { stable.flip() };
+				math::stable_amount_in(output_amount, reserve_in, reserve_out, fee, stable)
+			}
+		};
+		amount.ok_or_else(|| Error::<T>::NotEnoughLiquidity.into())
+	}
+
+	/// Reserves whose ratio is the marginal price of the pool: the reserves themselves on the
+	/// constant product curve, the reserves of a constant product pool with the same prices on
+	/// the stable curve
+	fn price_reserves(swap_id: T::SwapId, x: u128, y: u128) -> (u128, u128) {
+		if Self::pool_curve(swap_id) == Curve::ConstantProduct {
+			return (x, y);
+		}
+		Self::stable_of(&SwapPool::<T>::get(swap_id))
+			.ok()
+			.flatten()
+			.and_then(|stable| math::stable_price_reserves(x, y, stable))
+			.unwrap_or((x, y))
+	}
+
+	/// The asset of the pool that is not `asset`
+	fn other_asset(swap_pool: &SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>, asset: T::AssetId) -> Result<T::AssetId, dispatch::DispatchError> {
+		if swap_pool.asset_x == asset {
+			Ok(swap_pool.asset_y)
+		} else if swap_pool.asset_y == asset {
+			Ok(swap_pool.asset_x)
+		} else {
+			Err(Error::<T>::AssetNotFound.into())
+		}
+	}
+
+	/// Pool of the pair, either way round
+	pub fn pool_of(asset_a: T::AssetId, asset_b: T::AssetId) -> Result<SwapPoolDetails<T::SwapId, T::AccountId, T::AssetId>, dispatch::DispatchError> {
+		ensure!(SwapPair::<T>::contains_key((asset_a, asset_b)), Error::<T>::InvalidPath);
+		Ok(SwapPool::<T>::get(SwapPair::<T>::get((asset_a, asset_b))))
+	}
+
+	/// Amount of every asset of `path` when selling `amount_in` of the first one
+	pub fn get_amounts_out(amount_in: u128, path: &[T::AssetId]) -> Result<Vec<u128>, dispatch::DispatchError> {
+		Self::ensure_path(path)?;
+		let mut amounts = Vec::with_capacity(path.len());
+		amounts.push(amount_in);
+		for pair in path.windows(2) {
+			let pool = Self::pool_of(pair[0], pair[1])?;
+			let amount_out = Self::amount_out(&pool, pair[0], pair[1], amounts[amounts.len() - 1])?;
+			amounts.push(amount_out);
+		}
+		Ok(amounts)
+	}
+
+	/// Amount of every asset of `path` when buying `amount_out` of the last one
+	pub fn get_amounts_in(amount_out: u128, path: &[T::AssetId]) -> Result<Vec<u128>, dispatch::DispatchError> {
+		Self::ensure_path(path)?;
+		let mut amounts = vec![0u128; path.len()];
+		amounts[path.len() - 1] = amount_out;
+		for i in (1..path.len()).rev() {
+			let pool = Self::pool_of(path[i - 1], path[i])?;
+			amounts[i - 1] = Self::amount_in(&pool, path[i - 1], path[i], amounts[i])?;
+		}
+		Ok(amounts)
+	}
+
+	/// Path from `asset_in` to `asset_out` giving the most output for `amount_in`
+	pub fn best_path_exact_in(asset_in: T::AssetId, asset_out: T::AssetId, amount_in: u128) -> Option<(Vec<T::AssetId>, u128)> {
+		Self::candidate_paths(asset_in, asset_out)
+			.into_iter()
+			.filter_map(|path| {
+				let amounts = Self::get_amounts_out(amount_in, &path).ok()?;
+				let amount_out = amounts[amounts.len() - 1];
+				Some((path, amount_out))
+			})
+			.filter(|(_, amount_out)| *amount_out > 0)
+			.max_by_key(|(_, amount_out)| *amount_out)
+	}
+
+	/// Path from `asset_in` to `asset_out` costing the least input for `amount_out`
+	pub fn best_path_exact_out(asset_in: T::AssetId, asset_out: T::AssetId, amount_out: u128) -> Option<(Vec<T::AssetId>, u128)> {
+		Self::candidate_paths(asset_in, asset_out)
+			.into_iter()
+			.filter_map(|path| {
+				let amounts = Self::get_amounts_in(amount_out, &path).ok()?;
+				let amount_in = amounts[0];
+				Some((path, amount_in))
+			})
+			.min_by_key(|(_, amount_in)| *amount_in)
+	}
+
+	/// Every path through the pools from `asset_in` to `asset_out` of at most `MaxPathLength` assets
+	fn candidate_paths(asset_in: T::AssetId, asset_out: T::AssetId) -> Vec<Vec<T::AssetId>> {
+		let pairs: Vec<(T::AssetId, T::AssetId)> = SwapPool::<T>::iter_values()
+			.map(|pool| (pool.asset_x, pool.asset_y))
+			.collect();
+		let max_length = T::MaxPathLength::get() as usize;
+
+		let mut paths = Vec::new();
+		let mut stack = vec![vec![asset_in]];
+		while let Some(path) = stack.pop() {
+			let last = path[path.len() - 1];
+			if last == asset_out {
+				paths.push(path);
+				continue;
+			}
+			if path.len() >= max_length {
+				continue;
+			}
+			for &(asset_x, asset_y) in pairs.iter() {
+				let next = if asset_x == last {
+					asset_y
+				} else if asset_y == last {
+					asset_x
+				} else {
+					continue;
+				};
+				if !path.contains(&next) {
+					let mut next_path = path.clone();
+					next_path.push(next);
+					stack.push(next_path);
+				}
+			}
+		}
+		paths
+	}
+
+	fn ensure_path(path: &[T::AssetId]) -> dispatch::DispatchResult {
+		ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+		ensure!(path.len() <= T::MaxPathLength::get() as usize, Error::<T>::InvalidPath);
+		for (i, asset) in path.iter().enumerate() {
+			ensure!(!path[i + 1..].contains(asset), Error::<T>::InvalidPath);
+		}
+		Ok(())
+	}
+
+	/// Pay `amounts[0]` into the first pool, every pool then pays its output into the next one
+	/// and the last pool pays the sender
+	fn swap_by_path(sender: &T::AccountId, path: &[T::AssetId], amounts: &[u128]) -> dispatch::DispatchResult {
+		let pools = path.windows(2)
+			.map(|pair| Self::pool_of(pair[0], pair[1]))
+			.collect::<Result<Vec<_>, _>>()?;
+		for pool in pools.iter() {
+			Self::ensure_unlocked(pool.swap_id)?;
+		}
+
+		Self::transfer(path[0], sender, &pools[0].account, amounts[0], ExistenceRequirement::AllowDeath)?;
+		for (i, pool) in pools.iter().enumerate() {
+			let to = pools.get(i + 1).map(|next| &next.account).unwrap_or(sender);
+			Self::transfer(path[i + 1], &pool.account, to, amounts[i + 1], ExistenceRequirement::AllowDeath)?;
+			Self::deposit_swap_event(pool.swap_id, sender, path[i], amounts[i], path[i + 1], amounts[i + 1]);
+		}
+		for pool in pools.iter() {
+			let (x, y) = Self::reserves(pool);
+			Self::update_price(pool.swap_id, x, y);
+		}
+		Ok(())
+	}
+
+	fn ensure_unlocked(swap_id: T::SwapId) -> dispatch::DispatchResult {
+		ensure!(!Self::flash_locked(swap_id), Error::<T>::PoolLocked);
+		Ok(())
+	}
+
+	fn ensure_deadline(deadline: T::BlockNumber) -> dispatch::DispatchResult {
+		if deadline.saturated_into::<u32>() > 0u32 {
+			let now = frame_system::Module::<T>::block_number();
+			ensure!(deadline >= now, Error::<T>::Deadline);
+		}
+		Ok(())
+	}
+
+	/// Decimal places of the asset
+	fn decimals_of(asset: T::AssetId) -> Result<u32, dispatch::DispatchError> {
+		if asset == Self::origin_coin() {
+			return Ok(SGB_DECIMAL_PLACES);
+		}
+		let metadata = SubGameAssets::Metadata::<T>::get(asset);
+		ensure!(metadata.decimals > 0, Error::<T>::AssetNotFound);
+		Ok(metadata.decimals.saturated_into())
+	}
+
+	/// Symbol of the asset, used in the LP token name
+	fn symbol_of(asset: T::AssetId) -> alloc::string::String {
+		if asset == Self::origin_coin() {
+			return "SGB".into();
+		}
+		let metadata = SubGameAssets::Metadata::<T>::get(asset);
+		alloc::string::String::from_utf8_lossy(&metadata.symbol).into_owned()
+	}
+
+	fn to_asset_balance(amount: u128) -> Result<T::SGAssetBalance, dispatch::DispatchError> {
+		T::SGAssetBalance::try_from(amount).map_err(|_| Error::<T>::AmountOverflow.into())
+	}
+
+	/// Move the asset between accounts, without truncating the amount
+	pub fn transfer(
+		asset: T::AssetId,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: u128,
+		existence: ExistenceRequirement,
+	) -> dispatch::DispatchResult {
+		if asset == Self::origin_coin() {
+			let balance = BalanceOf::<T>::try_from(amount).map_err(|_| Error::<T>::AmountOverflow)?;
+			<T as Config>::Currency::transfer(from, to, balance, existence)
+		} else {
+			SubGameAssets::Module::<T>::_transfer(from.clone(), asset, to.clone(), Self::to_asset_balance(amount)?)
+		}
+	}
+}
+
+impl<T: Config> Oracle<T::AssetId, T::BlockNumber> for Module<T> {
+	fn spot_price(base: T::AssetId, quote: T::AssetId) -> Option<FixedU128> {
+		let swap_pool = Self::pool_of(base, quote).ok()?;
+		let (x, y) = Self::reserves(&swap_pool);
+		let (x, y) = Self::price_reserves(swap_pool.swap_id, x, y);
+		if swap_pool.asset_x == base {
+			FixedU128::checked_from_rational(y, x)
+		} else {
+			FixedU128::checked_from_rational(x, y)
+		}
+	}
+
+	fn twap(base: T::AssetId, quote: T::AssetId, window: T::BlockNumber) -> Option<FixedU128> {
+		let swap_pool = Self::pool_of(base, quote).ok()?;
+		let (price_x, price_y) = Self::twap_of(swap_pool.swap_id, window)?;
+		if swap_pool.asset_x == base {
+			Some(price_x)
+		} else {
+			Some(price_y)
+		}
+	}
+}
@@ -0,0 +1,323 @@
+//! Pool math on integers, every result is rounded in favour of the pool
+
+use sp_core::U256;
+use sp_runtime::{PerThing, Perbill};
+use sp_std::convert::TryFrom;
+
+/// Swap fees are in basis points
+pub const FEE_DENOMINATOR: u32 = 10_000;
+/// Fee tiers a pool can be created with: 0.05%, 0.3% and 1%
+pub const FEE_TIERS: [u32; 3] = [5, 30, 100];
+/// Fee of the pools created before fee tiers, 0.3%
+pub const DEFAULT_FEE: u32 = 30;
+
+/// LP token decimals
+pub const LP_DECIMAL_PLACES: u32 = 6;
+/// LP locked in the pool account by the first deposit, so the supply never returns to zero and
+/// the value of one LP unit cannot be inflated
+pub const MINIMUM_LIQUIDITY: u128 = 1_000;
+
+fn to_u128(value: U256) -> Option<u128> {
+	u128::try_from(value).ok()
+}
+
+fn pow10(exp: u32) -> Option<U256> {
+	U256::from(10u8).checked_pow(U256::from(exp))
+}
+
+/// `a * b / c`, rounded down
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+	if c == 0 {
+		return None;
+	}
+	to_u128(U256::from(a).checked_mul(U256::from(b))? / U256::from(c))
+}
+
+/// LP minted for the first deposit: the geometric mean of both amounts without their decimals,
+/// with `LP_DECIMAL_PLACES` decimals
+pub fn initial_liquidity(x: u128, decimals_x: u32, y: u128, decimals_y: u32) -> Option<u128> {
+	let numerator = U256::from(x)
+		.checked_mul(U256::from(y))?
+		.checked_mul(pow10(2 * LP_DECIMAL_PLACES)?)?;
+	let denominator = pow10(decimals_x.checked_add(decimals_y)?)?;
+	to_u128((numerator / denominator).integer_sqrt())
+}
+
+/// LP minted for a deposit of `dx` and `dy` into a pool holding `x` and `y` with `total_lp` issued.
+/// The deposit has to follow the pool ratio up to rounding, the smaller share is minted.
+pub fn liquidity_for_deposit(dx: u128, dy: u128, x: u128, y: u128, total_lp: u128) -> Option<u128> {
+	let lp_x = mul_div(dx, total_lp, x)?;
+	let lp_y = mul_div(dy, total_lp, y)?;
+	let want_dy = mul_div(lp_x, y, total_lp)?;
+	let want_dx = mul_div(lp_y, x, total_lp)?;
+	if want_dy != dy && want_dx != dx {
+		return None;
+	}
+	Some(lp_x.min(lp_y))
+}
+
+/// Amounts paid out for burning `lp` of `total_lp`, rounded down
+pub fn amounts_for_liquidity(lp: u128, x: u128, y: u128, total_lp: u128) -> Option<(u128, u128)> {
+	Some((mul_div(lp, x, total_lp)?, mul_div(lp, y, total_lp)?))
+}
+
+/// Output for selling `dx` into a pool holding `x` of the input and `y` of the output,
+/// with `fee` basis points of `dx` left in the pool
+pub fn get_amount_out(dx: u128, x: u128, y: u128, fee: u32) -> Option<u128> {
+	if x == 0 || y == 0 || fee >= FEE_DENOMINATOR {
+		return None;
+	}
+	let dx_with_fee = U256::from(dx).checked_mul(U256::from(FEE_DENOMINATOR - fee))?;
+	let numerator = dx_with_fee.checked_mul(U256::from(y))?;
+	let denominator = U256::from(x)
+		.checked_mul(U256::from(FEE_DENOMINATOR))?
+		.checked_add(dx_with_fee)?;
+	to_u128(numerator / denominator)
+}
+
+/// Input needed to buy `dy` from a pool holding `x` of the input and `y` of the output,
+/// with `fee` basis points of the input left in the pool
+pub fn get_amount_in(dy: u128, x: u128, y: u128, fee: u32) -> Option<u128> {
+	if x == 0 || dy >= y || fee >= FEE_DENOMINATOR {
+		return None;
+	}
+	let numerator = U256::from(x)
+		.checked_mul(U256::from(dy))?
+		.checked_mul(U256::from(FEE_DENOMINATOR))?;
+	let denominator = U256::from(y - dy).checked_mul(U256::from(FEE_DENOMINATOR - fee))?;
+	to_u128(numerator / denominator)?.checked_add(1)
+}
+
+/// Fee left in the pool when selling `dx`, rounded down
+pub fn fee_of(dx: u128, fee: u32) -> Option<u128> {
+	mul_div(dx, fee as u128, FEE_DENOMINATOR as u128)
+}
+
+/// Whether a pool that held `x` and `y` before a flash swap and `new_x` and `new_y` after it, of
+/// which `dx` and `dy` were paid in, kept `x * y` once `fee` basis points of the input are taken out
+pub fn flash_k_holds(x: u128, y: u128, new_x: u128, new_y: u128, dx: u128, dy: u128, fee: u32) -> Option<bool> {
+	let adjusted = |balance: u128, paid: u128| -> Option<U256> {
+		U256::from(balance)
+			.checked_mul(U256::from(FEE_DENOMINATOR))?
+			.checked_sub(U256::from(paid).checked_mul(U256::from(fee))?)
+	};
+	let k_after = adjusted(new_x, dx)?.checked_mul(adjusted(new_y, dy)?)?;
+	let k_before = U256::from(x)
+		.checked_mul(U256::from(y))?
+		.checked_mul(U256::from(FEE_DENOMINATOR).pow(U256::from(2u8)))?;
+	Some(k_after >= k_before)
+}
+
+/// Smallest amount paying back a flash swap of `dx` in the same asset, rounded up
+pub fn flash_repayment(dx: u128, fee: u32) -> Option<u128> {
+	if fee >= FEE_DENOMINATOR {
+		return None;
+	}
+	mul_div(dx, FEE_DENOMINATOR as u128, (FEE_DENOMINATOR - fee) as u128)?.checked_add(1)
+}
+
+/// `sqrt(x * y)`, always fits in `u128`
+pub fn root_k(x: u128, y: u128) -> u128 {
+	(U256::from(x) * U256::from(y)).integer_sqrt().low_u128()
+}
+
+/// LP minted to the protocol so that it owns `share` of the growth of `sqrt(k)` since `root_k_last`,
+/// which only comes from swap fees: `lp * (rk - rkl) * s / ((1 - s) * rk + s * rkl)`
+pub fn protocol_fee_liquidity(lp_supply: u128, root_k: u128, root_k_last: u128, share: Perbill) -> Option<u128> {
+	if root_k <= root_k_last || share.is_zero() {
+		return Some(0);
+	}
+	let parts = U256::from(share.deconstruct());
+	let accuracy = U256::from(Perbill::ACCURACY);
+	let numerator = U256::from(lp_supply)
+		.checked_mul(U256::from(root_k - root_k_last))?
+		.checked_mul(parts)?;
+	let denominator = (accuracy - parts)
+		.checked_mul(U256::from(root_k))?
+		.checked_add(parts.checked_mul(U256::from(root_k_last))?)?;
+	if denominator.is_zero() {
+		return None;
+	}
+	to_u128(numerator / denominator)
+}
+
+/// Whether `actual` is within `slipage` hundredths of a percent of `expected`
+pub fn within_slipage(expected: u128, actual: u128, slipage: u64) -> bool {
+	let diff = if expected > actual { expected - actual } else { actual - expected };
+	U256::from(diff) * U256::from(10_000u32) <= U256::from(slipage) * U256::from(expected)
+}
+
+/// Highest amplification coefficient of a stable pool
+pub const MAX_AMPLIFICATION: u32 = 1_000_000;
+/// Newton's method steps before the stableswap math gives up
+const STABLE_ITERATIONS: usize = 255;
+
+/// A stableswap pool seen from one of its assets: its amplification, and the multipliers bringing
+/// the balances of both assets to the same decimals
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Stable {
+	pub amplification: u32,
+	pub precision_x: u128,
+	pub precision_y: u128,
+}
+
+impl Stable {
+	/// The same pool seen from the other asset
+	pub fn flip(self) -> Self {
+		Stable {
+			amplification: self.amplification,
+			precision_x: self.precision_y,
+			precision_y: self.precision_x,
+		}
+	}
+
+	fn ann(&self) -> U256 {
+		U256::from(self.amplification) * U256::from(4u8)
+	}
+
+	fn normalize(&self, x: u128, y: u128) -> Option<(U256, U256)> {
+		Some((
+			U256::from(x).checked_mul(U256::from(self.precision_x))?,
+			U256::from(y).checked_mul(U256::from(self.precision_y))?,
+		))
+	}
+}
+
+/// Newton's method runs down to the root after its first step, it has converged once the steps
+/// are down to one or the rounding turns them around
+fn converged(step: usize, next: U256, previous: U256) -> bool {
+	if next > previous {
+		step > 0 || next - previous <= U256::one()
+	} else {
+		previous - next <= U256::one()
+	}
+}
+
+/// Stableswap invariant `D` of normalized balances, solving `4A(x + y) + D = 4AD + D³ / 4xy`
+/// with Newton's method
+fn stable_d(x: U256, y: U256, ann: U256) -> Option<U256> {
+	let sum = x.checked_add(y)?;
+	if sum.is_zero() {
+		return Some(U256::zero());
+	}
+	let two = U256::from(2u8);
+	let mut d = sum;
+	for step in 0..STABLE_ITERATIONS {
+		// D³ / 4xy
+		let d_p = d.checked_mul(d)?.checked_div(x.checked_mul(two)?)?
+			.checked_mul(d)?.checked_div(y.checked_mul(two)?)?;
+		let previous = d;
+		let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(two)?)?.checked_mul(d)?;
+		let denominator = ann.checked_sub(U256::one())?.checked_mul(d)?
+			.checked_add(d_p.checked_mul(U256::from(3u8))?)?;
+		d = numerator.checked_div(denominator)?;
+		if converged(step, d, previous) {
+			return Some(d.max(previous));
+		}
+	}
+	None
+}
+
+/// Normalized balance of the other asset that keeps the invariant `d` when one asset holds `x`
+fn stable_other_balance(x: U256, d: U256, ann: U256) -> Option<U256> {
+	let two = U256::from(2u8);
+	// y² + (x + D / 4A - D) y = D³ / (16Ax)
+	let c = d.checked_mul(d)?.checked_div(x.checked_mul(two)?)?
+		.checked_mul(d)?.checked_div(ann.checked_mul(two)?)?;
+	let b = x.checked_add(d.checked_div(ann)?)?;
+	let mut y = d;
+	for step in 0..STABLE_ITERATIONS {
+		let previous = y;
+		let numerator = y.checked_mul(y)?.checked_add(c)?;
+		let denominator = y.checked_mul(two)?.checked_add(b)?.checked_sub(d)?;
+		y = numerator.checked_div(denominator)?;
+		if converged(step, y, previous) {
+			return Some(y.max(previous));
+		}
+	}
+	None
+}
+
+/// Invariant `D` of a stable pool holding `x` and `y`, in the normalized decimals
+pub fn stable_invariant(x: u128, y: u128, pool: Stable) -> Option<U256> {
+	let (x, y) = pool.normalize(x, y)?;
+	stable_d(x, y, pool.ann())
+}
+
+/// Output for selling `dx` into a stable pool holding `x` of the input and `y` of the output,
+/// with `fee` basis points of `dx` left in the pool. `pool` is seen from the input.
+pub fn stable_amount_out(dx: u128, x: u128, y: u128, fee: u32, pool: Stable) -> Option<u128> {
+	if x == 0 || y == 0 || fee >= FEE_DENOMINATOR || pool.amplification == 0 {
+		return None;
+	}
+	let ann = pool.ann();
+	let (norm_x, norm_y) = pool.normalize(x, y)?;
+	let d = stable_d(norm_x, norm_y, ann)?;
+	let dx_with_fee = mul_div(dx, (FEE_DENOMINATOR - fee) as u128, FEE_DENOMINATOR as u128)?;
+	let new_x = norm_x.checked_add(U256::from(dx_with_fee).checked_mul(U256::from(pool.precision_x))?)?;
+	let new_y = stable_other_balance(new_x, d, ann)?;
+	// one less for the rounding of Newton's method, then rounded down to the output decimals
+	let dy = norm_y.saturating_sub(new_y).saturating_sub(U256::one()) / U256::from(pool.precision_y);
+	to_u128(dy)
+}
+
+/// Input needed to buy `dy` from a stable pool holding `x` of the input and `y` of the output,
+/// with `fee` basis points of the input left in the pool. `pool` is seen from the input.
+pub fn stable_amount_in(dy: u128, x: u128, y: u128, fee: u32, pool: Stable) -> Option<u128> {
+	if x == 0 || dy >= y || fee >= FEE_DENOMINATOR || pool.amplification == 0 {
+		return None;
+	}
+	let ann = pool.ann();
+	let (norm_x, norm_y) = pool.normalize(x, y)?;
+	let d = stable_d(norm_x, norm_y, ann)?;
+	// one more out and one more in for the rounding of Newton's method, so that
+	// `stable_amount_out` of the result pays at least `dy`
+	let new_y = norm_y
+		.checked_sub(U256::from(dy).checked_mul(U256::from(pool.precision_y))?)?
+		.checked_sub(U256::one())?;
+	let new_x = stable_other_balance(new_y, d, ann)?;
+	let precision_x = U256::from(pool.precision_x);
+	let dx_with_fee = (new_x.checked_sub(norm_x)? / precision_x).checked_add(U256::from(2u8))?;
+	let dx = dx_with_fee.checked_mul(U256::from(FEE_DENOMINATOR))?
+		/ U256::from(FEE_DENOMINATOR - fee);
+	to_u128(dx)?.checked_add(1)
+}
+
+/// Whether a stable pool that held `before` and holds `after`, of which `paid` were paid in,
+/// kept its invariant once `fee` basis points of the input are taken out
+pub fn stable_flash_d_holds(
+	before: (u128, u128),
+	after: (u128, u128),
+	paid: (u128, u128),
+	fee: u32,
+	pool: Stable,
+) -> Option<bool> {
+	let adjusted = |balance: u128, paid: u128| -> Option<u128> {
+		balance.checked_mul(FEE_DENOMINATOR as u128)?.checked_sub(paid.checked_mul(fee as u128)?)
+	};
+	let d_after = stable_invariant(adjusted(after.0, paid.0)?, adjusted(after.1, paid.1)?, pool)?;
+	let d_before = stable_invariant(before.0, before.1, pool)?.checked_mul(U256::from(FEE_DENOMINATOR))?;
+	Some(d_after >= d_before)
+}
+
+/// Balances of a constant product pool with the same marginal prices as a stable pool holding
+/// `x` and `y`. The price of x in y on the stableswap curve is
+/// `(4A * 4x²y² + D³y) / (4A * 4x²y² + D³x)` in normalized units.
+pub fn stable_price_reserves(x: u128, y: u128, pool: Stable) -> Option<(u128, u128)> {
+	let ann = pool.ann();
+	let (norm_x, norm_y) = pool.normalize(x, y)?;
+	let d = stable_d(norm_x, norm_y, ann)?;
+	if d.is_zero() {
+		return None;
+	}
+	// 4A * 4x²y² / D³, divided early to stay within U256
+	let xy_d = norm_x.checked_mul(norm_y)? / d;
+	let term = ann.checked_mul(U256::from(4u8))?.checked_mul(xy_d)?.checked_mul(xy_d)? / d;
+	let price_reserve_x = term.checked_add(norm_x)?.checked_mul(U256::from(pool.precision_y))?;
+	let price_reserve_y = term.checked_add(norm_y)?.checked_mul(U256::from(pool.precision_x))?;
+	// keep the ratio when scaling them down into u128
+	let bits = price_reserve_x.max(price_reserve_y).bits();
+	let shift = bits.saturating_sub(127);
+	Some((to_u128(price_reserve_x >> shift)?, to_u128(price_reserve_y >> shift)?))
+}
@@ -72,12 +72,21 @@ impl balances::Config for Test {
 
 parameter_types! {
     pub const StakeOwner: u64 = 1;
+    pub const MaxPathLength: u32 = 4;
+    pub const ProtocolFeeTo: u64 = 99;
+    pub const MaxObservations: u32 = 4;
 }
 impl pallet_swap::Config for Test {
     type Event = Event;
     type WeightInfo = ();
     type SwapId = u32;
     type Currency = balances::Module<Self>;
+    type MaxPathLength = MaxPathLength;
+    type FeeOrigin = EnsureRoot<Self::AccountId>;
+    type PoolOrigin = EnsureRoot<Self::AccountId>;
+    type Call = Call;
+    type ProtocolFeeTo = ProtocolFeeTo;
+    type MaxObservations = MaxObservations;
 }
 
 pub const MILLICENTS: u64 = 10_000_000_000;
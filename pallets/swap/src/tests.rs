@@ -1,6 +1,8 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_noop, assert_ok, traits::{OnFinalize, OnInitialize}};
+use crate::{Curve, Error, LpValue, Oracle, RawEvent, math, mock::*};
+use frame_support::{assert_noop, assert_ok, StorageMap, traits::{Get, OnFinalize, OnInitialize, OnRuntimeUpgrade}};
 use pallet_subgame_assets as SubGameAssets;
+use sp_core::U256;
+use sp_runtime::{FixedPointNumber, FixedU128, Perbill, traits::BadOrigin};
 
 pub const SGB_DECIMALS: u64 = 10_000_000_000;
 pub const USDT_DECIMALS: u64 = 1_000_000;
@@ -53,7 +55,7 @@ fn create_pool() {
         let x: u64 = 11 * SGB_DECIMALS;
         let asset_y: u32 = 7;
         let y: u64 = 1 * USDT_DECIMALS;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         let swap_pool = Swap::swap_pool(1);
         println!("===\n{:?}\n===", swap_pool);
@@ -78,7 +80,7 @@ fn create_pool() {
         let x: u64 = 1 * USDT_DECIMALS;
         let asset_y: u32 = 0;
         let y: u64 = 11 * SGB_DECIMALS;
-        assert_noop!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y), Error::<Test>::SwapAlreadyExists);
+        assert_noop!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct), Error::<Test>::SwapAlreadyExists);
     });
 }
 
@@ -93,14 +95,14 @@ fn create_pool2() {
         let x: u64 = 100000000 * GOGO_DECIMALS + 100;
         let asset_y: u32 = 0;
         let y: u64 = 11 * SGB_DECIMALS;
-        assert_noop!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y), Error::<Test>::NotEnoughBalance);
+        assert_noop!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct), Error::<Test>::NotEnoughBalance);
 
         let user = 1;
         let asset_x: u32 = 8;
         let x: u64 = 1 * GOGO_DECIMALS;
         let asset_y: u32 = 0;
         let y: u64 = 11 * SGB_DECIMALS;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         let swap_pool = Swap::swap_pool(1);
         println!("===\n{:?}\n===", swap_pool);
@@ -128,11 +130,12 @@ fn create_pool_lp_check() {
         let x: u64 = 1000000000;
         let asset_y: u32 = 7;
         let y: u64 = 200000000;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         let swap_pool = Swap::swap_pool(1);
         let got_lp_balance = SubGameAssets::Module::<Test>::total_supply(swap_pool.asset_lp);
-        let want_lp_balance = (libm::sqrt((x as f64 / GOGO_DECIMALS as f64) * (y as f64 / USDT_DECIMALS as f64)) * LP_DECIMALS as f64).floor() as u64;
+        // sqrt(1000 * 200) with 6 decimals
+        let want_lp_balance: u64 = 447213595;
         println!("===");
         println!("got_lp_balance = {:?}, want_lp_balance = {:?}", got_lp_balance, want_lp_balance);
         println!("===");
@@ -145,11 +148,12 @@ fn create_pool_lp_check() {
         let x: u64 = 350000000000000;
         let asset_y: u32 = 7;
         let y: u64 = 35000000000;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         let swap_pool = Swap::swap_pool(2);
         let got_lp_balance = SubGameAssets::Module::<Test>::total_supply(swap_pool.asset_lp);
-        let want_lp_balance = (libm::sqrt((x as f64 / SGB_DECIMALS as f64) * (y as f64 / USDT_DECIMALS as f64)) * LP_DECIMALS as f64).floor() as u64;
+        // sqrt(35000 * 35000) with 6 decimals
+        let want_lp_balance: u64 = 35000000000;
         println!("===");
         println!("got_lp_balance = {:?}, want_lp_balance = {:?}", got_lp_balance, want_lp_balance);
         println!("===");
@@ -167,33 +171,34 @@ fn add_liquidity() {
         let x: u64 = 1 * GOGO_DECIMALS;
         let asset_y: u32 = 0;
         let y: u64 = 11 * SGB_DECIMALS;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         // Should return zero balance error
         let user = 1;
         let swap_id = 1;
         let dx: u64 = 0 * GOGO_DECIMALS;
         let dy: u64 = 22 * SGB_DECIMALS;
-        assert_noop!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx, dy), Error::<Test>::ZeroBalance);
+        assert_noop!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx.into(), dy.into()), Error::<Test>::ZeroBalance);
 
         // Should return liquidity error
         let user = 1;
         let swap_id = 1;
         let dx: u64 = 1 * GOGO_DECIMALS;
         let dy: u64 = 22 * SGB_DECIMALS;
-        assert_noop!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx, dy), Error::<Test>::LiquidityKError);
+        assert_noop!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx.into(), dy.into()), Error::<Test>::LiquidityKError);
 
         let user = 1;
         let swap_id = 1;
         let dx: u64 = 2 * GOGO_DECIMALS;
         let dy: u64 = 22 * SGB_DECIMALS;
-        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx, dy));
+        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx.into(), dy.into()));
 
         // Check LP token balance
         let swap_pool = Swap::swap_pool(1);
         let got_lp_balance = SubGameAssets::Module::<Test>::total_supply(swap_pool.asset_lp);
-        let _lp_total_supply = (libm::sqrt((x as f64 / GOGO_DECIMALS as f64) * (y as f64 / SGB_DECIMALS as f64)) as f64 * LP_DECIMALS as f64).floor() as u64;
-        let want_lp_balance = ((dx as f64 / x as f64) * _lp_total_supply as f64).floor() as u64 + _lp_total_supply;
+        // sqrt(1 * 11) with 6 decimals
+        let _lp_total_supply: u64 = 3316624;
+        let want_lp_balance = dx / x * _lp_total_supply + _lp_total_supply;
         assert_eq!(want_lp_balance, got_lp_balance);
     });
 }
@@ -208,13 +213,13 @@ fn add_liquidity2() {
         let x: u64 = 350000000000000;
         let asset_y: u32 = 7;
         let y: u64 = 35000000000;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         let user = 1;
         let swap_id = 1;
         let dx: u64 = 50000000000000;
         let dy: u64 = 5000000000;
-        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx, dy));
+        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx.into(), dy.into()));
     });
 }
 
@@ -228,19 +233,20 @@ fn add_liquidity3() {
         let x: u64 = 338520327881663;
         let asset_y: u32 = 7;
         let y: u64 = 25170352201;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         let swap_id = 1;
         let new_lp_balance = 145856159058418;
         let swap_pool = Swap::swap_pool(swap_id);
         let old_lp_balance = SubGameAssets::Module::<Test>::total_supply(swap_pool.asset_lp);
         assert_ok!(SubGameAssets::Module::<Test>::_burn(swap_pool.account, swap_pool.asset_lp, user, old_lp_balance));
+        assert_ok!(SubGameAssets::Module::<Test>::_burn(swap_pool.account, swap_pool.asset_lp, swap_pool.account, old_lp_balance));
         assert_ok!(SubGameAssets::Module::<Test>::_mint(swap_pool.account, swap_pool.asset_lp, user, new_lp_balance));
         
         let user = 1;
         let dx: u64 = 13449169291;
         let dy: u64 = 1000000;
-        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx, dy));
+        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx.into(), dy.into()));
     });
 }
 
@@ -254,19 +260,20 @@ fn add_liquidity4() {
         let x: u64 = 267148620;
         let asset_y: u32 = 0;
         let y: u64 = 29617744175575;
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         let swap_id = 1;
         let new_lp_balance = 925091992;
         let swap_pool = Swap::swap_pool(swap_id);
         let old_lp_balance = SubGameAssets::Module::<Test>::total_supply(swap_pool.asset_lp);
         assert_ok!(SubGameAssets::Module::<Test>::_burn(swap_pool.account, swap_pool.asset_lp, user, old_lp_balance));
+        assert_ok!(SubGameAssets::Module::<Test>::_burn(swap_pool.account, swap_pool.asset_lp, swap_pool.account, old_lp_balance));
         assert_ok!(SubGameAssets::Module::<Test>::_mint(swap_pool.account, swap_pool.asset_lp, user, new_lp_balance));
         
         let user = 1;
         let dx: u64 = 1000000;
         let dy: u64 = 110866153481;
-        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx, dy));
+        assert_ok!(Swap::add_liquidity(Origin::signed(user.clone()), swap_id, dx.into(), dy.into()));
     });
 }
 
@@ -280,18 +287,18 @@ fn remove_liquidity() {
         let x: u64 = 1 * GOGO_DECIMALS;
         let asset_y: u32 = 7;
         let y: u64 = 11 * USDT_DECIMALS; 
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
         // Should return not enough LP token error
         let user = 2;
         let swap_id = 1;
         let lp_balance: u64 = 7;
-        assert_noop!(Swap::remove_liquidity(Origin::signed(user.clone()), swap_id, lp_balance), Error::<Test>::NotEnoughLPToken);
+        assert_noop!(Swap::remove_liquidity(Origin::signed(user.clone()), swap_id, lp_balance.into(), 0, 0), Error::<Test>::NotEnoughLPToken);
     });
 }
 
 #[test]
-fn remove_liquidity_too_many() {
+fn remove_all_liquidity() {
     new_test_ext().execute_with(|| {
         init_asset();
 
@@ -300,12 +307,111 @@ fn remove_liquidity_too_many() {
         let x: u64 = 1 * SGB_DECIMALS;
         let asset_y: u32 = 7;
         let y: u64 = 11 * USDT_DECIMALS; 
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
 
-        let user = 1;
-        let swap_id = 1;
-        let lp_balance: u64 = 3316624;
-        assert_noop!(Swap::remove_liquidity(Origin::signed(user.clone()), swap_id, lp_balance), Error::<Test>::TooManyLPToken);
+        // 【Given】the minimum liquidity is locked in the pool
+        let swap_pool = Swap::swap_pool(1);
+        let lp_supply: u128 = 3316624;
+        assert_eq!(math::MINIMUM_LIQUIDITY, SubGameAssets::Module::<Test>::balance(swap_pool.asset_lp, swap_pool.account) as u128);
+        let lp_value = Swap::lp_value(1, &user).unwrap();
+        assert_eq!(lp_supply - math::MINIMUM_LIQUIDITY, lp_value.lp_balance);
+
+        // 【When】asking for more than the LP is worth
+        // 【Then】nothing is removed
+        assert_noop!(
+            Swap::remove_liquidity(Origin::signed(user), 1, lp_value.lp_balance, lp_value.amount_x + 1, 0),
+            Error::<Test>::InsufficientOutputAmount
+        );
+        assert_noop!(
+            Swap::remove_liquidity(Origin::signed(user), 1, lp_value.lp_balance, 0, lp_value.amount_y + 1),
+            Error::<Test>::InsufficientOutputAmount
+        );
+
+        // 【When】the last provider removes all of their LP
+        assert_ok!(Swap::remove_liquidity(Origin::signed(user), 1, lp_value.lp_balance, lp_value.amount_x, lp_value.amount_y));
+
+        // 【Then】only the locked LP and what backs it stay in the pool
+        let (x, y, lp) = pool_reserves(1);
+        assert_eq!(math::MINIMUM_LIQUIDITY, lp);
+        assert_eq!(x as u64 + lp_value.amount_x as u64, 1 * SGB_DECIMALS);
+        assert_eq!(y as u64 + lp_value.amount_y as u64, 11 * USDT_DECIMALS);
+        assert!(x > 0 && y > 0);
+
+        // 【Then】the pool can be funded again at its price
+        let dx = 10 * x;
+        let dy = math::mul_div(math::mul_div(dx, lp, x).unwrap(), y, lp).unwrap();
+        assert_ok!(Swap::add_liquidity(Origin::signed(user), 1, dx, dy));
+    });
+}
+
+#[test]
+fn remove_all_liquidity_without_lock() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+
+        // 【Given】a pool from before the lock
+        let swap_pool = Swap::swap_pool(1);
+        assert_ok!(SubGameAssets::Module::<Test>::_burn(swap_pool.account, swap_pool.asset_lp, swap_pool.account, math::MINIMUM_LIQUIDITY as u64));
+
+        // 【When】the last provider removes the whole supply
+        let (_, _, lp) = pool_reserves(1);
+        assert_ok!(Swap::remove_liquidity(Origin::signed(1), 1, lp, 0, 0));
+
+        // 【Then】the pool is empty
+        assert_eq!((0, 0, 0), pool_reserves(1));
+
+        // 【When】it is funded again
+        assert_ok!(Swap::add_liquidity(Origin::signed(1), 1, 10 * GOGO_DECIMALS as u128, 20 * USDT_DECIMALS as u128));
+
+        // 【Then】it is priced and locked like a new pool
+        let (x, y, lp) = pool_reserves(1);
+        assert_eq!((10 * GOGO_DECIMALS as u128, 20 * USDT_DECIMALS as u128), (x, y));
+        assert_eq!(math::initial_liquidity(x, 6, y, 6).unwrap(), lp);
+        assert_eq!(math::MINIMUM_LIQUIDITY, SubGameAssets::Module::<Test>::balance(swap_pool.asset_lp, swap_pool.account) as u128);
+    });
+}
+
+#[test]
+fn close_pool() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        run_to_block(1);
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 0, 100 * SGB_DECIMALS as u128, 30, Curve::ConstantProduct));
+        let swap_pool = Swap::swap_pool(1);
+        run_to_block(2);
+        assert_ok!(Swap::swap(Origin::signed(1), 1, 8, GOGO_DECIMALS as u128, 0, 1, 0, 0));
+
+        // 【Then】only governance closes pools, once no LP is held outside them
+        assert_noop!(Swap::close_pool(Origin::signed(1), 1), BadOrigin);
+        assert_noop!(Swap::close_pool(Origin::root(), 1), Error::<Test>::PoolNotEmpty);
+        assert_noop!(Swap::close_pool(Origin::root(), 9), Error::<Test>::NoSwapExists);
+
+        // 【When】the last provider leaves and the pool is closed
+        let lp_balance = Swap::lp_value(1, &1).unwrap().lp_balance;
+        assert_ok!(Swap::remove_liquidity(Origin::signed(1), 1, lp_balance, 0, 0));
+        let (x, y, _) = pool_reserves(1);
+        let fee_to = ProtocolFeeTo::get();
+        assert_ok!(Swap::close_pool(Origin::root(), 1));
+
+        // 【Then】the rest of the reserves go to the protocol
+        assert_eq!(x, SubGameAssets::Module::<Test>::balance(8, fee_to) as u128);
+        assert_eq!(y, Balances::free_balance(fee_to) as u128);
+        assert_eq!((0, 0), Swap::reserves(&swap_pool));
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_swap(RawEvent::PoolClosed(1, x, y))));
+
+        // 【Then】the pool, its LP asset and its prices are gone
+        assert_eq!(None, Swap::pool_reserves(1));
+        assert_eq!(None, Swap::swap_id_of_lp(swap_pool.asset_lp));
+        assert_eq!(0, SubGameAssets::Module::<Test>::total_supply(swap_pool.asset_lp));
+        assert!(SubGameAssets::Module::<Test>::_get_metadata(swap_pool.asset_lp).name.is_empty());
+        assert_eq!(0, Swap::observation_count(1));
+        assert_eq!(None, <Swap as Oracle<u32, u64>>::spot_price(8, 0));
+        assert_eq!(Err(Error::<Test>::InvalidPath.into()), Swap::pool_of(8, 0).map(|_| ()));
+
+        // 【Then】the pair can be created again
+        assert_ok!(Swap::create_pool(Origin::signed(1), 0, 100 * SGB_DECIMALS as u128, 8, 1000 * GOGO_DECIMALS as u128, 5, Curve::ConstantProduct));
+        assert_eq!(2, Swap::swap_pool(2).swap_id);
     });
 }
 
@@ -319,7 +425,7 @@ fn swap() {
         let x: u64 = 1 * GOGO_DECIMALS;
         let asset_y: u32 = 7;
         let y: u64 = 11 * USDT_DECIMALS; 
-        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x, asset_y, y));
+        assert_ok!(Swap::create_pool(Origin::signed(user.clone()), asset_x, x.into(), asset_y, y.into(), 30, Curve::ConstantProduct));
         let swap_pool = Swap::swap_pool(1);
 
         let before_user_y_balance = SubGameAssets::Module::<Test>::balance(swap_pool.asset_y, user);
@@ -332,7 +438,7 @@ fn swap() {
         let expected_output_amount: u64 = 5 * USDT_DECIMALS; 
         let slipage: u64 = 90;
         let deadline: u64 = 30;
-        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, input_amount, output_asset, expected_output_amount, slipage, deadline), Error::<Test>::Slipage);
+        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, input_amount.into(), output_asset, expected_output_amount.into(), slipage, deadline), Error::<Test>::Slipage);
 
         let swap_id = 1;
         let input_asset: u32 = 8;
@@ -341,7 +447,7 @@ fn swap() {
         let expected_output_amount: u64 = 5 * USDT_DECIMALS; 
         let slipage: u64 = 990;
         let deadline: u64 = 30;
-        assert_ok!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, input_amount, output_asset, expected_output_amount, slipage, deadline));
+        assert_ok!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, input_amount.into(), output_asset, expected_output_amount.into(), slipage, deadline));
 
         let after_user_y_balance = SubGameAssets::Module::<Test>::balance(swap_pool.asset_y, user);
 
@@ -353,18 +459,18 @@ fn swap() {
         // Should return AssetNotFound error
         let _input_asset: u32 = 5;
         let _output_asset: u32 = 7;
-        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, _input_asset, input_amount, _output_asset, expected_output_amount, slipage, deadline), Error::<Test>::AssetNotFound);
+        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, _input_asset, input_amount.into(), _output_asset, expected_output_amount.into(), slipage, deadline), Error::<Test>::AssetNotFound);
         let _input_asset: u32 = 8;
         let _output_asset: u32 = 5;
-        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, _input_asset, input_amount, _output_asset, expected_output_amount, slipage, deadline), Error::<Test>::AssetNotFound);
+        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, _input_asset, input_amount.into(), _output_asset, expected_output_amount.into(), slipage, deadline), Error::<Test>::AssetNotFound);
         
         // Should return ZeroExpectedAmount error
         let _expected_output_amount: u64 = 0; 
-        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, input_amount, output_asset, _expected_output_amount, slipage, deadline), Error::<Test>::ZeroExpectedAmount);
+        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, input_amount.into(), output_asset, _expected_output_amount.into(), slipage, deadline), Error::<Test>::ZeroExpectedAmount);
         
         // Should return ZeroBalance error
         let _input_amount: u64 = 0 * GOGO_DECIMALS;
-        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, _input_amount, output_asset, expected_output_amount, slipage, deadline), Error::<Test>::ZeroBalance);
+        assert_noop!(Swap::swap(Origin::signed(user.clone()), swap_id, input_asset, _input_amount.into(), output_asset, expected_output_amount.into(), slipage, deadline), Error::<Test>::ZeroBalance);
     });
 }
 
@@ -372,17 +478,831 @@ fn swap() {
 fn swap2() {
     new_test_ext().execute_with(|| {
 
-        // $r = 1 - 0.003
-		// $a = $dx / $x
-		// $dy = ($a * $r) / (1 + ($a * $r)) * $y
-        let x: f64 = 9378908395443.0;
-        let y: f64 = 1037063538.0;
-        let dx: f64 = 10000000000.25;
-        let r: f64 = 1.0 - 0.003;
-        let a: f64 = dx / x;
-        let dy: f64 = (a * r) / (1.0 + (a * r)) * y;
-        let output_amount: u64 = dy.floor() as u64;
+        // dy = dx * 997 * y / (x * 1000 + dx * 997)
+        let x: u128 = 9378908395443;
+        let y: u128 = 1037063538;
+        let dx: u128 = 10000000000;
+        assert_eq!(Some(1101252), math::get_amount_out(dx, x, y, 30));
+
+        // Buying back the output never costs less than the input
+        let dy = math::get_amount_out(dx, x, y, 30).unwrap();
+        assert!(math::get_amount_in(dy, x, y, 30).unwrap() <= dx);
+        assert!(math::get_amount_out(math::get_amount_in(dy, x, y, 30).unwrap(), x, y, 30).unwrap() >= dy);
+    });
+}
+/// Deterministic pseudo random numbers for the property tests
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, max: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) % max + 1
+    }
+}
+
+fn pool_reserves(swap_id: u32) -> (u128, u128, u128) {
+    let swap_pool = Swap::swap_pool(swap_id);
+    let (x, y) = Swap::reserves(&swap_pool);
+    let lp = SubGameAssets::Module::<Test>::total_supply(swap_pool.asset_lp);
+    (x, y, lp.into())
+}
+
+#[test]
+fn swap_k_never_decreases() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        let user = 1;
+        assert_ok!(Swap::create_pool(Origin::signed(user), 8, 1000 * GOGO_DECIMALS as u128, 7, 200 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+
+        let mut rng = Lcg(7);
+        let (mut x, mut y, _) = pool_reserves(1);
+        let mut swapped = 0;
+        for _ in 0..200 {
+            let (input_asset, output_asset) = if rng.next(2) == 1 { (8, 7) } else { (7, 8) };
+            let input_amount = rng.next(100 * GOGO_DECIMALS) as u128;
+            if Swap::swap(Origin::signed(user), 1, input_asset, input_amount, output_asset, 1, 0, 0).is_ok() {
+                swapped += 1;
+            }
+
+            // 【Then】k never decreases, whatever the rounding
+            let (new_x, new_y, _) = pool_reserves(1);
+            assert!(new_x * new_y >= x * y);
+            x = new_x;
+            y = new_y;
+        }
+
+        // 【Then】the invariant was checked on swaps that went through
+        assert!(swapped >= 150);
+    });
+}
+
+#[test]
+fn lp_conserved() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        // 【Given】two liquidity providers
+        let users = [1u64, 2u64];
+        assert_ok!(SubGameAssets::Module::<Test>::_transfer(1, 7, 2, 10000 * USDT_DECIMALS));
+        assert_ok!(SubGameAssets::Module::<Test>::_transfer(1, 8, 2, 10000 * GOGO_DECIMALS));
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 7, 200 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+        let swap_pool = Swap::swap_pool(1);
+
+        let mut rng = Lcg(11);
+        let (mut x, mut y, mut lp) = pool_reserves(1);
+        let (mut attempted, mut succeeded) = (0, 0);
+        for _ in 0..200 {
+            // 【When】a random add, remove or swap
+            let user = users[rng.next(2) as usize - 1];
+            let result = match rng.next(3) {
+                1 => {
+                    let dx = rng.next(50 * GOGO_DECIMALS) as u128;
+                    let lp_x = math::mul_div(dx, lp, x).unwrap();
+                    let dy = math::mul_div(lp_x, y, lp).unwrap();
+                    Some(Swap::add_liquidity(Origin::signed(user), 1, dx, dy))
+                },
+                2 => {
+                    let balance: u64 = SubGameAssets::Module::<Test>::balance(swap_pool.asset_lp, user);
+                    if balance > 0 {
+                        Some(Swap::remove_liquidity(Origin::signed(user), 1, rng.next(balance) as u128, 0, 0))
+                    } else {
+                        None
+                    }
+                },
+                _ => {
+                    let input_amount = rng.next(20 * GOGO_DECIMALS) as u128;
+                    Some(Swap::swap(Origin::signed(user), 1, 8, input_amount, 7, 1, 0, 0))
+                },
+            };
+            if let Some(result) = result {
+                attempted += 1;
+                if result.is_ok() {
+                    succeeded += 1;
+                }
+            }
+
+            // 【Then】the LP supply equals the holders' balances and the locked minimum
+            let (new_x, new_y, new_lp) = pool_reserves(1);
+            let held: u64 = users.iter().chain(&[swap_pool.account]).map(|who| SubGameAssets::Module::<Test>::balance(swap_pool.asset_lp, *who)).sum();
+            assert_eq!(new_lp, held as u128);
+
+            // 【Then】the value of one LP token (x * y / lp^2) never decreases
+            let before = U256::from(x) * U256::from(y) * U256::from(new_lp) * U256::from(new_lp);
+            let after = U256::from(new_x) * U256::from(new_y) * U256::from(lp) * U256::from(lp);
+            assert!(after >= before);
+            x = new_x;
+            y = new_y;
+            lp = new_lp;
+        }
+
+        // 【Then】the checks ran on operations that went through
+        assert!(succeeded * 4 >= attempted * 3);
+    });
+}
+
+/// GOGO-SGB and SGB-USDT pools, so GOGO only reaches USDT through SGB
+fn init_path_pools() {
+    init_asset();
+
+    run_to_block(1);
+    assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 0, 100 * SGB_DECIMALS as u128, 30, Curve::ConstantProduct));
+    run_to_block(2);
+    assert_ok!(Swap::create_pool(Origin::signed(1), 0, 100 * SGB_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+}
+
+#[test]
+fn swap_exact_in_by_path() {
+    new_test_ext().execute_with(|| {
+        init_path_pools();
+
+        let user = 1;
+        let path = vec![8, 0, 7];
+        let amount_in = 10 * GOGO_DECIMALS as u128;
+        let amounts = Swap::get_amounts_out(amount_in, &path).unwrap();
+        let amount_out = amounts[2];
+
+        // 【When】the output is under the minimum
+        // 【Then】nothing moves
+        assert_noop!(
+            Swap::swap_exact_in_by_path(Origin::signed(user), path.clone(), amount_in, amount_out + 1, 0),
+            Error::<Test>::InsufficientOutputAmount
+        );
+
+        // 【When】the deadline has passed
+        run_to_block(5);
+        assert_noop!(
+            Swap::swap_exact_in_by_path(Origin::signed(user), path.clone(), amount_in, amount_out, 4),
+            Error::<Test>::Deadline
+        );
+
+        // 【When】the output meets the minimum
+        let before_gogo = SubGameAssets::Module::<Test>::balance(8, user);
+        let before_usdt = SubGameAssets::Module::<Test>::balance(7, user);
+        let before_sgb = Balances::free_balance(&user);
+        assert_ok!(Swap::swap_exact_in_by_path(Origin::signed(user), path.clone(), amount_in, amount_out, 5));
+
+        // 【Then】the user pays the input, gets the output and keeps the SGB untouched
+        assert_eq!(before_gogo - amount_in as u64, SubGameAssets::Module::<Test>::balance(8, user));
+        assert_eq!(before_usdt + amount_out as u64, SubGameAssets::Module::<Test>::balance(7, user));
+        assert_eq!(before_sgb, Balances::free_balance(&user));
+
+        // 【Then】the SGB went from the first pool into the second
+        let (gogo, sgb) = Swap::reserves(&Swap::swap_pool(1));
+        assert_eq!(1000 * GOGO_DECIMALS as u128 + amount_in, gogo);
+        assert_eq!(100 * SGB_DECIMALS as u128 - amounts[1], sgb);
+        let (sgb, usdt) = Swap::reserves(&Swap::swap_pool(2));
+        assert_eq!(100 * SGB_DECIMALS as u128 + amounts[1], sgb);
+        assert_eq!(1000 * USDT_DECIMALS as u128 - amount_out, usdt);
+    });
+}
+
+#[test]
+fn swap_exact_out_by_path() {
+    new_test_ext().execute_with(|| {
+        init_path_pools();
+
+        let user = 1;
+        let path = vec![8, 0, 7];
+        let amount_out = 10 * USDT_DECIMALS as u128;
+        let amounts = Swap::get_amounts_in(amount_out, &path).unwrap();
+        let amount_in = amounts[0];
+
+        // 【When】the input is over the maximum
+        // 【Then】nothing moves
+        assert_noop!(
+            Swap::swap_exact_out_by_path(Origin::signed(user), path.clone(), amount_out, amount_in - 1, 0),
+            Error::<Test>::ExcessiveInputAmount
+        );
+
+        // 【When】the input is within the maximum
+        let before_gogo = SubGameAssets::Module::<Test>::balance(8, user);
+        let before_usdt = SubGameAssets::Module::<Test>::balance(7, user);
+        assert_ok!(Swap::swap_exact_out_by_path(Origin::signed(user), path.clone(), amount_out, amount_in, 0));
+
+        // 【Then】the user gets exactly the output
+        assert_eq!(before_gogo - amount_in as u64, SubGameAssets::Module::<Test>::balance(8, user));
+        assert_eq!(before_usdt + amount_out as u64, SubGameAssets::Module::<Test>::balance(7, user));
+
+        // 【Then】selling the input would have given at least the output
+        assert!(math::get_amount_out(amounts[1], 100 * SGB_DECIMALS as u128, 1000 * USDT_DECIMALS as u128, 30).unwrap() >= amount_out);
+    });
+}
+
+#[test]
+fn swap_by_path_invalid_path() {
+    new_test_ext().execute_with(|| {
+        init_path_pools();
+
+        let user = 1;
+        let amount_in = 10 * GOGO_DECIMALS as u128;
+
+        // Too short
+        assert_noop!(Swap::swap_exact_in_by_path(Origin::signed(user), vec![8], amount_in, 1, 0), Error::<Test>::InvalidPath);
+        // No GOGO-USDT pool
+        assert_noop!(Swap::swap_exact_in_by_path(Origin::signed(user), vec![8, 7], amount_in, 1, 0), Error::<Test>::InvalidPath);
+        // Goes through the same pool twice
+        assert_noop!(Swap::swap_exact_in_by_path(Origin::signed(user), vec![8, 0, 8], amount_in, 1, 0), Error::<Test>::InvalidPath);
+        // Longer than MaxPathLength
+        assert_noop!(Swap::swap_exact_in_by_path(Origin::signed(user), vec![8, 0, 7, 0, 8], amount_in, 1, 0), Error::<Test>::InvalidPath);
+    });
+}
+
+#[test]
+fn best_path() {
+    new_test_ext().execute_with(|| {
+        init_path_pools();
+
+        // 【Given】a shallow direct GOGO-USDT pool
+        run_to_block(3);
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 10 * GOGO_DECIMALS as u128, 7, 10 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+
+        // 【Then】small trades go direct, large ones through SGB
+        let small = 1000;
+        let (path, amount_out) = Swap::best_path_exact_in(8, 7, small).unwrap();
+        assert_eq!(vec![8, 7], path);
+        assert_eq!(Swap::get_amounts_out(small, &path).unwrap()[1], amount_out);
+
+        let large = 10 * GOGO_DECIMALS as u128;
+        let (path, amount_out) = Swap::best_path_exact_in(8, 7, large).unwrap();
+        assert_eq!(vec![8, 0, 7], path);
+        assert!(amount_out > Swap::get_amounts_out(large, &[8, 7]).unwrap()[1]);
+
+        let (path, amount_in) = Swap::best_path_exact_out(8, 7, 5 * USDT_DECIMALS as u128).unwrap();
+        assert_eq!(vec![8, 0, 7], path);
+        assert_eq!(Swap::get_amounts_in(5 * USDT_DECIMALS as u128, &path).unwrap()[0], amount_in);
+
+        // 【Then】no path to an asset without a pool
+        assert_eq!(None, Swap::best_path_exact_in(8, 9, small));
+    });
+}
+
+#[test]
+fn quotes_match_swap() {
+    new_test_ext().execute_with(|| {
+        init_path_pools();
+
+        let user = 1;
+        let input_amount = 3 * GOGO_DECIMALS as u128;
+
+        // 【Given】the quote for selling GOGO into the GOGO-SGB pool
+        let (output_asset, quoted) = Swap::quote_amount_out(1, 8, input_amount).unwrap();
+        assert_eq!(0, output_asset);
+        assert_eq!(Err(Error::<Test>::AssetNotFound.into()), Swap::quote_amount_out(1, 7, input_amount));
+        assert_eq!(Err(Error::<Test>::NoSwapExists.into()), Swap::quote_amount_out(9, 8, input_amount));
+
+        // 【When】swapping
+        let before = Balances::free_balance(&user);
+        assert_ok!(Swap::swap(Origin::signed(user), 1, 8, input_amount, 0, quoted, 1, 0));
+
+        // 【Then】the swap pays out the quote
+        assert_eq!(before + quoted as u64, Balances::free_balance(&user));
+
+        // 【Then】buying back the quote costs no more than the input
+        let (input_asset, amount_in) = Swap::quote_amount_in(1, 0, quoted).unwrap();
+        assert_eq!(8, input_asset);
+        assert!(amount_in > 0);
+    });
+}
+
+#[test]
+fn pool_reserves_and_lp_value() {
+    new_test_ext().execute_with(|| {
+        init_path_pools();
+
+        // 【Then】the native reserve comes from the pool account's free balance
+        let reserves = Swap::pool_reserves(2).unwrap();
+        assert_eq!(0, reserves.asset_x);
+        assert_eq!(100 * SGB_DECIMALS as u128, reserves.reserve_x);
+        assert_eq!(7, reserves.asset_y);
+        assert_eq!(1000 * USDT_DECIMALS as u128, reserves.reserve_y);
+        assert_eq!(Balances::free_balance(&Swap::swap_pool(2).account) as u128, reserves.reserve_x);
+        assert_eq!(None, Swap::pool_reserves(9));
+
+        // 【Given】user 1 holds all of the LP but the locked minimum
+        let user = 1;
+        let lp_value = Swap::lp_value(2, &user).unwrap();
+        assert_eq!(reserves.lp_supply - math::MINIMUM_LIQUIDITY, lp_value.lp_balance);
+
+        // 【When】user 1 removes all of their LP
+        let before_sgb = Balances::free_balance(&user);
+        let before_usdt = SubGameAssets::Module::<Test>::balance(7, user);
+        assert_ok!(Swap::remove_liquidity(Origin::signed(user), 2, lp_value.lp_balance, 0, 0));
+
+        // 【Then】they get what the LP value said
+        assert_eq!(before_sgb + lp_value.amount_x as u64, Balances::free_balance(&user));
+        assert_eq!(before_usdt + lp_value.amount_y as u64, SubGameAssets::Module::<Test>::balance(7, user));
+        assert_eq!(LpValue { lp_balance: 0, lp_supply: math::MINIMUM_LIQUIDITY, amount_x: 0, amount_y: 0 }, Swap::lp_value(2, &user).unwrap());
+    });
+}
+
+#[test]
+fn fee_tiers() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        run_to_block(1);
+
+        // 【When】the fee is not a tier
+        // 【Then】the pool is not created
+        assert_noop!(
+            Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 25, Curve::ConstantProduct),
+            Error::<Test>::InvalidFeeTier
+        );
+
+        // 【Given】a 0.05% GOGO-SGB pool and a 1% SGB-USDT pool with the same depth
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 0, 1000 * SGB_DECIMALS as u128, 5, Curve::ConstantProduct));
+        run_to_block(2);
+        assert_ok!(Swap::create_pool(Origin::signed(1), 0, 1000 * SGB_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 100, Curve::ConstantProduct));
+        assert_eq!(5, Swap::pool_fee(1));
+        assert_eq!(100, Swap::pool_fee(2));
+        assert_eq!(100, Swap::pool_reserves(2).unwrap().fee);
+        assert_eq!(math::DEFAULT_FEE, Swap::pool_fee(9));
+
+        // 【Then】the cheaper tier gives more output
+        let input_amount = 10 * SGB_DECIMALS as u128;
+        let (_, cheap) = Swap::quote_amount_out(1, 0, input_amount).unwrap();
+        let (_, dear) = Swap::quote_amount_out(2, 0, input_amount).unwrap();
+        assert_eq!(Some(cheap), math::get_amount_out(input_amount, 1000 * SGB_DECIMALS as u128, 1000 * GOGO_DECIMALS as u128, 5));
+        assert_eq!(Some(dear), math::get_amount_out(input_amount, 1000 * SGB_DECIMALS as u128, 1000 * USDT_DECIMALS as u128, 100));
+        assert!(cheap > dear);
+
+        // 【When】swapping in the 1% pool
+        // 【Then】the fee taken is reported
+        assert_ok!(Swap::swap(Origin::signed(1), 2, 0, input_amount, 7, dear, 1, 0));
+        let fee = input_amount / 100;
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_swap(RawEvent::SwapFee(2, 0, fee))));
+    });
+}
+
+#[test]
+fn protocol_fee() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        run_to_block(1);
+        let fee_to = ProtocolFeeTo::get();
+
+        // 【Given】a fifth of the fees go to the protocol
+        assert_noop!(Swap::set_protocol_fee(Origin::signed(1), Perbill::from_percent(20)), BadOrigin);
+        assert_ok!(Swap::set_protocol_fee(Origin::root(), Perbill::from_percent(20)));
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+        let swap_pool = Swap::swap_pool(1);
+
+        // 【When】liquidity is added without any swap
+        // 【Then】the protocol gets nothing
+        assert_ok!(Swap::add_liquidity(Origin::signed(1), 1, 10 * GOGO_DECIMALS as u128, 10 * USDT_DECIMALS as u128));
+        assert_eq!(0, SubGameAssets::Module::<Test>::balance(swap_pool.asset_lp, fee_to));
+
+        // 【When】swaps grow k and liquidity is added again
+        for _ in 0..10 {
+            assert_ok!(Swap::swap(Origin::signed(1), 1, 8, 50 * GOGO_DECIMALS as u128, 7, 1, 0, 0));
+            assert_ok!(Swap::swap(Origin::signed(1), 1, 7, 50 * USDT_DECIMALS as u128, 8, 1, 0, 0));
+        }
+        let (x, y, lp) = pool_reserves(1);
+        let k_last = Swap::swap_pool(1).swap_k;
+        let want = math::protocol_fee_liquidity(lp, math::root_k(x, y), math::root_k(k_last, 1), Perbill::from_percent(20)).unwrap();
+        assert!(want > 0);
+        let dx = 10 * GOGO_DECIMALS as u128;
+        let dy = math::mul_div(math::mul_div(dx, lp + want, x).unwrap(), y, lp + want).unwrap();
+        assert_ok!(Swap::add_liquidity(Origin::signed(1), 1, dx, dy));
+
+        // 【Then】the protocol is minted its share of the growth as LP
+        assert_eq!(want, SubGameAssets::Module::<Test>::balance(swap_pool.asset_lp, fee_to) as u128);
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_swap(RawEvent::ProtocolFeeMinted(1, fee_to, want))));
+        let (x, y, _) = pool_reserves(1);
+        assert_eq!(x * y, Swap::swap_pool(1).swap_k);
+
+        // 【Then】its LP is worth about a fifth of the fees, less than the fees themselves
+        let value = Swap::lp_value(1, &fee_to).unwrap();
+        assert!(value.amount_x > 0 && value.amount_y > 0);
+        assert!(value.amount_x < 500 * GOGO_DECIMALS as u128 * 3 / 1000);
+
+        // 【When】the protocol fee is turned off
+        // 【Then】k stops being tracked and nothing more is minted
+        assert_ok!(Swap::set_protocol_fee(Origin::root(), Perbill::from_percent(0)));
+        assert_ok!(Swap::swap(Origin::signed(1), 1, 8, 50 * GOGO_DECIMALS as u128, 7, 1, 0, 0));
+        assert_ok!(Swap::remove_liquidity(Origin::signed(1), 1, 1000, 0, 0));
+        assert_eq!(0, Swap::swap_pool(1).swap_k);
+        assert_eq!(want, SubGameAssets::Module::<Test>::balance(swap_pool.asset_lp, fee_to) as u128);
+    });
+}
+
+#[test]
+fn k_last_migration() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 0, 100 * SGB_DECIMALS as u128, 30, Curve::ConstantProduct));
+        assert_ok!(Swap::create_pool(Origin::signed(1), 0, 100 * SGB_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 100, Curve::ConstantProduct));
+
+        // 【Given】a pool from before fee tiers, holding x * y in swap_k
+        let (x, y, _) = pool_reserves(1);
+        crate::SwapPool::<Test>::mutate(1, |swap_pool| swap_pool.swap_k = x * y);
+        crate::PoolFee::<Test>::remove(1);
+        // 【Given】a pool with a k from its last liquidity change
+        crate::SwapPool::<Test>::mutate(2, |swap_pool| swap_pool.swap_k = 5);
+
+        // 【When】the runtime is upgraded
+        Swap::on_runtime_upgrade();
 
-        println!("{:?}", output_amount);
+        // 【Then】the old pool has no k and keeps the default fee
+        assert_eq!(0, Swap::swap_pool(1).swap_k);
+        assert_eq!(math::DEFAULT_FEE, Swap::pool_fee(1));
+        assert_eq!(Some(math::DEFAULT_FEE), crate::PoolFee::<Test>::get(1));
+
+        // 【Then】the new pool is untouched
+        assert_eq!(5, Swap::swap_pool(2).swap_k);
+        assert_eq!(100, Swap::pool_fee(2));
+        assert_eq!(crate::STORAGE_VERSION, Swap::storage_version());
+
+        // 【When】the runtime is upgraded again
+        crate::SwapPool::<Test>::mutate(1, |swap_pool| swap_pool.swap_k = 7);
+        crate::PoolFee::<Test>::remove(1);
+        crate::ObservationCount::<Test>::remove(1);
+        Swap::on_runtime_upgrade();
+
+        // 【Then】the migrations do not run again
+        assert_eq!(7, Swap::swap_pool(1).swap_k);
+        assert_eq!(None, crate::PoolFee::<Test>::get(1));
+        assert_eq!(0, Swap::observation_count(1));
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn lp_asset_ids() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        run_to_block(8);
+
+        // 【When】two pools are created in the same block
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 0, 100 * SGB_DECIMALS as u128, 30, Curve::ConstantProduct));
+        assert_ok!(Swap::create_pool(Origin::signed(1), 0, 100 * SGB_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+
+        // 【Then】their LP assets take the first reserved ids
+        let lp_1 = Swap::swap_pool(1).asset_lp;
+        let lp_2 = Swap::swap_pool(2).asset_lp;
+        assert_eq!(SubGameAssets::RESERVED_ASSET_ID_START, lp_1);
+        assert_eq!(SubGameAssets::RESERVED_ASSET_ID_START + 1, lp_2);
+        assert_eq!(Some(1), Swap::swap_id_of_lp(lp_1));
+        assert_eq!(Some(2), Swap::swap_id_of_lp(lp_2));
+        assert_eq!(None, Swap::swap_id_of_lp(7));
+
+        // 【Given】a pool from before the lookup
+        crate::LpAssetSwap::<Test>::remove(lp_2);
+
+        // 【When】the runtime is upgraded
+        Swap::on_runtime_upgrade();
+
+        // 【Then】the lookup is filled in
+        assert_eq!(Some(2), Swap::swap_id_of_lp(lp_2));
+        assert_eq!(Some(1), Swap::swap_id_of_lp(lp_1));
+    });
+}
+
+#[test]
+fn price_accumulators() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        run_to_block(1);
+
+        // 【Given】a GOGO-USDT pool at a price of 1 created at block 1
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+        assert_eq!(1, Swap::price_cumulative(1).block_number);
+        assert_eq!(1, Swap::observation_count(1));
+
+        // 【When】10 blocks pass
+        run_to_block(11);
+
+        // 【Then】the accumulators run up without a write
+        let one = FixedU128::one().into_inner();
+        let current = Swap::current_cumulative(1).unwrap();
+        assert_eq!(11, current.block_number);
+        assert_eq!(10 * one, current.price_x_cumulative);
+        assert_eq!(10 * one, current.price_y_cumulative);
+        assert_eq!(1, Swap::price_cumulative(1).block_number);
+        assert_eq!(None, Swap::current_cumulative(9));
+
+        // 【When】assets are sent to the pool account without a swap
+        let swap_pool = Swap::swap_pool(1);
+        assert_ok!(SubGameAssets::Module::<Test>::_transfer(1, 8, swap_pool.account, 1000 * GOGO_DECIMALS));
+
+        // 【Then】the accumulators keep the reserves of the last update
+        assert_eq!(current, Swap::current_cumulative(1).unwrap());
+
+        // 【When】a swap changes the price
+        assert_ok!(Swap::swap(Origin::signed(1), 1, 8, 100 * GOGO_DECIMALS as u128, 7, 1, 0, 0));
+
+        // 【Then】the old price is accumulated first, once per block, and the new reserves recorded
+        let sums = |cumulative: crate::PriceCumulative<u64>| (cumulative.price_x_cumulative, cumulative.price_y_cumulative, cumulative.block_number);
+        let (x, y, _) = pool_reserves(1);
+        assert_eq!(sums(current.clone()), sums(Swap::price_cumulative(1)));
+        assert_eq!((x, y), (Swap::price_cumulative(1).reserve_x, Swap::price_cumulative(1).reserve_y));
+        assert_ok!(Swap::swap(Origin::signed(1), 1, 7, 10 * USDT_DECIMALS as u128, 8, 1, 0, 0));
+        let (x, y, _) = pool_reserves(1);
+        assert_eq!(sums(current), sums(Swap::price_cumulative(1)));
+        assert_eq!((x, y), (Swap::price_cumulative(1).reserve_x, Swap::price_cumulative(1).reserve_y));
+        assert_eq!(2, Swap::observation_count(1));
+    });
+}
+
+#[test]
+fn twap() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        run_to_block(1);
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+
+        // 【Given】the price moves at block 11
+        run_to_block(11);
+        assert_ok!(Swap::swap(Origin::signed(1), 1, 8, 100 * GOGO_DECIMALS as u128, 7, 1, 0, 0));
+        let (x, y, _) = pool_reserves(1);
+        let price_x = FixedU128::checked_from_rational(y, x).unwrap();
+        let price_y = FixedU128::checked_from_rational(x, y).unwrap();
+        let one = FixedU128::one().into_inner();
+
+        // 【When】querying at block 21
+        run_to_block(21);
+
+        // 【Then】a 20 block window averages both prices
+        let (twap_x, twap_y) = Swap::twap_of(1, 20).unwrap();
+        assert_eq!((10 * one + 10 * price_x.into_inner()) / 20, twap_x.into_inner());
+        assert_eq!((10 * one + 10 * price_y.into_inner()) / 20, twap_y.into_inner());
+
+        // 【Then】a 10 block window only sees the new price
+        assert_eq!((price_x, price_y), Swap::twap_of(1, 10).unwrap());
+
+        // 【Then】a window older than the pool has no price
+        assert_eq!(None, Swap::twap_of(1, 21));
+        assert_eq!(None, Swap::twap_of(9, 10));
+
+        // 【Then】the Oracle quotes either direction
+        assert_eq!(Some(twap_x), <Swap as Oracle<u32, u64>>::twap(8, 7, 20));
+        assert_eq!(Some(twap_y), <Swap as Oracle<u32, u64>>::twap(7, 8, 20));
+        assert_eq!(Some(price_x), <Swap as Oracle<u32, u64>>::spot_price(8, 7));
+        assert_eq!(Some(price_y), <Swap as Oracle<u32, u64>>::spot_price(7, 8));
+        assert_eq!(None, <Swap as Oracle<u32, u64>>::twap(8, 0, 20));
+        assert_eq!(None, <() as Oracle<u32, u64>>::twap(8, 7, 20));
+
+        // 【When】the price moves in the current block
+        // 【Then】no window sees the move, an empty one has no average
+        assert_ok!(Swap::swap(Origin::signed(1), 1, 8, 100 * GOGO_DECIMALS as u128, 7, 1, 0, 0));
+        assert_eq!(Some(twap_x), <Swap as Oracle<u32, u64>>::twap(8, 7, 20));
+        assert_eq!(Some(price_x), <Swap as Oracle<u32, u64>>::twap(8, 7, 1));
+        assert_eq!(None, <Swap as Oracle<u32, u64>>::twap(8, 7, 0));
+        assert_eq!(None, Swap::twap_of(1, 0));
+        assert!(<Swap as Oracle<u32, u64>>::spot_price(8, 7) < Some(price_x));
+
+        // 【When】the next block starts
+        // 【Then】the last window sees the price the block ended with
+        run_to_block(22);
+        let (x, y, _) = pool_reserves(1);
+        assert_eq!(FixedU128::checked_from_rational(y, x), <Swap as Oracle<u32, u64>>::twap(8, 7, 1));
+    });
+}
+
+#[test]
+fn twap_observations_are_pruned() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        run_to_block(1);
+        assert_ok!(Swap::create_pool(Origin::signed(1), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+
+        // 【Given】more swaps in distinct blocks than observations are kept
+        for block in 11..15 {
+            run_to_block(block);
+            assert_ok!(Swap::swap(Origin::signed(1), 1, 8, GOGO_DECIMALS as u128, 7, 1, 0, 0));
+        }
+        assert_eq!(5, Swap::observation_count(1));
+
+        // 【Then】the oldest observation is gone, so is the window that needs it
+        run_to_block(21);
+        assert!(Swap::twap_of(1, 10).is_some());
+        assert!(Swap::twap_of(1, 9).is_some());
+        assert_eq!(None, Swap::twap_of(1, 11));
+
+        // 【Given】a pool from before the accumulators
+        crate::ObservationCount::<Test>::remove(1);
+
+        // 【When】the runtime is upgraded
+        Swap::on_runtime_upgrade();
+
+        // 【Then】they start from the upgrade block
+        assert_eq!(1, Swap::observation_count(1));
+        assert_eq!(21, Swap::price_cumulative(1).block_number);
+        assert_eq!(None, Swap::twap_of(1, 1));
+    });
+}
+
+fn transfer_call(asset_id: u32, target: u64, amount: u128) -> Box<Call> {
+    Box::new(Call::SubGameAssets(SubGameAssets::Call::transfer(asset_id, target, amount as u64)))
+}
+
+#[test]
+fn flash_swap() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+        System::set_block_number(1);
+
+        let user = 1;
+        assert_ok!(Swap::create_pool(Origin::signed(user), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+        let pool_account = Swap::swap_pool(1).account;
+        let amount_out = 100 * GOGO_DECIMALS as u128;
+        let repayment = math::flash_repayment(amount_out, 30).unwrap();
+        assert_eq!(100300903, repayment);
+
+        // 【Then】the loan has to be paid back with the fee
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 1, amount_out, 0, transfer_call(8, pool_account, repayment - 1)), Error::<Test>::FlashSwapKError);
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 1, amount_out, 0, transfer_call(8, 2, repayment)), Error::<Test>::InsufficientInputAmount);
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 1, 0, 0, transfer_call(8, pool_account, repayment)), Error::<Test>::ZeroBalance);
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 1, 1000 * GOGO_DECIMALS as u128, 0, transfer_call(8, pool_account, repayment)), Error::<Test>::NotEnoughLiquidity);
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 2, amount_out, 0, transfer_call(8, pool_account, repayment)), Error::<Test>::NoSwapExists);
+
+        // 【When】user 1 borrows 100 GOGO and pays back just enough
+        let (x, y, _) = pool_reserves(1);
+        assert_ok!(Swap::flash_swap(Origin::signed(user), 1, amount_out, 0, transfer_call(8, pool_account, repayment)));
+
+        // 【Then】the pool keeps the fee and k grew
+        let (new_x, new_y, _) = pool_reserves(1);
+        assert_eq!(x - amount_out + repayment, new_x);
+        assert_eq!(y, new_y);
+        assert!(new_x * new_y > x * y);
+        assert!(!Swap::flash_locked(1));
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_swap(RawEvent::FlashSwap(1, user, amount_out, 0, repayment, 0))));
+    });
+}
+
+#[test]
+fn flash_swap_repaid_in_other_asset() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        let user = 1;
+        assert_ok!(Swap::create_pool(Origin::signed(user), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+        let pool_account = Swap::swap_pool(1).account;
+        let (x, y, _) = pool_reserves(1);
+        let amount_out = 100 * GOGO_DECIMALS as u128;
+
+        // 【Given】the USDT a swap would take for 100 GOGO
+        let amount_in = math::get_amount_in(amount_out, y, x, 30).unwrap();
+
+        // 【Then】one less does not pay the loan back
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 1, amount_out, 0, transfer_call(7, pool_account, amount_in - 1)), Error::<Test>::FlashSwapKError);
+
+        // 【When】user 1 borrows 100 GOGO and pays in USDT
+        let gogo_before = SubGameAssets::Module::<Test>::balance(8, user);
+        assert_ok!(Swap::flash_swap(Origin::signed(user), 1, amount_out, 0, transfer_call(7, pool_account, amount_in)));
+
+        // 【Then】it is the same as a swap
+        assert_eq!(gogo_before + amount_out as u64, SubGameAssets::Module::<Test>::balance(8, user));
+        assert_eq!((x - amount_out, y + amount_in), {
+            let (new_x, new_y, _) = pool_reserves(1);
+            (new_x, new_y)
+        });
+    });
+}
+
+#[test]
+fn flash_swap_reverts_with_call() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        let user = 1;
+        assert_ok!(Swap::create_pool(Origin::signed(user), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, Curve::ConstantProduct));
+        let pool_account = Swap::swap_pool(1).account;
+        let amount_out = 100 * GOGO_DECIMALS as u128;
+
+        // 【Then】a failing call reverts the loan
+        let balance = SubGameAssets::Module::<Test>::balance(8, 2);
+        assert_noop!(
+            Swap::flash_swap(Origin::signed(2), 1, amount_out, 0, transfer_call(8, pool_account, balance as u128 + amount_out + 1)),
+            SubGameAssets::Error::<Test>::AssetBalanceLow
+        );
+
+        // 【Then】the pool cannot be used again while it is lent
+        let nested_swap = Box::new(Call::Swap(crate::Call::swap(1, 8, GOGO_DECIMALS as u128, 7, 1, 0, 0)));
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 1, amount_out, 0, nested_swap), Error::<Test>::PoolLocked);
+        let nested_flash_swap = Box::new(Call::Swap(crate::Call::flash_swap(1, amount_out, 0, transfer_call(8, pool_account, amount_out * 2))));
+        assert_noop!(Swap::flash_swap(Origin::signed(user), 1, amount_out, 0, nested_flash_swap), Error::<Test>::PoolLocked);
+        assert!(!Swap::flash_locked(1));
+    });
+}
+
+const STABLE: Curve = Curve::Stable { amplification: 100 };
+
+#[test]
+fn create_stable_pool() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        let user = 1;
+        let x = 1000 * GOGO_DECIMALS as u128;
+        let y = 1000 * USDT_DECIMALS as u128;
+
+        // 【Then】the amplification is at least 1 and at most MAX_AMPLIFICATION
+        assert_noop!(
+            Swap::create_pool(Origin::signed(user), 8, x, 7, y, 30, Curve::Stable { amplification: 0 }),
+            Error::<Test>::InvalidAmplification
+        );
+        assert_noop!(
+            Swap::create_pool(Origin::signed(user), 8, x, 7, y, 30, Curve::Stable { amplification: math::MAX_AMPLIFICATION + 1 }),
+            Error::<Test>::InvalidAmplification
+        );
+
+        // 【When】a stable pool is created
+        assert_ok!(Swap::create_pool(Origin::signed(user), 8, x, 7, y, 30, STABLE));
+
+        // 【Then】it keeps its curve and mints LP like any pool
+        assert_eq!(STABLE, Swap::pool_curve(1));
+        let reserves = Swap::pool_reserves(1).unwrap();
+        assert_eq!(STABLE, reserves.curve);
+        assert_eq!(math::initial_liquidity(x, 6, y, 6).unwrap(), reserves.lp_supply);
+        assert_eq!(Curve::ConstantProduct, Swap::pool_curve(2));
+    });
+}
+
+#[test]
+fn stable_pool_swap() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        let user = 1;
+        assert_ok!(Swap::create_pool(Origin::signed(user), 8, 1000 * GOGO_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, STABLE));
+        let input_amount = 100 * USDT_DECIMALS as u128;
+
+        // 【Given】the quote for selling 100 USDT
+        let (output_asset, quoted) = Swap::quote_amount_out(1, 7, input_amount).unwrap();
+        assert_eq!(8, output_asset);
+
+        // 【Then】it is close to 1:1 after the fee, far better than on the constant product curve
+        assert_eq!(99650080, quoted);
+        assert!(quoted > math::get_amount_out(input_amount, 1000 * USDT_DECIMALS as u128, 1000 * GOGO_DECIMALS as u128, 30).unwrap());
+
+        // 【When】swapping
+        let before = SubGameAssets::Module::<Test>::balance(8, user);
+        assert_ok!(Swap::swap(Origin::signed(user), 1, 7, input_amount, 8, quoted, 1, 0));
+
+        // 【Then】the swap pays out the quote and the price stays near the peg
+        assert_eq!(before + quoted as u64, SubGameAssets::Module::<Test>::balance(8, user));
+        let price = Swap::spot_price(8, 7).unwrap();
+        assert!(price > FixedU128::saturating_from_integer(1));
+        assert!(price < FixedU128::saturating_from_rational(101, 100));
+
+        // 【Then】buying back the quote pays at least the quote
+        let (input_asset, amount_in) = Swap::quote_amount_in(1, 7, quoted).unwrap();
+        assert_eq!(8, input_asset);
+        assert!(Swap::quote_amount_out(1, 8, amount_in).unwrap().1 >= quoted);
+    });
+}
+
+#[test]
+fn stable_pool_normalizes_decimals() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        // 【Given】a stable SGB-USDT pool, SGB has 10 decimals and USDT 6
+        let user = 1;
+        assert_ok!(Swap::create_pool(Origin::signed(user), 0, 1000 * SGB_DECIMALS as u128, 7, 1000 * USDT_DECIMALS as u128, 30, STABLE));
+
+        // 【Then】1 SGB buys about 1 USDT
+        let (_, quoted) = Swap::quote_amount_out(1, 0, SGB_DECIMALS as u128).unwrap();
+        assert_eq!(996995, quoted);
+
+        // 【Then】and it routes like any pool
+        assert_ok!(Swap::create_pool(Origin::signed(user), 7, 1000 * USDT_DECIMALS as u128, 8, 1000 * GOGO_DECIMALS as u128, 30, Curve::ConstantProduct));
+        let amounts = Swap::get_amounts_out(SGB_DECIMALS as u128, &[0, 7, 8]).unwrap();
+        assert_eq!(quoted, amounts[1]);
+        let before = SubGameAssets::Module::<Test>::balance(8, user);
+        assert_ok!(Swap::swap_exact_in_by_path(Origin::signed(user), vec![0, 7, 8], SGB_DECIMALS as u128, amounts[2], 0));
+        assert_eq!(before + amounts[2] as u64, SubGameAssets::Module::<Test>::balance(8, user));
+    });
+}
+
+#[test]
+fn stable_invariant_never_decreases() {
+    new_test_ext().execute_with(|| {
+        init_asset();
+
+        let user = 1;
+        assert_ok!(Swap::create_pool(Origin::signed(user), 8, 1000 * GOGO_DECIMALS as u128, 7, 200 * USDT_DECIMALS as u128, 30, STABLE));
+        let stable = math::Stable { amplification: 100, precision_x: 1, precision_y: 1 };
+
+        let mut rng = Lcg(11);
+        let (x, y, _) = pool_reserves(1);
+        let mut d = math::stable_invariant(x, y, stable).unwrap();
+        let mut swapped = 0;
+        for _ in 0..200 {
+            let (input_asset, output_asset) = if rng.next(2) == 1 { (8, 7) } else { (7, 8) };
+            let input_amount = rng.next(100 * GOGO_DECIMALS) as u128;
+            if Swap::swap(Origin::signed(user), 1, input_asset, input_amount, output_asset, 1, 0, 0).is_ok() {
+                swapped += 1;
+            }
+
+            // 【Then】D never decreases, whatever the rounding
+            let (new_x, new_y, _) = pool_reserves(1);
+            let new_d = math::stable_invariant(new_x, new_y, stable).unwrap();
+            assert!(new_d >= d);
+            d = new_d;
+        }
+
+        // 【Then】the invariant was checked on swaps that went through
+        assert!(swapped >= 150);
+    });
+}
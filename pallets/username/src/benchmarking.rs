@@ -0,0 +1,80 @@
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use sp_std::{vec, vec::Vec};
+use sp_runtime::{
+	traits::{
+		Saturating,
+	}
+};
+use frame_support::{
+	assert_ok,
+};
+
+#[allow(unused)]
+use crate::Module as Pallet;
+
+/// A valid name of `MaxNameLength` bytes
+fn long_name<T: Config>() -> Vec<u8> {
+	vec![b'a'; T::MaxNameLength::get() as usize]
+}
+
+fn funds<T: Config>() -> BalanceOf<T> {
+	T::NameDeposit::get().saturating_mul(100u32.into()).saturating_add(1_000_000u32.into())
+}
+
+fn funded_caller<T: Config>() -> T::AccountId {
+	let user: T::AccountId = whitelisted_caller();
+	T::Currency::make_free_balance_be(&user, funds::<T>());
+	user
+}
+
+benchmarks! {
+	register {
+		let user = funded_caller::<T>();
+		let name = long_name::<T>();
+	}: _(RawOrigin::Signed(user.clone()), name.clone())
+	verify {
+		assert_eq!(Pallet::<T>::name(&user), Some(name));
+	}
+
+	transfer {
+		let user = funded_caller::<T>();
+		let to: T::AccountId = account("to", 0, 0);
+		T::Currency::make_free_balance_be(&to, funds::<T>());
+		assert_ok!(Pallet::<T>::register(RawOrigin::Signed(user.clone()).into(), long_name::<T>()));
+	}: _(RawOrigin::Signed(user.clone()), to.clone())
+	verify {
+		assert_eq!(Pallet::<T>::name(&to), Some(long_name::<T>()));
+	}
+
+	release {
+		let user = funded_caller::<T>();
+		assert_ok!(Pallet::<T>::register(RawOrigin::Signed(user.clone()).into(), long_name::<T>()));
+	}: _(RawOrigin::Signed(user.clone()))
+	verify {
+		assert_eq!(Pallet::<T>::name(&user), None);
+	}
+
+	add_reserved {
+		let name = long_name::<T>();
+	}: _(RawOrigin::Root, name.clone())
+	verify {
+		assert!(Pallet::<T>::is_reserved(name));
+	}
+
+	remove_reserved {
+		let name = long_name::<T>();
+		Reserved::insert(&name, true);
+	}: _(RawOrigin::Root, name.clone())
+	verify {
+		assert!(!Pallet::<T>::is_reserved(name));
+	}
+}
+
+impl_benchmark_test_suite!(
+	Pallet,
+	crate::mock::new_test_ext(),
+	crate::mock::Test,
+);
@@ -0,0 +1,301 @@
+//! SubGame Username Registry
+//!
+//! One name per account, checked against a length and charset rule and a reserved list managed
+//! by governance. Registering a name reserves a deposit, returned when the name is released.
+//! Names can be transferred, and both the owner of a name and the name of an account are looked
+//! up in one read. Other pallets use the registry through `UsernameRegistry`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
+    traits::{BalanceStatus, Currency, EnsureOrigin, Get, ReservableCurrency},
+    weights::{Weight},
+};
+use frame_system::ensure_signed;
+use sp_runtime::{DispatchError, traits::Zero};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+mod default_weight;
+pub trait WeightInfo {
+    fn register() -> Weight;
+    fn transfer() -> Weight;
+    fn release() -> Weight;
+    fn add_reserved() -> Weight;
+    fn remove_reserved() -> Weight;
+}
+
+type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// Bytes a name is made of, checked after the name is lowercased
+pub trait Charset {
+    fn allows(byte: u8) -> bool;
+}
+
+/// `a-z` and `0-9`
+pub struct LowercaseAlphanumeric;
+impl Charset for LowercaseAlphanumeric {
+    fn allows(byte: u8) -> bool {
+        byte.is_ascii_lowercase() || byte.is_ascii_digit()
+    }
+}
+
+/// Names of accounts, for other pallets
+pub trait UsernameRegistry<AccountId> {
+    /// Register the name for the account and take the deposit, or keep it if the account owns it
+    /// already. Returns the name as stored.
+    fn register(who: &AccountId, name: &[u8]) -> Result<Vec<u8>, DispatchError>;
+    /// Release the name of the account and return the deposit
+    fn release(who: &AccountId) -> dispatch::DispatchResult;
+    /// Account owning the name
+    fn lookup(name: &[u8]) -> Option<AccountId>;
+    /// Name of the account
+    fn name_of(who: &AccountId) -> Option<Vec<u8>>;
+    /// Give the account a name it took before the registry, without a deposit or the name rules
+    fn register_existing(who: &AccountId, name: &[u8]) -> dispatch::DispatchResult;
+    /// Reserve a name nobody owns
+    fn reserve(name: &[u8]) -> dispatch::DispatchResult;
+}
+
+/// Asked before a name changes owner, so pallets referring to the name can refuse
+pub trait OnNameTransfer<AccountId> {
+    fn on_name_transfer(from: &AccountId, to: &AccountId, name: &[u8]) -> dispatch::DispatchResult;
+}
+
+impl<AccountId> OnNameTransfer<AccountId> for () {
+    fn on_name_transfer(_: &AccountId, _: &AccountId, _: &[u8]) -> dispatch::DispatchResult {
+        Ok(())
+    }
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Registration<AccountId, Balance> {
+    pub owner: AccountId,
+    /// Reserved from the owner until the name is released
+    pub deposit: Balance,
+}
+
+pub trait Config: frame_system::Config {
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// Deposit reserved for a name
+    type NameDeposit: Get<BalanceOf<Self>>;
+    type MinNameLength: Get<u32>;
+    type MaxNameLength: Get<u32>;
+    type Charset: Charset;
+    /// Origin managing the reserved names
+    type ReservedOrigin: EnsureOrigin<Self::Origin>;
+    type OnNameTransfer: OnNameTransfer<Self::AccountId>;
+    type WeightInfo: WeightInfo;
+}
+
+decl_storage! {
+    trait Store for Module<T: Config> as Username {
+        pub Registrations get(fn registration): map hasher(blake2_128_concat) Vec<u8> => Option<Registration<T::AccountId, BalanceOf<T>>>;
+        /// Reverse lookup of `Registrations`
+        pub Names get(fn name): map hasher(blake2_128_concat) T::AccountId => Option<Vec<u8>>;
+        /// Names nobody can register
+        pub Reserved get(fn is_reserved): map hasher(blake2_128_concat) Vec<u8> => bool;
+    }
+    add_extra_genesis {
+        config(reserved_names): Vec<Vec<u8>>;
+        build(|config: &GenesisConfig| {
+            for name in &config.reserved_names {
+                Reserved::insert(name.to_ascii_lowercase(), true);
+            }
+        });
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as frame_system::Config>::AccountId,
+        Balance = BalanceOf<T>,
+    {
+        Registered(AccountId, Vec<u8>, Balance),
+        /// from, to, name
+        Transferred(AccountId, AccountId, Vec<u8>),
+        Released(AccountId, Vec<u8>),
+        ReservedAdded(Vec<u8>),
+        ReservedRemoved(Vec<u8>),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Config> {
+        /// Shorter than `MinNameLength` or longer than `MaxNameLength`
+        InvalidLength,
+        /// A byte the charset does not allow
+        InvalidCharacter,
+        NameReserved,
+        NameTaken,
+        /// The account owns a name already
+        AlreadyHasName,
+        /// The account owns no name
+        NoName,
+        /// The deposit cannot be reserved
+        DepositNotEnough,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Config> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+        fn deposit_event() = default;
+
+        /// Register a name for the caller, reserving `NameDeposit`
+        #[weight = T::WeightInfo::register()]
+        pub fn register(origin, name: Vec<u8>) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(!Names::<T>::contains_key(&who), Error::<T>::AlreadyHasName);
+            Self::do_register(&who, &name)?;
+            Ok(())
+        }
+
+        /// Give the caller's name and its deposit to an account without a name
+        #[weight = T::WeightInfo::transfer()]
+        pub fn transfer(origin, to: T::AccountId) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+            let name = Self::name(&who).ok_or(Error::<T>::NoName)?;
+            ensure!(!Names::<T>::contains_key(&to), Error::<T>::AlreadyHasName);
+            T::OnNameTransfer::on_name_transfer(&who, &to, &name)?;
+
+            let mut registration = Self::registration(&name).ok_or(Error::<T>::NoName)?;
+            T::Currency::repatriate_reserved(&who, &to, registration.deposit, BalanceStatus::Reserved)?;
+            registration.owner = to.clone();
+            <Registrations::<T>>::insert(&name, registration);
+            <Names::<T>>::remove(&who);
+            <Names::<T>>::insert(&to, name.clone());
+
+            Self::deposit_event(RawEvent::Transferred(who, to, name));
+            Ok(())
+        }
+
+        /// Give up the caller's name and get the deposit back
+        #[weight = T::WeightInfo::release()]
+        pub fn release(origin) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_release(&who)
+        }
+
+        /// Governance reserves a name nobody owns
+        #[weight = T::WeightInfo::add_reserved()]
+        pub fn add_reserved(origin, name: Vec<u8>) -> dispatch::DispatchResult {
+            T::ReservedOrigin::ensure_origin(origin)?;
+            Self::do_reserve(&name)
+        }
+
+        #[weight = T::WeightInfo::remove_reserved()]
+        pub fn remove_reserved(origin, name: Vec<u8>) -> dispatch::DispatchResult {
+            T::ReservedOrigin::ensure_origin(origin)?;
+            let name = Self::normalize(&name);
+
+            Reserved::remove(&name);
+
+            Self::deposit_event(RawEvent::ReservedRemoved(name));
+            Ok(())
+        }
+    }
+}
+
+impl<T: Config> Module<T> {
+    /// The name lowercased, names are stored and looked up this way
+    pub fn normalize(name: &[u8]) -> Vec<u8> {
+        name.to_ascii_lowercase()
+    }
+
+    /// Check the name against the length and charset rules, returns it normalized
+    pub fn validate(name: &[u8]) -> Result<Vec<u8>, DispatchError> {
+        let name = Self::normalize(name);
+        let len = name.len() as u32;
+        ensure!(len >= T::MinNameLength::get() && len <= T::MaxNameLength::get(), Error::<T>::InvalidLength);
+        ensure!(name.iter().all(|byte| T::Charset::allows(*byte)), Error::<T>::InvalidCharacter);
+        Ok(name)
+    }
+
+    fn do_register(who: &T::AccountId, name: &[u8]) -> Result<Vec<u8>, DispatchError> {
+        let name = Self::validate(name)?;
+        ensure!(!Self::is_reserved(&name), Error::<T>::NameReserved);
+        ensure!(!Registrations::<T>::contains_key(&name), Error::<T>::NameTaken);
+
+        let deposit = T::NameDeposit::get();
+        T::Currency::reserve(who, deposit).map_err(|_| Error::<T>::DepositNotEnough)?;
+        <Registrations::<T>>::insert(&name, Registration { owner: who.clone(), deposit });
+        <Names::<T>>::insert(who, name.clone());
+
+        Self::deposit_event(RawEvent::Registered(who.clone(), name.clone(), deposit));
+        Ok(name)
+    }
+
+    fn do_reserve(name: &[u8]) -> dispatch::DispatchResult {
+        let name = Self::normalize(name);
+        ensure!(!Registrations::<T>::contains_key(&name), Error::<T>::NameTaken);
+
+        Reserved::insert(&name, true);
+
+        Self::deposit_event(RawEvent::ReservedAdded(name));
+        Ok(())
+    }
+
+    fn do_release(who: &T::AccountId) -> dispatch::DispatchResult {
+        let name = Self::name(who).ok_or(Error::<T>::NoName)?;
+        if let Some(registration) = <Registrations::<T>>::take(&name) {
+            T::Currency::unreserve(who, registration.deposit);
+        }
+        <Names::<T>>::remove(who);
+
+        Self::deposit_event(RawEvent::Released(who.clone(), name));
+        Ok(())
+    }
+}
+
+impl<T: Config> UsernameRegistry<T::AccountId> for Module<T> {
+    fn register(who: &T::AccountId, name: &[u8]) -> Result<Vec<u8>, DispatchError> {
+        match Self::name(who) {
+            Some(owned) if owned == Self::normalize(name) => Ok(owned),
+            Some(_) => Err(Error::<T>::AlreadyHasName.into()),
+            None => Self::do_register(who, name),
+        }
+    }
+
+    fn release(who: &T::AccountId) -> dispatch::DispatchResult {
+        Self::do_release(who)
+    }
+
+    fn lookup(name: &[u8]) -> Option<T::AccountId> {
+        Self::registration(Self::normalize(name)).map(|registration| registration.owner)
+    }
+
+    fn name_of(who: &T::AccountId) -> Option<Vec<u8>> {
+        Self::name(who)
+    }
+
+    fn register_existing(who: &T::AccountId, name: &[u8]) -> dispatch::DispatchResult {
+        let name = Self::normalize(name);
+        ensure!(!Names::<T>::contains_key(who), Error::<T>::AlreadyHasName);
+        ensure!(!Registrations::<T>::contains_key(&name), Error::<T>::NameTaken);
+
+        let deposit = Zero::zero();
+        <Registrations::<T>>::insert(&name, Registration { owner: who.clone(), deposit });
+        <Names::<T>>::insert(who, name.clone());
+
+        Self::deposit_event(RawEvent::Registered(who.clone(), name, deposit));
+        Ok(())
+    }
+
+    fn reserve(name: &[u8]) -> dispatch::DispatchResult {
+        Self::do_reserve(name)
+    }
+}
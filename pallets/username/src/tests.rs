@@ -0,0 +1,152 @@
+use crate::{Error, Registration, UsernameRegistry, mock::*};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+fn name(name: &str) -> Vec<u8> {
+    name.as_bytes().to_vec()
+}
+
+#[test]
+fn register() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+
+        // 【When】user 2 registers a name with capitals
+        assert_ok!(Username::register(Origin::signed(user), name("Player1")));
+
+        // 【Then】it is stored lowercased, both ways, and the deposit is reserved
+        assert_eq!(Some(Registration { owner: user, deposit: 1000 }), Username::registration(name("player1")));
+        assert_eq!(Some(name("player1")), Username::name(user));
+        assert_eq!(Some(user), <Username as UsernameRegistry<u64>>::lookup(b"PLAYER1"));
+        assert_eq!(1000, Balances::reserved_balance(&user));
+        assert!(System::events().iter().any(|record| record.event == Event::pallet_username(crate::RawEvent::Registered(user, name("player1"), 1000))));
+
+        // 【Then】one name per account and one account per name
+        assert_noop!(Username::register(Origin::signed(user), name("player2")), Error::<Test>::AlreadyHasName);
+        assert_noop!(Username::register(Origin::signed(3), name("pLayer1")), Error::<Test>::NameTaken);
+
+        // 【Then】the deposit has to be there
+        assert_noop!(Username::register(Origin::signed(4), name("player4")), Error::<Test>::DepositNotEnough);
+    });
+}
+
+#[test]
+fn register_invalid_name() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+
+        // 【Then】names follow the length and charset rules and are not reserved
+        assert_noop!(Username::register(Origin::signed(user), name("ab")), Error::<Test>::InvalidLength);
+        assert_noop!(Username::register(Origin::signed(user), name("abcdefghijklmnopq")), Error::<Test>::InvalidLength);
+        assert_noop!(Username::register(Origin::signed(user), name("player_1")), Error::<Test>::InvalidCharacter);
+        assert_noop!(Username::register(Origin::signed(user), vec![b'a', b'b', 0xff]), Error::<Test>::InvalidCharacter);
+        assert_noop!(Username::register(Origin::signed(user), name("gametop")), Error::<Test>::NameReserved);
+
+        assert_ok!(Username::register(Origin::signed(user), name("abc")));
+    });
+}
+
+#[test]
+fn transfer() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Username::register(Origin::signed(2), name("player2")));
+        assert_ok!(Username::register(Origin::signed(3), name("player3")));
+
+        // 【Then】only to an account without a name
+        assert_noop!(Username::transfer(Origin::signed(2), 3), Error::<Test>::AlreadyHasName);
+        assert_noop!(Username::transfer(Origin::signed(1), 2), Error::<Test>::NoName);
+
+        // 【When】user 2 gives its name to user 1
+        assert_ok!(Username::transfer(Origin::signed(2), 1));
+
+        // 【Then】the name and its deposit moved
+        assert_eq!(Some(1), <Username as UsernameRegistry<u64>>::lookup(b"player2"));
+        assert_eq!(None, Username::name(2));
+        assert_eq!(Some(name("player2")), Username::name(1));
+        assert_eq!(0, Balances::reserved_balance(&2));
+        assert_eq!(1000000 - 1000, Balances::free_balance(&2));
+        assert_eq!(1000, Balances::reserved_balance(&1));
+    });
+}
+
+#[test]
+fn release() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+        assert_noop!(Username::release(Origin::signed(user)), Error::<Test>::NoName);
+        assert_ok!(Username::register(Origin::signed(user), name("player2")));
+
+        // 【When】user 2 releases its name
+        assert_ok!(Username::release(Origin::signed(user)));
+
+        // 【Then】the deposit is back and anybody can take the name
+        assert_eq!(0, Balances::reserved_balance(&user));
+        assert_eq!(1000000, Balances::free_balance(&user));
+        assert_eq!(None, Username::registration(name("player2")));
+        assert_eq!(None, Username::name(user));
+        assert_ok!(Username::register(Origin::signed(3), name("player2")));
+    });
+}
+
+#[test]
+fn reserved_names() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Username::register(Origin::signed(2), name("player2")));
+
+        // 【Then】governance reserves names nobody owns
+        assert_noop!(Username::add_reserved(Origin::signed(1), name("admin")), DispatchError::BadOrigin);
+        assert_noop!(Username::add_reserved(Origin::root(), name("Player2")), Error::<Test>::NameTaken);
+        assert_ok!(Username::add_reserved(Origin::root(), name("Admin")));
+        assert!(Username::is_reserved(name("admin")));
+        assert_noop!(Username::register(Origin::signed(3), name("admin")), Error::<Test>::NameReserved);
+
+        // 【When】it frees a name reserved at genesis
+        assert_ok!(Username::remove_reserved(Origin::root(), name("gametop")));
+
+        // 【Then】it can be registered
+        assert_ok!(Username::register(Origin::signed(3), name("gametop")));
+    });
+}
+
+#[test]
+fn registry_trait() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+
+        // 【Then】registering the name an account owns keeps it, another name fails
+        assert_eq!(Ok(name("player2")), <Username as UsernameRegistry<u64>>::register(&user, b"Player2"));
+        assert_eq!(Ok(name("player2")), <Username as UsernameRegistry<u64>>::register(&user, b"player2"));
+        assert_eq!(1000, Balances::reserved_balance(&user));
+        assert_eq!(Err(Error::<Test>::AlreadyHasName.into()), <Username as UsernameRegistry<u64>>::register(&user, b"player3"));
+
+        assert_eq!(Some(name("player2")), <Username as UsernameRegistry<u64>>::name_of(&user));
+        assert_ok!(<Username as UsernameRegistry<u64>>::release(&user));
+        assert_eq!(None, <Username as UsernameRegistry<u64>>::lookup(b"player2"));
+    });
+}
+
+#[test]
+fn registry_trait_existing_names() {
+    new_test_ext().execute_with(|| {
+        let user = 2;
+
+        // 【When】a name taken before the registry is given to its account
+        // 【Then】it is kept without a deposit or the name rules
+        assert_ok!(<Username as UsernameRegistry<u64>>::register_existing(&user, b"Old_Name"));
+        assert_eq!(Some(Registration { owner: user, deposit: 0 }), Username::registration(name("old_name")));
+        assert_eq!(Some(user), <Username as UsernameRegistry<u64>>::lookup(b"old_name"));
+        assert_eq!(0, Balances::reserved_balance(&user));
+
+        // 【Then】neither the name nor the account is taken twice
+        assert_noop!(<Username as UsernameRegistry<u64>>::register_existing(&3, b"old_name"), Error::<Test>::NameTaken);
+        assert_noop!(<Username as UsernameRegistry<u64>>::register_existing(&user, b"other"), Error::<Test>::AlreadyHasName);
+
+        // 【When】a pallet reserves a name
+        assert_ok!(<Username as UsernameRegistry<u64>>::reserve(b"Root"));
+
+        // 【Then】nobody registers it, and a taken name cannot be reserved
+        assert!(Username::is_reserved(name("root")));
+        assert_noop!(Username::register(Origin::signed(3), name("root")), Error::<Test>::NameReserved);
+        assert_noop!(<Username as UsernameRegistry<u64>>::reserve(b"old_name"), Error::<Test>::NameTaken);
+    });
+}
@@ -11,7 +11,7 @@ use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthority
 use sp_api::impl_runtime_apis;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::traits::{
-    AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, OpaqueKeys, NumberFor, Verify,
+    AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, OpaqueKeys, NumberFor, Verify,
 };
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
@@ -72,6 +72,10 @@ pub use pallet_bridge;
 pub use pallet_stake;
 // swap
 pub use pallet_swap;
+// swap LP farming
+pub use pallet_farm;
+// username registry
+pub use pallet_username;
 // TSP Whitelist
 pub use pallet_tspwhitelist;
 
@@ -144,10 +148,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("subgame"),
     impl_name: create_runtime_str!("subgame"),
     authoring_version: 1,
-    spec_version: 176,
+    spec_version: 177,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
 };
 
 /// This determines the average expected block time that we are targeting.
@@ -852,12 +856,26 @@ ord_parameter_types! {
         hex_literal::hex!("267bae633094eeb37b830d26ff4c6fa1e65ac162ef2e75ded0b8153f01beaa2f")
     );
 }
+parameter_types! {
+    pub const ChipsModuleId: ModuleId = ModuleId(*b"sg/chips");
+    pub const ChipsMaxLedgerEntries: u32 = 500;
+}
 impl pallet_chips::Config for Runtime {
     type Event = Event;
     type Balances = pallet_balances::Module<Runtime>;
     type ChipBalance = u128;
-    type MasterAddress = W3FValidity;
+    type ModuleId = ChipsModuleId;
     type WeightInfo = ();
+    type Assets = SubgameAssets;
+    type RateOrigin = MoreThanHalfCouncil;
+    type MaxLedgerEntries = ChipsMaxLedgerEntries;
+}
+/// Moves the chips collateral from the old `W3FValidity` custodian into the chips treasury
+pub struct ChipsTreasuryMigration;
+impl frame_support::traits::OnRuntimeUpgrade for ChipsTreasuryMigration {
+    fn on_runtime_upgrade() -> Weight {
+        Chips::migrate_from_custodian(&W3FValidity::get())
+    }
 }
 /*** Pallet Chips ***/
 
@@ -892,19 +910,52 @@ impl pallet_gametemplates::Config for Runtime {
 /*** Pallet GameTemplate ***/
 
 /*** Pallet GameCenter ***/
+parameter_types! {
+    pub const GameCenterMaxHistory: u32 = 1000;
+    pub const GameCenterMaxPlayerGames: u32 = 100;
+}
 impl pallet_gamecenter::Config for Runtime {
     type Event = Event;
     type WeightInfo = ();
-    type GuessHash = GameGuessHashModule;
+    type TemplateRegistry = GameTemplates;
+    // Add new games here
+    type Templates = (GameGuessHashModule,);
+    type MaxHistory = GameCenterMaxHistory;
+    type MaxPlayerGames = GameCenterMaxPlayerGames;
 }
 /*** Pallet GameCenter ***/
 
+/*** Pallet Subgame Randomness ***/
+/// The BABE VRF output of the current block. Only the block author knows it before the block is
+/// authored, and the author cannot choose it. A block of a secondary plain slot has no VRF output
+/// and falls back to the epoch randomness.
+pub struct BabeBlockVrf;
+impl Randomness<Hash> for BabeBlockVrf {
+    fn random(subject: &[u8]) -> Hash {
+        let vrf = Babe::author_vrf_randomness().unwrap_or_else(Babe::randomness);
+        <BlakeTwo256 as sp_runtime::traits::Hash>::hash_of(&(vrf, subject))
+    }
+}
+impl pallet_subgame_randomness::Config for Runtime {
+    type Randomness = BabeBlockVrf;
+}
+/*** Pallet Subgame Randomness ***/
+
 /*** Pallet Game1: Guess Hash ***/
+parameter_types! {
+    pub const GuessHashMaxBetsPerGame: u32 = 200;
+    pub const GuessHashTemplateId: u32 = 1;
+    pub GuessHashSettlementWeightLimit: Weight = Perbill::from_percent(10) * RuntimeBlockWeights::get().max_block;
+}
 impl pallet_gametemplates_guess_hash::Config for Runtime {
     type Event = Event;
     type GameIndex = u32;
     type WeightInfo = ();
     type Chips = Chips;
+    type SeedSource = pallet_gametemplates_guess_hash::BabeVrf<SubgameRandomness>;
+    type MaxBetsPerGame = GuessHashMaxBetsPerGame;
+    type SettlementWeightLimit = GuessHashSettlementWeightLimit;
+    type TemplateId = GuessHashTemplateId;
 }
 /*** Pallet Game1: Guess Hash ***/
 
@@ -927,6 +978,25 @@ impl pallet_scheduler::Config for Runtime {
 }
 /***  scheduler ***/
 
+/*** Pallet Username ***/
+parameter_types! {
+    pub const UsernameDeposit: Balance = 1 * DOLLARS;
+    pub const UsernameMinLength: u32 = 3;
+    pub const UsernameMaxLength: u32 = 32;
+}
+impl pallet_username::Config for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+    type NameDeposit = UsernameDeposit;
+    type MinNameLength = UsernameMinLength;
+    type MaxNameLength = UsernameMaxLength;
+    type Charset = pallet_username::LowercaseAlphanumeric;
+    type ReservedOrigin = MoreThanHalfCouncil;
+    type OnNameTransfer = Stake;
+    type WeightInfo = ();
+}
+/*** Pallet Username ***/
+
 /*** Pallet Stake ***/
 ord_parameter_types! {
     pub const StakeOwner: AccountId = AccountId::from(
@@ -937,6 +1007,12 @@ ord_parameter_types! {
         hex_literal::hex!("f03bb9ee7cba9bf90724ac5bd90fcd9553969448dbd4cd3c88b0ee41a062c515")
     );
 }
+parameter_types! {
+    pub const StakeUnbondingPeriod: BlockNumber = 7 * DAYS;
+    pub const StakeMaxUnbondingChunks: u32 = 32;
+    pub const StakeMaxReferralDepth: u32 = 3;
+    pub const StakeMaxDownline: u32 = 500;
+}
 impl pallet_stake::Config for Runtime {
     type Event = Event;
     type Balances = pallet_balances::Module<Runtime>;
@@ -944,6 +1020,11 @@ impl pallet_stake::Config for Runtime {
     type ImportAddress = ImportOwner;
     type WeightInfo = ();
     type Currency = Balances;
+    type UnbondingPeriod = StakeUnbondingPeriod;
+    type MaxUnbondingChunks = StakeMaxUnbondingChunks;
+    type MaxReferralDepth = StakeMaxReferralDepth;
+    type MaxDownline = StakeMaxDownline;
+    type Usernames = Username;
 }
 /*** Pallet Stake ***/
 
@@ -968,6 +1049,11 @@ ord_parameter_types! {
         hex_literal::hex!("50eebb67d5888f999969633cdf644bf552500a18ecd156a972dd19fe7d4f1051")
     );
 }
+parameter_types! {
+    pub const StakeNftMaxExpiriesPerBlock: u32 = 50;
+    pub const StakeNftMaxExpiryBucketsPerBlock: u32 = 10;
+    pub const StakeNftMaxExpiryProbes: u32 = 10;
+}
 impl pallet_stake_nft::Config for Runtime {
     type ProgramId = u64;
     type PalletId = u64;
@@ -976,6 +1062,10 @@ impl pallet_stake_nft::Config for Runtime {
     type Lease = Lease;
     type OwnerAddress = ModuleOwner;
     type Event = Event;
+    type MaxExpiriesPerBlock = StakeNftMaxExpiriesPerBlock;
+    type MaxExpiryBucketsPerBlock = StakeNftMaxExpiryBucketsPerBlock;
+    type MaxExpiryProbes = StakeNftMaxExpiryProbes;
+    type WeightInfo = ();
 }
 
 parameter_types! {
@@ -1024,11 +1114,29 @@ impl pallet_subgame_assets::Config for Runtime {
 }
 
 /*** Pallet Swap ***/
+parameter_types! {
+    pub const SwapMaxPathLength: u32 = 4;
+    pub SwapProtocolFeeTo: AccountId = TreasuryModuleId::get().into_account();
+    pub const SwapMaxObservations: u32 = 1200;
+}
 impl pallet_swap::Config for Runtime {
     type Event = Event;
     type WeightInfo = ();
     type SwapId = u32;
     type Currency = Balances;
+    type MaxPathLength = SwapMaxPathLength;
+    type FeeOrigin = MoreThanHalfCouncil;
+    type PoolOrigin = MoreThanHalfCouncil;
+    type Call = Call;
+    type ProtocolFeeTo = SwapProtocolFeeTo;
+    type MaxObservations = SwapMaxObservations;
+}
+
+/*** Pallet Farm ***/
+impl pallet_farm::Config for Runtime {
+    type Event = Event;
+    type OwnerAddress = ModuleOwner;
+    type WeightInfo = ();
 }
 
 /*** Pallet Manage Card Info ***/
@@ -1047,6 +1155,7 @@ impl pallet_card_factory::Config for Runtime {
     type Lease = Lease;
     type ManageCardInfo = ManageCardInfo;
     type PalletId = PalletIdPalletCardFactory;
+    type Randomness = SubgameRandomness;
     type WeightInfo = ();
 }
 
@@ -1120,6 +1229,7 @@ impl pallet_sonic_racer::Config for Runtime {
     type OwnerAddress = SonicRacerOwner;
     type PackagePoolAddress = PackagePoolAddress;
     type Balances = Balances;
+    type Randomness = SubgameRandomness;
 	type WeightInfo = ();
 }
 
@@ -1193,6 +1303,9 @@ construct_runtime!(
         GameRechargePro: pallet_game_recharge_pro::{Module, Call, Storage, Event<T>},
         SonicRacer: pallet_sonic_racer::{Module, Call, Storage, Event<T>},
         TspWhitelist: pallet_tspwhitelist::{Module, Call, Storage, Event<T>},
+        Farm: pallet_farm::{Module, Call, Storage, Event<T>},
+        Username: pallet_username::{Module, Call, Storage, Event<T>, Config},
+        SubgameRandomness: pallet_subgame_randomness::{Module, Call, Storage},
     }
 );
 
@@ -1227,6 +1340,7 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllModules,
+    ChipsTreasuryMigration,
 >;
 
 pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
@@ -1442,7 +1556,11 @@ impl_runtime_apis! {
             add_benchmark!(params, batches, pallet_timestamp, Timestamp);
             add_benchmark!(params, batches, pallet_stake, Stake);
             add_benchmark!(params, batches, pallet_swap, Swap);
+            add_benchmark!(params, batches, pallet_farm, Farm);
+            add_benchmark!(params, batches, pallet_stake_nft, SubgameStakeNft);
+            add_benchmark!(params, batches, pallet_username, Username);
             add_benchmark!(params, batches, pallet_bridge, Bridge);
+            add_benchmark!(params, batches, pallet_gametemplates_guess_hash, GameGuessHashModule);
 
             if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
             Ok(batches)
@@ -1477,4 +1595,84 @@ impl_runtime_apis! {
         }
     }
     /*** Pallet Contracts ***/
+
+    /*** Pallet Chips ***/
+    impl pallet_chips_runtime_api::ChipsApi<Block, u128> for Runtime {
+        fn solvency() -> pallet_chips::Solvency<u128> {
+            Chips::solvency()
+        }
+    }
+    /*** Pallet Chips ***/
+
+    /*** Pallet Swap ***/
+    impl pallet_swap_runtime_api::SwapApi<Block, AccountId, u32, u32, BlockNumber> for Runtime {
+        fn get_amount_out(swap_id: u32, input_asset: u32, input_amount: u128) -> Option<u128> {
+            Swap::quote_amount_out(swap_id, input_asset, input_amount).ok().map(|(_, amount)| amount)
+        }
+
+        fn get_amount_in(swap_id: u32, output_asset: u32, output_amount: u128) -> Option<u128> {
+            Swap::quote_amount_in(swap_id, output_asset, output_amount).ok().map(|(_, amount)| amount)
+        }
+
+        fn pool_reserves(swap_id: u32) -> Option<pallet_swap::PoolReserves<u32>> {
+            Swap::pool_reserves(swap_id)
+        }
+
+        fn lp_value(swap_id: u32, who: AccountId) -> Option<pallet_swap::LpValue> {
+            Swap::lp_value(swap_id, &who)
+        }
+
+        fn best_path_exact_in(asset_in: u32, asset_out: u32, amount_in: u128) -> Option<(Vec<u32>, u128)> {
+            Swap::best_path_exact_in(asset_in, asset_out, amount_in)
+        }
+
+        fn best_path_exact_out(asset_in: u32, asset_out: u32, amount_out: u128) -> Option<(Vec<u32>, u128)> {
+            Swap::best_path_exact_out(asset_in, asset_out, amount_out)
+        }
+
+        fn twap(base: u32, quote: u32, window: BlockNumber) -> Option<sp_runtime::FixedU128> {
+            <Swap as pallet_swap::Oracle<u32, BlockNumber>>::twap(base, quote, window)
+        }
+    }
+    /*** Pallet Swap ***/
+
+    /*** Pallet Stake ***/
+    impl pallet_stake_runtime_api::StakeApi<Block, AccountId, Balance> for Runtime {
+        fn pending_reward(who: AccountId) -> Balance {
+            Stake::pending_reward(&who)
+        }
+
+        fn downline(who: AccountId) -> Vec<Vec<AccountId>> {
+            Stake::downline_of(&who)
+        }
+
+        fn referral_earnings(who: AccountId) -> Balance {
+            Stake::referral_earnings(&who)
+        }
+    }
+    /*** Pallet Stake ***/
+
+    /*** Pallet GameCenter ***/
+    impl pallet_gamecenter_runtime_api::GameCenterApi<Block, AccountId, BlockNumber> for Runtime {
+        fn open_games(
+            template_id: pallet_gamecenter::TemplateId,
+        ) -> Vec<pallet_gamecenter::GameInstanceOf<Runtime>> {
+            GameCenter::open_games(template_id)
+        }
+
+        fn player_games(
+            who: AccountId,
+        ) -> Vec<(pallet_gamecenter::TemplateId, pallet_gamecenter::GameInstanceOf<Runtime>)> {
+            GameCenter::player_games(who)
+        }
+
+        fn history(
+            template_id: pallet_gamecenter::TemplateId,
+            page: u32,
+            page_size: u32,
+        ) -> Vec<pallet_gamecenter::GameInstanceOf<Runtime>> {
+            GameCenter::history_page(template_id, page, page_size)
+        }
+    }
+    /*** Pallet GameCenter ***/
 }